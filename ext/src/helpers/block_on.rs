@@ -0,0 +1,164 @@
+use std::{
+    cell::Cell,
+    future::Future,
+    pin::{pin, Pin},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::Duration,
+};
+
+use super::nogvl;
+use magnus::{prelude::*, Error, RClass, Ruby, Value};
+
+/// The longest [`block_on`] stays parked without checking for interrupts.
+const PARK_SLICE: Duration = Duration::from_millis(50);
+
+/// A unit of work suspended on Wasmtime's fiber, waiting for [`block_on`] to
+/// run it on Ruby's stack.
+trait HostTask {
+    fn run(&mut self);
+}
+
+thread_local! {
+    static PENDING_TASK: Cell<Option<*mut dyn HostTask>> = const { Cell::new(None) };
+}
+
+/// Drives a Wasmtime future to completion on the current Ruby thread.
+///
+/// Async Wasm runs on a separate fiber stack, which Ruby can't run on: its GC
+/// scans the machine stack of the current thread. Ruby callbacks are thus
+/// wrapped in [`on_host_stack`], which suspends the Wasm fiber and hands the
+/// callback over to this loop. When the future yields and is ready to make
+/// progress again (e.g. an epoch or fuel yield), control is given to the
+/// current `Fiber.scheduler`, or to other threads when there is no scheduler.
+/// When it waits on something else (e.g. WASI IO), the thread parks without
+/// the GVL until woken, so other fibers of the thread don't run meanwhile.
+pub fn block_on<F: Future>(ruby: &Ruby, future: F) -> Result<F::Output, Error> {
+    // Tokio-based futures, e.g. from WASI, register with the embedded
+    // runtime: its worker thread drives their IO and timers, and wakes this
+    // loop up.
+    #[cfg(feature = "tokio")]
+    let _runtime = crate::Engine::tokio_runtime().enter();
+    let mut future = pin!(future);
+    let waker = Arc::new(ThreadWaker {
+        thread: thread::current(),
+        woken: AtomicBool::new(false),
+    });
+    let cx_waker = Waker::from(waker.clone());
+    let mut cx = Context::from_waker(&cx_waker);
+
+    loop {
+        waker.woken.store(false, Ordering::Release);
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return Ok(output);
+        }
+
+        match PENDING_TASK.with(|task| task.take()) {
+            // SAFETY: the task lives in the future we're polling, which stays
+            // pinned until it completes, and is cleared from `PENDING_TASK`
+            // when dropped.
+            Some(task) => unsafe { (*task).run() },
+            None if waker.woken.load(Ordering::Acquire) => yield_to_scheduler(ruby)?,
+            None => park(ruby, &waker)?,
+        }
+    }
+}
+
+/// Unparks the thread running [`block_on`].
+struct ThreadWaker {
+    thread: Thread,
+    woken: AtomicBool,
+}
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+        self.thread.unpark();
+    }
+}
+
+/// Parks the thread without the GVL until `waker` is woken, handling Ruby
+/// interrupts every [`PARK_SLICE`].
+fn park(ruby: &Ruby, waker: &ThreadWaker) -> Result<(), Error> {
+    while !waker.woken.load(Ordering::Acquire) {
+        nogvl(|| thread::park_timeout(PARK_SLICE));
+        ruby.thread_check_ints()?;
+    }
+    Ok(())
+}
+
+/// Returns a future that runs `func` on Ruby's stack, see [`block_on`].
+pub fn on_host_stack<F, R>(func: F) -> impl Future<Output = R>
+where
+    F: FnOnce() -> R,
+{
+    OnHostStack {
+        func: Some(func),
+        output: None,
+    }
+}
+
+struct OnHostStack<F, R> {
+    func: Option<F>,
+    output: Option<R>,
+}
+
+impl<F: FnOnce() -> R, R> HostTask for OnHostStack<F, R> {
+    fn run(&mut self) {
+        if let Some(func) = self.func.take() {
+            self.output = Some(func());
+        }
+    }
+}
+
+impl<F: FnOnce() -> R, R> Future for OnHostStack<F, R> {
+    type Output = R;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<R> {
+        // SAFETY: nothing is moved out of `self` besides `output`, which is
+        // never pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        if let Some(output) = this.output.take() {
+            return Poll::Ready(output);
+        }
+
+        let task: *mut (dyn HostTask + '_) = this;
+        // SAFETY: only erases the lifetime, see `Drop`.
+        let task: *mut (dyn HostTask + 'static) = unsafe { std::mem::transmute(task) };
+        PENDING_TASK.with(|pending| pending.set(Some(task)));
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+impl<F, R> Drop for OnHostStack<F, R> {
+    fn drop(&mut self) {
+        let this = self as *mut Self as *mut ();
+        PENDING_TASK.with(|pending| {
+            if pending.get().is_some_and(|task| task as *mut () == this) {
+                pending.set(None);
+            }
+        });
+    }
+}
+
+fn yield_to_scheduler(ruby: &Ruby) -> Result<(), Error> {
+    let fiber: RClass = ruby.class_object().const_get("Fiber")?;
+    let scheduler: Value = fiber.funcall("scheduler", ())?;
+
+    if scheduler.is_nil() {
+        ruby.class_thread().funcall::<_, _, Value>("pass", ())?;
+    } else {
+        ruby.module_kernel().funcall::<_, _, Value>("sleep", (0,))?;
+    }
+
+    Ok(())
+}
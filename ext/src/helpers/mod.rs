@@ -1,3 +1,4 @@
+mod block_on;
 mod macros;
 mod nogvl;
 mod output_limited_buffer;
@@ -5,6 +6,7 @@ mod static_id;
 mod symbol_enum;
 mod tmplock;
 
+pub use block_on::{block_on, on_host_stack};
 pub use nogvl::{nogvl, with_gvl};
 pub use output_limited_buffer::OutputLimitedBuffer;
 pub use static_id::StaticId;
@@ -9,6 +9,8 @@ use tokio::io::AsyncWrite;
 use wasmtime_wasi::cli::{IsTerminal, StdoutStream};
 use wasmtime_wasi::p2::{OutputStream, Pollable, StreamError, StreamResult};
 
+use super::with_gvl;
+
 /// A buffer that limits the number of bytes that can be written to it.
 /// If the buffer is full, it will truncate the data.
 /// Is used in the buffer implementations of stdout and stderr in `WasiP1Ctx` and `WasiCtxBuilder`.
@@ -85,7 +87,8 @@ impl Pollable for OutputLimitedBuffer {
 impl OutputStream for OutputLimitedBuffer {
     fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
         let mut stream = self.inner.lock().expect("Should be only writer");
-        stream.write(&bytes)
+        // Writes may come from Wasm running without the GVL.
+        with_gvl(|| stream.write(&bytes))
     }
 
     fn flush(&mut self) -> StreamResult<()> {
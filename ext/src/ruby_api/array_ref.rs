@@ -0,0 +1,239 @@
+use super::{
+    convert::{field_type_to_value, to_field_type, ToRubyValue, ToWasmVal},
+    root,
+    store::{Store, StoreContextValue},
+};
+use crate::{err, error, Engine};
+use magnus::{
+    function, gc::Marker, method, prelude::*, typed_data::Obj, DataTypeFunctions, Error, Object,
+    RArray, Ruby, TypedData, Value,
+};
+use wasmtime::{
+    ArrayRef as ArrayRefImpl, ArrayRefPre, ArrayType as ArrayTypeImpl, FieldType, OwnedRooted,
+    Rooted, ValType,
+};
+
+/// @yard
+/// @rename Wasmtime::ArrayType
+/// Represents a WebAssembly GC array type.
+///
+/// The element type is described like a {StructType} field: a type Symbol,
+/// which may also be +:i8+ or +:i16+ for packed elements, or +[:mut, type]+
+/// for mutable elements.
+/// @see https://docs.rs/wasmtime/latest/wasmtime/struct.ArrayType.html Wasmtime's Rust doc
+#[derive(TypedData)]
+#[magnus(
+    class = "Wasmtime::ArrayType",
+    size,
+    mark,
+    free_immediately,
+    unsafe_generics
+)]
+pub struct ArrayType {
+    inner: ArrayTypeImpl,
+}
+
+impl DataTypeFunctions for ArrayType {}
+
+impl ArrayType {
+    /// @yard
+    /// @def new(engine, element)
+    /// @param engine [Engine] Requires +wasm_gc: true+.
+    /// @param element [Symbol, RefType, Array] The elements' type,
+    ///   e.g. +[:mut, :i8]+.
+    /// @return [ArrayType]
+    pub fn new(engine: &Engine, element: Value) -> Result<Self, Error> {
+        let engine = engine.get();
+        let inner = ArrayTypeImpl::new(engine, to_field_type(element, engine)?);
+
+        Ok(Self { inner })
+    }
+
+    pub fn from_inner(inner: ArrayTypeImpl) -> Self {
+        Self { inner }
+    }
+
+    /// @yard
+    /// @return [Symbol, RefType, Array] The elements' type.
+    pub fn element(ruby: &Ruby, rb_self: &Self) -> Result<Value, Error> {
+        field_type_to_value(ruby, &rb_self.inner.field_type())
+    }
+
+    pub fn get(&self) -> &ArrayTypeImpl {
+        &self.inner
+    }
+}
+
+/// @yard
+/// @rename Wasmtime::ArrayRef
+/// Represents a reference to a WebAssembly GC array. Arrays are returned by
+/// Wasm functions for +arrayref+ (and +anyref+ or +eqref+) results, and can
+/// be allocated from Ruby with {.new}.
+///
+/// Packed +:i8+ and +:i16+ elements are read as zero-extended Integers.
+/// @see https://docs.rs/wasmtime/latest/wasmtime/struct.ArrayRef.html Wasmtime's Rust doc
+#[derive(TypedData)]
+#[magnus(
+    class = "Wasmtime::ArrayRef",
+    size,
+    mark,
+    free_immediately,
+    unsafe_generics
+)]
+pub struct ArrayRef<'a> {
+    store: StoreContextValue<'a>,
+    inner: OwnedRooted<ArrayRefImpl>,
+}
+
+impl DataTypeFunctions for ArrayRef<'_> {
+    fn mark(&self, marker: &Marker) {
+        self.store.mark(marker)
+    }
+}
+
+impl<'a> ArrayRef<'a> {
+    /// @yard
+    /// Allocates an array holding +elements+ in +store+'s GC heap. Raises
+    /// for {Store}s with async support.
+    ///
+    /// @def new(store, type, elements)
+    /// @param store [Store]
+    /// @param type [ArrayType]
+    /// @param elements [Array<Object>]
+    /// @return [ArrayRef]
+    pub fn new(store: Obj<Store>, ty: &ArrayType, elements: RArray) -> Result<Self, Error> {
+        let store_value: StoreContextValue = store.into();
+        let field = ty.inner.field_type();
+        let values = elements.to_vec::<Value>()?;
+        let vals = values
+            .iter()
+            .map(|value| value.to_wasm_val(&store_value, field.element_type().unpack().clone()))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut context = store.context_mut();
+        if context.data().is_async() {
+            return err!("cannot allocate an array in a store with async support");
+        }
+        let pre = ArrayRefPre::new(&mut context, ty.inner.clone());
+        let inner = ArrayRefImpl::new_fixed(&mut context, &pre, &vals)
+            .and_then(|array_ref| array_ref.to_owned_rooted(&mut context))
+            .map_err(|e| error!("{}", e))?;
+
+        let array_ref = Self {
+            store: store_value,
+            inner,
+        };
+        for value in values {
+            array_ref.retain_non_nil_extern_ref(&field, value)?;
+        }
+
+        Ok(array_ref)
+    }
+
+    pub fn from_inner(store: StoreContextValue<'a>, inner: OwnedRooted<ArrayRefImpl>) -> Self {
+        Self { store, inner }
+    }
+
+    /// @yard
+    /// @def type
+    /// @return [ArrayType]
+    pub fn type_(ruby: &Ruby, rb_self: &Self) -> Result<Obj<ArrayType>, Error> {
+        let ty = rb_self
+            .inner
+            .ty(rb_self.store.context()?)
+            .map_err(|e| error!("{}", e))?;
+        Ok(ruby.obj_wrap(ArrayType::from_inner(ty)))
+    }
+
+    /// @yard
+    /// Returns the element at +index+.
+    ///
+    /// @def get(index)
+    /// @param index [Integer]
+    /// @return [Object]
+    pub fn get(ruby: &Ruby, rb_self: &Self, index: u32) -> Result<Value, Error> {
+        rb_self
+            .inner
+            .get(rb_self.store.context_mut()?, index)
+            .map_err(|e| error!("{}", e))?
+            .to_ruby_value(ruby, &rb_self.store)
+    }
+
+    /// @yard
+    /// Sets the element at +index+ to +value+. The array's element type must
+    /// be mutable.
+    ///
+    /// @def set(index, value)
+    /// @param index [Integer]
+    /// @param value [Object]
+    /// @return [void]
+    pub fn set(&self, index: u32, value: Value) -> Result<(), Error> {
+        let field = self.field_type()?;
+        let val = value.to_wasm_val(&self.store, field.element_type().unpack().clone())?;
+        self.inner
+            .set(self.store.context_mut()?, index, val)
+            .map_err(|e| error!("{}", e))?;
+        self.retain_non_nil_extern_ref(&field, value)
+    }
+
+    /// @yard
+    /// @return [Integer] The number of elements.
+    pub fn size(&self) -> Result<u32, Error> {
+        self.inner
+            .len(self.store.context()?)
+            .map_err(|e| error!("{}", e))
+    }
+
+    /// @yard
+    /// @return [Array<Object>] All the elements.
+    pub fn to_a(ruby: &Ruby, rb_self: &Self) -> Result<RArray, Error> {
+        let size = rb_self.size()?;
+        let array = ruby.ary_new_capa(size as usize);
+        for index in 0..size {
+            array.push(Self::get(ruby, rb_self, index)?)?;
+        }
+        Ok(array)
+    }
+
+    /// Roots the array in `store`, which must be the store it was allocated
+    /// in, to pass it to Wasm.
+    pub fn to_rooted(&self, store: &StoreContextValue) -> Result<Rooted<ArrayRefImpl>, Error> {
+        if !self.store.is_same_store(store)? {
+            return err!("array belongs to a different store");
+        }
+        Ok(self.inner.to_rooted(store.context_mut()?))
+    }
+
+    fn field_type(&self) -> Result<FieldType, Error> {
+        self.inner
+            .ty(self.store.context()?)
+            .map(|ty| ty.field_type())
+            .map_err(|e| error!("{}", e))
+    }
+
+    fn retain_non_nil_extern_ref(&self, field: &FieldType, value: Value) -> Result<(), Error> {
+        if !value.is_nil() && field.element_type().unpack().matches(&ValType::EXTERNREF) {
+            self.store.retain(value)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn init(ruby: &Ruby) -> Result<(), Error> {
+    let type_class = root().define_class("ArrayType", ruby.class_object())?;
+    type_class.define_singleton_method("new", function!(ArrayType::new, 2))?;
+    type_class.define_method("element", method!(ArrayType::element, 0))?;
+
+    let class = root().define_class("ArrayRef", ruby.class_object())?;
+    class.define_singleton_method("new", function!(ArrayRef::new, 3))?;
+    class.define_method("type", method!(ArrayRef::type_, 0))?;
+    class.define_method("get", method!(ArrayRef::get, 1))?;
+    class.define_method("[]", method!(ArrayRef::get, 1))?;
+    class.define_method("set", method!(ArrayRef::set, 2))?;
+    class.define_method("[]=", method!(ArrayRef::set, 2))?;
+    class.define_method("size", method!(ArrayRef::size, 0))?;
+    class.define_method("length", method!(ArrayRef::size, 0))?;
+    class.define_method("to_a", method!(ArrayRef::to_a, 0))?;
+
+    Ok(())
+}
@@ -1,7 +1,16 @@
-use super::{convert::WrapWasmtimeType, externals::Extern, root, store::StoreData};
+use super::{
+    convert::WrapWasmtimeType,
+    externals::Extern,
+    memory::IoBuffers,
+    root,
+    store::{self, StoreData},
+};
 use crate::error;
-use magnus::{class, method, typed_data::Obj, Error, Module as _, RString, Ruby, Value};
-use std::cell::UnsafeCell;
+use magnus::{
+    class, gc::Marker, method, typed_data::Obj, DataTypeFunctions, Error, Module as _, RString,
+    Ruby, Value,
+};
+use std::cell::{RefCell, UnsafeCell};
 use wasmtime::{AsContext, AsContextMut, Caller as CallerImpl, StoreContext, StoreContextMut};
 
 /// A handle to a [`wasmtime::Caller`] that's only valid during a Func execution.
@@ -47,15 +56,23 @@ impl<'a> CallerHandle<'a> {
 /// block argument in {Func.new}).
 /// @see https://docs.rs/wasmtime/latest/wasmtime/struct.Caller.html Wasmtime's Rust doc
 #[derive(Debug)]
-#[magnus::wrap(class = "Wasmtime::Caller", free_immediately, unsafe_generics)]
+#[magnus::wrap(class = "Wasmtime::Caller", free_immediately, mark, unsafe_generics)]
 pub struct Caller<'a> {
     handle: CallerHandle<'a>,
+    io_buffers: RefCell<IoBuffers>,
+}
+
+impl DataTypeFunctions for Caller<'_> {
+    fn mark(&self, marker: &Marker) {
+        self.io_buffers.borrow().mark(marker);
+    }
 }
 
 impl<'a> Caller<'a> {
     pub fn new(caller: CallerImpl<'a, StoreData>) -> Self {
         Self {
             handle: CallerHandle::new(caller),
+            io_buffers: Default::default(),
         }
     }
 
@@ -99,11 +116,21 @@ impl<'a> Caller<'a> {
     pub fn set_fuel(&self, fuel: u64) -> Result<(), Error> {
         self.handle
             .get_mut()
-            .and_then(|c| c.set_fuel(fuel).map_err(|e| error!("{}", e)))?;
+            .and_then(|c| store::set_fuel(c, fuel).map_err(|e| error!("{}", e)))?;
 
         Ok(())
     }
 
+    /// @yard
+    /// (see Store#fuel_consumed)
+    /// @def fuel_consumed
+    pub fn fuel_consumed(&self) -> Result<u64, Error> {
+        self.handle
+            .get()
+            .map(store::fuel_consumed)?
+            .map_err(|e| error!("{}", e))
+    }
+
     pub fn context(&self) -> Result<StoreContext<'_, StoreData>, Error> {
         self.handle.get().map(|c| c.as_context())
     }
@@ -112,8 +139,20 @@ impl<'a> Caller<'a> {
         self.handle.get_mut().map(|c| c.as_context_mut())
     }
 
-    pub fn expire(&self) {
+    /// Tracks an +IO::Buffer+ mapping memory through this caller, so that
+    /// it's released when the caller expires.
+    pub fn track_io_buffer(&self, buffer: Value) -> Result<(), Error> {
+        self.io_buffers.borrow_mut().track(buffer)
+    }
+
+    /// Ends the host call. Nothing keeps the store alive past it, so buffers
+    /// mapped through the caller are released; the error of a buffer that is
+    /// still locked is returned to fail the call. The store keeps tracking
+    /// such a buffer, so that its memories can't grow until it's freed.
+    pub fn expire(&self) -> Result<(), Error> {
+        let released = self.io_buffers.borrow_mut().release();
         self.handle.expire();
+        released
     }
 }
 
@@ -125,6 +164,7 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     klass.define_method("export", method!(Caller::export, 1))?;
     klass.define_method("get_fuel", method!(Caller::get_fuel, 0))?;
     klass.define_method("set_fuel", method!(Caller::set_fuel, 1))?;
+    klass.define_method("fuel_consumed", method!(Caller::fuel_consumed, 0))?;
 
     Ok(())
 }
@@ -16,6 +16,7 @@ use magnus::{
 };
 use rb_sys::tracking_allocator::ManuallyTracked;
 use wasmtime::component::Component as ComponentImpl;
+use wasmtime_environ::wasmparser::{Parser, Validator};
 
 pub use func::Func;
 pub use instance::Instance;
@@ -31,11 +32,16 @@ pub fn component_namespace(ruby: &Ruby) -> RModule {
 use crate::{
     error,
     helpers::{nogvl, Tmplock},
+    ruby_api::errors::validation_err,
     Engine,
 };
 /// @yard
 /// @rename Wasmtime::Component::Component
 /// Represents a WebAssembly component.
+///
+/// Components are frozen when created and can be shared with other Ractors,
+/// e.g. by passing them to +Ractor.new+. A component can be instantiated
+/// concurrently from any thread or Ractor, each with its own {Store}.
 /// @see https://docs.rs/wasmtime/latest/wasmtime/component/struct.Component.html Wasmtime's Rust doc
 #[magnus::wrap(
     class = "Wasmtime::Component::Component",
@@ -58,13 +64,13 @@ impl Component {
     /// @param engine [Wasmtime::Engine]
     /// @param wat_or_wasm [String] The String of WAT or Wasm.
     /// @return [Wasmtime::Component::Component]
-    pub fn new(engine: &Engine, wat_or_wasm: RString) -> Result<Self, Error> {
+    pub fn new(ruby: &Ruby, engine: &Engine, wat_or_wasm: RString) -> Result<Obj<Self>, Error> {
         let eng = engine.get();
         let (locked_slice, _locked_slice_guard) = wat_or_wasm.as_locked_slice()?;
         let component = nogvl(|| ComponentImpl::new(eng, locked_slice))
             .map_err(|e| error!("Could not build component: {}", e))?;
 
-        Ok(component.into())
+        Ok(Self::wrap_frozen(ruby, component))
     }
 
     /// @yard
@@ -72,14 +78,45 @@ impl Component {
     /// @param engine [Wasmtime::Engine]
     /// @param path [String]
     /// @return [Wasmtime::Component::Component]
-    pub fn from_file(engine: &Engine, path: RString) -> Result<Self, Error> {
+    pub fn from_file(ruby: &Ruby, engine: &Engine, path: RString) -> Result<Obj<Self>, Error> {
         let eng = engine.get();
         let (path, _locked_str_guard) = path.as_locked_str()?;
         // SAFETY: this string is immediately copied and never moved off the stack
         let component = nogvl(|| ComponentImpl::from_file(eng, path))
             .map_err(|e| error!("Could not build component from file: {}", e))?;
 
-        Ok(component.into())
+        Ok(Self::wrap_frozen(ruby, component))
+    }
+
+    /// @yard
+    /// Validates a Wasm component binary without compiling it. This is
+    /// considerably cheaper than {.new}.
+    ///
+    /// Validation uses the same Wasm features as {.new} on +engine+, e.g.
+    /// +wasm_threads+ or +wasm_gc+, minus those its compiler doesn't
+    /// support, such as tail calls with the +winch+ strategy.
+    ///
+    /// @def validate(engine, wasm)
+    /// @param engine [Wasmtime::Engine]
+    /// @param wasm [String] The binary String of a Wasm component. The text format is not accepted.
+    /// @return [nil]
+    /// @raise [Wasmtime::ValidationError] if +wasm+ is not a valid component.
+    pub fn validate(engine: &Engine, wasm: RString) -> Result<(), Error> {
+        let features = engine.features();
+        let (locked_slice, _locked_slice_guard) = wasm.as_locked_slice()?;
+
+        if !Parser::is_component(locked_slice) {
+            return Err(validation_err(wasmtime::Error::msg(
+                "module passed to component validation",
+            )));
+        }
+
+        nogvl(|| {
+            Validator::new_with_features(features)
+                .validate_all(locked_slice)
+                .map(|_| ())
+        })
+        .map_err(|e| validation_err(e.into()))
     }
 
     /// @yard
@@ -93,10 +130,14 @@ impl Component {
     /// @param engine [Wasmtime::Engine]
     /// @param compiled [String] String obtained with either {Wasmtime::Engine#precompile_component} or {#serialize}.
     /// @return [Wasmtime::Component::Component]
-    pub fn deserialize(engine: &Engine, compiled: RString) -> Result<Self, Error> {
+    pub fn deserialize(
+        ruby: &Ruby,
+        engine: &Engine,
+        compiled: RString,
+    ) -> Result<Obj<Self>, Error> {
         // SAFETY: this string is immediately copied and never moved off the stack
         unsafe { ComponentImpl::deserialize(engine.get(), compiled.as_slice()) }
-            .map(Into::into)
+            .map(|component| Self::wrap_frozen(ruby, component))
             .map_err(|e| error!("Could not deserialize component: {}", e))
     }
 
@@ -108,9 +149,13 @@ impl Component {
     /// @param path [String]
     /// @return [Wasmtime::Component::Component]
     /// @see .deserialize
-    pub fn deserialize_file(engine: &Engine, path: RString) -> Result<Self, Error> {
+    pub fn deserialize_file(
+        ruby: &Ruby,
+        engine: &Engine,
+        path: RString,
+    ) -> Result<Obj<Self>, Error> {
         unsafe { ComponentImpl::deserialize_file(engine.get(), path.as_str()?) }
-            .map(Into::into)
+            .map(|component| Self::wrap_frozen(ruby, component))
             .map_err(|e| error!("Could not deserialize component from file: {}", e))
     }
 
@@ -129,6 +174,13 @@ impl Component {
     pub fn get(&self) -> &ComponentImpl {
         &self.inner
     }
+
+    /// Wraps `inner` in a frozen, and thus Ractor-shareable, Ruby object.
+    fn wrap_frozen(ruby: &Ruby, inner: ComponentImpl) -> Obj<Self> {
+        let component = ruby.obj_wrap(Self::from(inner));
+        component.freeze();
+        component
+    }
 }
 
 impl From<ComponentImpl> for Component {
@@ -159,6 +211,7 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     let class = namespace.define_class("Component", ruby.class_object())?;
     class.define_singleton_method("new", function!(Component::new, 2))?;
     class.define_singleton_method("from_file", function!(Component::from_file, 2))?;
+    class.define_singleton_method("validate", function!(Component::validate, 2))?;
     class.define_singleton_method("deserialize", function!(Component::deserialize, 2))?;
     class.define_singleton_method(
         "deserialize_file",
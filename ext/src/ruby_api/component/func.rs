@@ -1,3 +1,4 @@
+use crate::helpers::nogvl;
 use crate::ruby_api::{
     component::{
         convert::{component_val_to_rb, rb_to_component_val},
@@ -60,6 +61,7 @@ pub struct Func {
     store: Obj<Store>,
     instance: Obj<Instance>,
     inner: FuncImpl,
+    gvl: bool,
 }
 unsafe impl Send for Func {}
 
@@ -77,9 +79,10 @@ impl Func {
     /// @param args [Array<Object>] the function's arguments as per its Wasm definition
     /// @return [Object] the function's return value as per its Wasm definition
     /// @see Func Func class-level documentation for type conversion logic
+    /// @see Instance#get_func Instance#get_func for releasing the GVL during calls
     pub fn call(&self, args: &[Value]) -> Result<Value, Error> {
         let ruby = Ruby::get().unwrap();
-        Func::invoke(&ruby, self.store, &self.inner, args)
+        Func::invoke(&ruby, self.store, &self.inner, self.gvl, args)
     }
 
     pub fn from_inner(inner: FuncImpl, instance: Obj<Instance>, store: Obj<Store>) -> Self {
@@ -87,13 +90,19 @@ impl Func {
             store,
             instance,
             inner,
+            gvl: true,
         }
     }
 
+    pub fn without_gvl(self) -> Self {
+        Self { gvl: false, ..self }
+    }
+
     pub fn invoke(
         ruby: &Ruby,
         store: Obj<Store>,
         func: &FuncImpl,
+        gvl: bool,
         args: &[Value],
     ) -> Result<Value, Error> {
         let store_context_value = StoreContextValue::from(store);
@@ -102,8 +111,13 @@ impl Func {
         let mut results = vec![wasmtime::component::Val::Bool(false); results_ty.len()];
         let params = convert_params(ruby, &store_context_value, func_ty.params(), args)?;
 
-        func.call(store.context_mut(), &params, &mut results)
-            .map_err(|e| store_context_value.handle_wasm_error(ruby, e))?;
+        let context = store.context_mut();
+        if gvl {
+            func.call(context, &params, &mut results)
+        } else {
+            nogvl(|| func.call(context, &params, &mut results))
+        }
+        .map_err(|e| store_context_value.handle_wasm_error(ruby, e))?;
 
         // Check for any errors stored during execution (e.g., from socket checks)
         if let Some(error) = store_context_value.take_last_error()? {
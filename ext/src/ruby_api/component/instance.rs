@@ -1,7 +1,7 @@
 use crate::ruby_api::{component::Func, Store};
 use std::{borrow::BorrowMut, cell::RefCell};
 
-use crate::error;
+use crate::{define_rb_intern, error};
 use magnus::{
     class,
     error::ErrorType,
@@ -18,6 +18,10 @@ use magnus::{
 use magnus::{IntoValue, RModule};
 use wasmtime::component::{ComponentExportIndex, Instance as InstanceImpl, Type, Val};
 
+define_rb_intern!(
+    GVL => "gvl",
+);
+
 /// @yard
 /// Represents a WebAssembly component instance.
 /// @see https://docs.rs/wasmtime/latest/wasmtime/component/struct.Instance.html Wasmtime's Rust doc
@@ -44,8 +48,11 @@ impl Instance {
     /// @yard
     /// Retrieves a Wasm function from the component instance.
     ///
-    /// @def get_func(handle)
+    /// @def get_func(handle, gvl: true)
     /// @param handle [String, Array<String>] The path of the function to retrieve
+    /// @param gvl [Boolean] When +false+, releases the GVL during calls so other Ruby threads run in parallel (each thread must use its own {Store}). Host functions re-acquire the GVL. Defaults to +true+.
+    ///
+    /// Failing to respect the {Store}-per-thread requirement, when using `gvl: false` is highly unsafe and will result in undefined behavior.
     /// @return [Func, nil] The function if it exists, nil otherwise
     ///
     /// @example Retrieve a top-level +add+ export:
@@ -53,11 +60,22 @@ impl Instance {
     ///
     /// @example Retrieve an +add+ export nested under an +adder+ instance top-level export:
     ///   instance.get_func(["adder", "add"])
-    pub fn get_func(rb_self: Obj<Self>, handle: Value) -> Result<Option<Func>, Error> {
+    ///
+    /// @example Retrieve an +add+ export that runs without holding the GVL:
+    ///   instance.get_func("add", gvl: false)
+    pub fn get_func(rb_self: Obj<Self>, args: &[Value]) -> Result<Option<Func>, Error> {
+        let args = scan_args::scan_args::<(Value,), (), (), (), _, ()>(args)?;
+        let kw = scan_args::get_kwargs::<_, (), (Option<bool>,), ()>(args.keywords, &[], &[*GVL])?;
+        let (handle,) = args.required;
+
         let func = rb_self
             .export_index(handle)?
             .and_then(|index| rb_self.inner.get_func(rb_self.store.context_mut(), index))
-            .map(|inner| Func::from_inner(inner, rb_self, rb_self.store));
+            .map(|inner| Func::from_inner(inner, rb_self, rb_self.store))
+            .map(|func| match kw.optional.0 {
+                Some(false) => func.without_gvl(),
+                _ => func,
+            });
 
         Ok(func)
     }
@@ -101,7 +119,7 @@ impl Instance {
 
 pub fn init(ruby: &Ruby, namespace: &RModule) -> Result<(), Error> {
     let instance = namespace.define_class("Instance", ruby.class_object())?;
-    instance.define_method("get_func", method!(Instance::get_func, 1))?;
+    instance.define_method("get_func", method!(Instance::get_func, -1))?;
 
     Ok(())
 }
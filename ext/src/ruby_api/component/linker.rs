@@ -1,7 +1,8 @@
 use super::convert;
 use super::{Component, Instance};
 use crate::{
-    err,
+    define_rb_intern, err,
+    helpers::{nogvl, with_gvl},
     ruby_api::{
         errors::{self, ExceptionMessage},
         store::{StoreContextValue, StoreData},
@@ -20,7 +21,7 @@ use magnus::{
     gc::Marker,
     method,
     r_string::RString,
-    scan_args::scan_args,
+    scan_args::{get_kwargs, scan_args},
     typed_data::Obj,
     value::{Opaque, ReprValue},
     DataTypeFunctions, Error, Module as _, Object, RArray, RModule, Ruby, TryConvert, TypedData,
@@ -29,6 +30,10 @@ use magnus::{
 use wasmtime::component::{Linker as LinkerImpl, LinkerInstance as LinkerInstanceImpl, Val};
 use wasmtime_wasi::{ResourceTable, WasiCtx};
 
+define_rb_intern!(
+    GVL => "gvl",
+);
+
 /// @yard
 /// @rename Wasmtime::Component::Linker
 /// @see https://docs.rs/wasmtime/latest/wasmtime/component/struct.Linker.html Wasmtime's Rust doc
@@ -127,33 +132,40 @@ impl Linker {
 
     /// @yard
     /// Instantiates a {Component} in a {Store} using the defined imports in the linker.
-    /// @def instantiate(store, component)
+    /// @def instantiate(store, component, gvl: true)
     /// @param store [Store]
     /// @param component [Component]
+    /// @param gvl [Boolean] When +false+, releases the GVL during instantiation (e.g. while running
+    ///   start functions). Host functions re-acquire the GVL. Defaults to +true+.
     /// @return [Instance]
-    fn instantiate(
-        _ruby: &Ruby,
-        rb_self: Obj<Self>,
-        store: Obj<Store>,
-        component: &Component,
-    ) -> Result<Instance, Error> {
+    fn instantiate(_ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<Instance, Error> {
+        let args = scan_args::<(Obj<Store>, Obj<Component>), (), (), (), _, ()>(args)?;
+        let kw = get_kwargs::<_, (), (Option<bool>,), ()>(args.keywords, &[], &[*GVL])?;
+        let (store, component) = args.required;
+
         if *rb_self.has_wasi.borrow() && !store.context().data().has_wasi_ctx() {
             return err!("{}", errors::missing_wasi_ctx_error("linker.instantiate"));
         }
+        if store.context().data().is_async() {
+            return err!("components are not supported with async engines");
+        }
 
         let inner = rb_self.inner.borrow();
-        inner
-            .instantiate(store.context_mut(), component.get())
-            .map(|instance| {
-                rb_self
-                    .refs
-                    .borrow()
-                    .iter()
-                    .for_each(|value| store.retain(*value));
-
-                Instance::from_inner(store, instance)
-            })
-            .map_err(|e| error!("{}", e))
+        let context = store.context_mut();
+        match kw.optional.0 {
+            Some(false) => nogvl(|| inner.instantiate(context, component.get())),
+            _ => inner.instantiate(context, component.get()),
+        }
+        .map(|instance| {
+            rb_self
+                .refs
+                .borrow()
+                .iter()
+                .for_each(|value| store.retain(*value));
+
+            Instance::from_inner(store, instance)
+        })
+        .map_err(|e| error!("{}", e))
     }
 
     pub(crate) fn add_wasi_p2(&self) -> Result<(), Error> {
@@ -365,61 +377,66 @@ fn make_component_func_closure(
           func: wasmtime::component::types::ComponentFunc,
           params: &[Val],
           results: &mut [Val]| {
-        let ruby = Ruby::get().unwrap();
-
-        // Convert Wasm params to Ruby values
-        let rparams = ruby.ary_new_capa(params.len());
-        for (i, param) in params.iter().enumerate() {
-            let rb_value =
-                convert::component_val_to_rb(&ruby, param.clone(), None).map_err(|e| {
-                    wasmtime::Error::msg(format!("failed to convert parameter at index {i}: {e}"))
+        // Host functions may be called without the GVL, see `gvl: false`.
+        with_gvl(|| {
+            let ruby = Ruby::get().unwrap();
+
+            // Convert Wasm params to Ruby values
+            let rparams = ruby.ary_new_capa(params.len());
+            for (i, param) in params.iter().enumerate() {
+                let rb_value =
+                    convert::component_val_to_rb(&ruby, param.clone(), None).map_err(|e| {
+                        wasmtime::Error::msg(format!(
+                            "failed to convert parameter at index {i}: {e}"
+                        ))
+                    })?;
+                rparams.push(rb_value).map_err(|e| {
+                    wasmtime::Error::msg(format!("failed to push parameter at index {i}: {e}"))
                 })?;
-            rparams.push(rb_value).map_err(|e| {
-                wasmtime::Error::msg(format!("failed to push parameter at index {i}: {e}"))
+            }
+
+            // Call the Ruby Proc
+            let callable = ruby.get_inner(callable);
+            let proc_result = callable.call::<_, Value>(rparams).map_err(|e| {
+                // Store the Ruby error on StoreData so it can be properly raised later
+                store_context.data_mut().set_error(e);
+                // Return a generic error that will be replaced with the Ruby error
+                wasmtime::Error::msg("")
             })?;
-        }
 
-        // Call the Ruby Proc
-        let callable = ruby.get_inner(callable);
-        let proc_result = callable.call::<_, Value>(rparams).map_err(|e| {
-            // Store the Ruby error on StoreData so it can be properly raised later
-            store_context.data_mut().set_error(e);
-            // Return a generic error that will be replaced with the Ruby error
-            wasmtime::Error::msg("")
-        })?;
-
-        // Get expected result types from function signature
-        let results_types: Vec<_> = func.results().collect();
-        let num_results = results_types.len();
-
-        // Handle result conversion based on arity
-        // Note: WIT only supports 0 or 1 return values (use tuples for multiple values)
-        match num_results {
-            0 => {
-                // No return value expected
-                Ok(())
-            }
-            1 => {
-                // Single return value - convert directly
-                // Don't unwrap arrays - the value might be a list or tuple type
-                let expected_ty = &results_types[0];
-                let converted = convert::rb_to_component_val(proc_result, None, expected_ty)
-                    .map_err(|e| {
-                        store_context.data_mut().set_error(e);
-                        wasmtime::Error::msg("")
-                    })?;
-                results[0] = converted;
-                Ok(())
+            // Get expected result types from function signature
+            let results_types: Vec<_> = func.results().collect();
+            let num_results = results_types.len();
+
+            // Handle result conversion based on arity
+            // Note: WIT only supports 0 or 1 return values (use tuples for multiple values)
+            match num_results {
+                0 => {
+                    // No return value expected
+                    Ok(())
+                }
+                1 => {
+                    // Single return value - convert directly
+                    // Don't unwrap arrays - the value might be a list or tuple type
+                    let expected_ty = &results_types[0];
+                    let converted = convert::rb_to_component_val(proc_result, None, expected_ty)
+                        .map_err(|e| {
+                            store_context.data_mut().set_error(e);
+                            wasmtime::Error::msg("")
+                        })?;
+                    results[0] = converted;
+                    Ok(())
+                }
+                _ => {
+                    // WIT doesn't support multiple return values - this should never happen
+                    store_context.data_mut().set_error(Error::new(
+                        ruby.exception_runtime_error(),
+                        format!("unexpected number of results: {}", num_results),
+                    ));
+                    Err(wasmtime::Error::msg(""))
+                }
             }
-            _ => {
-                // WIT doesn't support multiple return values - this should never happen
-                store_context.data_mut().set_error(Error::new(
-                    ruby.exception_runtime_error(),
-                    format!("unexpected number of results: {}", num_results),
-                ));
-                Err(wasmtime::Error::msg(""))
-            }
-        }
+        })
     }
 }
 
@@ -428,7 +445,7 @@ pub fn init(ruby: &Ruby, namespace: &RModule) -> Result<(), Error> {
     linker.define_singleton_method("new", function!(Linker::new, 1))?;
     linker.define_method("root", method!(Linker::root, 0))?;
     linker.define_method("instance", method!(Linker::instance, 1))?;
-    linker.define_method("instantiate", method!(Linker::instantiate, 2))?;
+    linker.define_method("instantiate", method!(Linker::instantiate, -1))?;
 
     let linker_instance = namespace.define_class("LinkerInstance", ruby.class_object())?;
     linker_instance.define_method("module", method!(LinkerInstance::module, 2))?;
@@ -1,11 +1,12 @@
 use magnus::{
-    class, function, method, module::Module, typed_data::Obj, DataTypeFunctions, Error, Object,
-    RModule, Ruby,
+    class, function, method, module::Module, scan_args, typed_data::Obj, DataTypeFunctions, Error,
+    Object, RModule, Ruby, Value,
 };
 use wasmtime_wasi::p2::bindings::sync::Command;
 
 use crate::{
-    err, error,
+    define_rb_intern, err, error,
+    helpers::nogvl,
     ruby_api::{
         component::{linker::Linker, Component},
         errors,
@@ -14,6 +15,10 @@ use crate::{
     Store,
 };
 
+define_rb_intern!(
+    GVL => "gvl",
+);
+
 #[magnus::wrap(class = "Wasmtime::Component::WasiCommand", size, free_immediately)]
 pub struct WasiCommand {
     command: Command,
@@ -30,6 +35,9 @@ impl WasiCommand {
         if linker.has_wasi() && !store.context().data().has_wasi_ctx() {
             return err!("{}", errors::missing_wasi_ctx_error("WasiCommand.new"));
         }
+        if store.context().data().is_async() {
+            return err!("components are not supported with async engines");
+        }
         let command =
             Command::instantiate(store.context_mut(), component.get(), &linker.inner_mut())
                 .map_err(|e| error!("{e}"))?;
@@ -37,17 +45,25 @@ impl WasiCommand {
     }
 
     /// @yard
-    /// @def call_run(store)
+    /// @def call_run(store, gvl: true)
     /// @param store [Store]
+    /// @param gvl [Boolean] When +false+, releases the GVL while the command runs so other Ruby
+    ///   threads run in parallel (each thread must use its own {Store}). Defaults to +true+.
     /// @return [nil]
-    pub fn call_run(_ruby: &Ruby, rb_self: Obj<Self>, store: Obj<Store>) -> Result<(), Error> {
+    pub fn call_run(_ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<(), Error> {
+        let args = scan_args::scan_args::<(Obj<Store>,), (), (), (), _, ()>(args)?;
+        let kw = scan_args::get_kwargs::<_, (), (Option<bool>,), ()>(args.keywords, &[], &[*GVL])?;
+        let (store,) = args.required;
+
         let store_context_value = StoreContextValue::from(store);
-        rb_self
-            .command
-            .wasi_cli_run()
-            .call_run(store.context_mut())
-            .map_err(|err| error!("{err}"))?
-            .map_err(|_| error!("Error running `run`"))?;
+        let run = rb_self.command.wasi_cli_run();
+        let context = store.context_mut();
+        match kw.optional.0 {
+            Some(false) => nogvl(|| run.call_run(context)),
+            _ => run.call_run(context),
+        }
+        .map_err(|err| error!("{err}"))?
+        .map_err(|_| error!("Error running `run`"))?;
 
         // Check for any errors stored during execution (e.g., from socket checks)
         if let Some(error) = store_context_value.take_last_error()? {
@@ -61,7 +77,7 @@ impl WasiCommand {
 pub fn init(ruby: &Ruby, namespace: &RModule) -> Result<(), Error> {
     let linker = namespace.define_class("WasiCommand", ruby.class_object())?;
     linker.define_singleton_method("new", function!(WasiCommand::new, 3))?;
-    linker.define_method("call_run", method!(WasiCommand::call_run, 1))?;
+    linker.define_method("call_run", method!(WasiCommand::call_run, -1))?;
 
     Ok(())
 }
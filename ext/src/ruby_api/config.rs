@@ -21,8 +21,10 @@ define_rb_intern!(
     EPOCH_INTERRUPTION => "epoch_interruption",
     MAX_WASM_STACK => "max_wasm_stack",
     WASM_THREADS => "wasm_threads",
+    SHARED_MEMORY => "shared_memory",
     WASM_MULTI_MEMORY => "wasm_multi_memory",
     WASM_MEMORY64 => "wasm_memory64",
+    WASM_CUSTOM_PAGE_SIZES => "wasm_custom_page_sizes",
     PROFILER => "profiler",
     CRANELIFT_OPT_LEVEL => "cranelift_opt_level",
     STRATEGY => "strategy",
@@ -42,8 +44,11 @@ define_rb_intern!(
     POOLING => "pooling",
     ON_DEMAND => "on_demand",
     WASM_REFERENCE_TYPES => "wasm_reference_types",
+    WASM_FUNCTION_REFERENCES => "wasm_function_references",
+    WASM_GC => "wasm_gc",
     WASM_EXCEPTIONS => "wasm_exceptions",
     ASYNC_STACK_ZEROING => "async_stack_zeroing",
+    ASYNC_SUPPORT => "async_support",
 );
 
 lazy_static! {
@@ -77,6 +82,12 @@ lazy_static! {
     };
 }
 
+/// Whether the config `hash` enables async support, see [`hash_to_config`].
+pub fn hash_async_support(hash: RHash) -> Result<bool, Error> {
+    hash.lookup::<_, Option<bool>>(*ASYNC_SUPPORT)
+        .map(|async_support| async_support.unwrap_or(false))
+}
+
 pub fn hash_to_config(hash: RHash) -> Result<Config, Error> {
     let ruby = Ruby::get_with(hash);
     let mut config = Config::default();
@@ -98,14 +109,22 @@ pub fn hash_to_config(hash: RHash) -> Result<Config, Error> {
             config.max_wasm_stack(entry.try_into()?);
         } else if *WASM_THREADS == id {
             config.wasm_threads(entry.try_into()?);
+        } else if *SHARED_MEMORY == id {
+            config.shared_memory(entry.try_into()?);
         } else if *WASM_MULTI_MEMORY == id {
             config.wasm_multi_memory(entry.try_into()?);
         } else if *WASM_MEMORY64 == id {
             config.wasm_memory64(entry.try_into()?);
+        } else if *WASM_CUSTOM_PAGE_SIZES == id {
+            config.wasm_custom_page_sizes(entry.try_into()?);
         } else if *PARALLEL_COMPILATION == id {
             config.parallel_compilation(entry.try_into()?);
         } else if *WASM_REFERENCE_TYPES == id {
             config.wasm_reference_types(entry.try_into()?);
+        } else if *WASM_FUNCTION_REFERENCES == id {
+            config.wasm_function_references(entry.try_into()?);
+        } else if *WASM_GC == id {
+            config.wasm_gc(entry.try_into()?);
         } else if *WASM_EXCEPTIONS == id {
             config.wasm_exceptions(entry.try_into()?);
         } else if *PROFILER == id {
@@ -132,6 +151,10 @@ pub fn hash_to_config(hash: RHash) -> Result<Config, Error> {
             config.allocation_strategy(strategy);
         } else if *ASYNC_STACK_ZEROING == id {
             config.async_stack_zeroing(entry.try_into()?);
+        } else if *ASYNC_SUPPORT == id {
+            // Only checked here, see `hash_async_support`: Wasmtime engines
+            // always support async.
+            bool::try_from(entry)?;
         } else {
             return Err(Error::new(
                 ruby.exception_arg_error(),
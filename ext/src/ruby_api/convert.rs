@@ -1,15 +1,22 @@
 use crate::{define_rb_intern, err, error, helpers::SymbolEnum};
 use lazy_static::lazy_static;
 use magnus::{
-    prelude::*, try_convert, Error, IntoValue, RArray, Ruby, Symbol, TryConvert, TypedData, Value,
+    prelude::*, try_convert, Error, Integer, IntoValue, RArray, RString, Ruby, Symbol, TryConvert,
+    TypedData, Value,
+};
+use wasmtime::{
+    AnyRef, Engine, ExternRef, FieldType, HeapType, Mutability, RefType, Rooted, StorageType, Val,
+    ValType, I31,
 };
-use wasmtime::{ExternRef, RefType, Val, ValType};
 
 use super::{
+    array_ref::ArrayRef,
     func::{Func, FuncType},
     global::{Global, GlobalType},
-    memory::{Memory, MemoryType},
+    memory::{Memory, MemoryType, SharedMemory},
+    ref_type,
     store::StoreContextValue,
+    struct_ref::StructRef,
     table::{Table, TableType},
 };
 
@@ -21,6 +28,16 @@ define_rb_intern!(
     V128 => "v128",
     FUNCREF => "funcref",
     EXTERNREF => "externref",
+    ANYREF => "anyref",
+    EQREF => "eqref",
+    I31REF => "i31ref",
+    STRUCTREF => "structref",
+    ARRAYREF => "arrayref",
+    I8 => "i8",
+    I16 => "i16",
+    MUT => "mut",
+    INTEGER => "integer",
+    STRING => "string",
 );
 
 lazy_static! {
@@ -33,10 +50,39 @@ lazy_static! {
             (*V128, ValType::V128),
             (*FUNCREF, ValType::FUNCREF),
             (*EXTERNREF, ValType::EXTERNREF),
+            (*ANYREF, ValType::ANYREF),
+            (*EQREF, ValType::EQREF),
+            (*I31REF, ValType::I31REF),
+            (*STRUCTREF, ValType::STRUCTREF),
+            (*ARRAYREF, ValType::ARRAYREF),
         ];
 
         SymbolEnum::new("WebAssembly type", mapping)
     };
+    static ref V128_FORMAT_MAPPING: SymbolEnum<'static, V128Format> = {
+        let mapping = vec![
+            (*INTEGER, V128Format::Integer),
+            (*STRING, V128Format::String),
+        ];
+
+        SymbolEnum::new(":v128", mapping)
+    };
+}
+
+/// How `v128` values are represented in Ruby, configured per {Store}.
+#[derive(Clone, Copy, Default)]
+pub enum V128Format {
+    /// An unsigned 128-bit Integer.
+    #[default]
+    Integer,
+    /// A 16-byte binary String, little-endian like Wasm memory.
+    String,
+}
+
+impl V128Format {
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        V128_FORMAT_MAPPING.get(value)
+    }
 }
 
 pub trait ToRubyValue {
@@ -67,7 +113,16 @@ impl ToRubyValue for Val {
                 None => Ok(().into_value_with(ruby)),
                 Some(funcref) => Ok(Func::from_inner(*store, *funcref).into_value_with(ruby)),
             },
-            Val::V128(_) => err!("converting from v128 to Ruby unsupported"),
+            Val::AnyRef(anyref) => match anyref {
+                None => Ok(().into_value_with(ruby)),
+                Some(anyref) => any_ref_to_ruby(ruby, store, anyref),
+            },
+            Val::V128(v) => match store.context()?.data().v128_format() {
+                V128Format::Integer => Ok(v.as_u128().into_value_with(ruby)),
+                V128Format::String => {
+                    Ok(ruby.str_from_slice(&v.as_u128().to_le_bytes()).as_value())
+                }
+            },
             t => err!("cannot convert value: {t:?} to Ruby value"),
         }
     }
@@ -93,6 +148,10 @@ impl ToWasmVal for Value {
             return Ok(Val::ExternRef(extern_ref_value));
         }
 
+        if ty.matches(&ValType::ANYREF) {
+            return to_any_ref(*self, store).map(Val::AnyRef);
+        }
+
         if ty.matches(&ValType::FUNCREF) {
             let func_ref_value = match self.is_nil() {
                 true => None,
@@ -106,14 +165,98 @@ impl ToWasmVal for Value {
             ValType::I64 => Ok(i64::try_convert(*self)?.into()),
             ValType::F32 => Ok(f32::try_convert(*self)?.into()),
             ValType::F64 => Ok(f64::try_convert(*self)?.into()),
-            ValType::V128 => err!("converting from Ruby to v128 not supported"),
-            // TODO: to be filled in once typed function references and/or GC
-            // are enabled by default.
+            ValType::V128 => Ok(Val::V128(to_v128(*self)?.into())),
             t => err!("unsupported type: {t:?}"),
         }
     }
 }
 
+/// Converts an `anyref` to an Integer for `i31ref`s, or to a {StructRef} or
+/// an {ArrayRef}.
+fn any_ref_to_ruby(
+    ruby: &Ruby,
+    store: &StoreContextValue,
+    anyref: &Rooted<AnyRef>,
+) -> Result<Value, Error> {
+    let mut context = store.context_mut()?;
+    if let Some(i31) = anyref.as_i31(&context).map_err(|e| error!("{e}"))? {
+        return Ok(i31.get_i32().into_value_with(ruby));
+    }
+    if let Some(struct_ref) = anyref.as_struct(&context).map_err(|e| error!("{e}"))? {
+        let inner = struct_ref
+            .to_owned_rooted(&mut context)
+            .map_err(|e| error!("{e}"))?;
+        return Ok(ruby
+            .obj_wrap(StructRef::from_inner(*store, inner))
+            .as_value());
+    }
+    if let Some(array_ref) = anyref.as_array(&context).map_err(|e| error!("{e}"))? {
+        let inner = array_ref
+            .to_owned_rooted(&mut context)
+            .map_err(|e| error!("{e}"))?;
+        return Ok(ruby
+            .obj_wrap(ArrayRef::from_inner(*store, inner))
+            .as_value());
+    }
+    err!("cannot convert anyref to Ruby value")
+}
+
+/// Converts +nil+, an Integer (as an `i31ref`), a {StructRef} or an
+/// {ArrayRef} to an `anyref`.
+fn to_any_ref(value: Value, store: &StoreContextValue) -> Result<Option<Rooted<AnyRef>>, Error> {
+    let ruby = Ruby::get_with(value);
+    if value.is_nil() {
+        return Ok(None);
+    }
+    if let Some(integer) = Integer::from_value(value) {
+        let i31 = integer
+            .to_i32()
+            .ok()
+            .and_then(I31::new_i32)
+            .ok_or_else(|| {
+                Error::new(
+                    ruby.exception_range_error(),
+                    format!("{} out of range for i31ref", value.inspect()),
+                )
+            })?;
+        return Ok(Some(AnyRef::from_i31(store.context_mut()?, i31)));
+    }
+    if let Ok(struct_ref) = <&StructRef>::try_convert(value) {
+        return struct_ref.to_rooted(store).map(|r| Some(r.to_anyref()));
+    }
+    if let Ok(array_ref) = <&ArrayRef>::try_convert(value) {
+        return array_ref.to_rooted(store).map(|r| Some(r.to_anyref()));
+    }
+
+    Err(Error::new(
+        ruby.exception_type_error(),
+        format!(
+            "expected nil, an Integer, a StructRef or an ArrayRef, got {}",
+            value.inspect()
+        ),
+    ))
+}
+
+/// Converts a 16-byte String or an Integer to a `v128`. Negative Integers
+/// are taken as two's complement.
+fn to_v128(value: Value) -> Result<u128, Error> {
+    if let Some(string) = RString::from_value(value) {
+        let bytes = unsafe { string.as_slice() };
+        return match <[u8; 16]>::try_from(bytes) {
+            Ok(bytes) => Ok(u128::from_le_bytes(bytes)),
+            Err(_) => err!(
+                "expected a 16-byte String for v128, got {} bytes",
+                bytes.len()
+            ),
+        };
+    }
+
+    let integer = Integer::try_convert(value)?;
+    integer
+        .to_u128()
+        .or_else(|_| integer.to_i128().map(|i| i as u128))
+}
+
 struct ExternRefValue(Value);
 impl From<Value> for ExternRefValue {
     fn from(v: Value) -> Self {
@@ -133,6 +276,8 @@ impl ToExtern for Value {
             Ok(<&Func>::try_convert(*self)?.into())
         } else if self.is_kind_of(Memory::class(ruby)) {
             Ok(<&Memory>::try_convert(*self)?.into())
+        } else if self.is_kind_of(SharedMemory::class(ruby)) {
+            Ok(<&SharedMemory>::try_convert(*self)?.into())
         } else if self.is_kind_of(Table::class(ruby)) {
             Ok(<&Table>::try_convert(*self)?.into())
         } else if self.is_kind_of(Global::class(ruby)) {
@@ -146,68 +291,110 @@ impl ToExtern for Value {
     }
 }
 
-pub trait ToSym {
-    fn to_sym(&self) -> Result<Symbol, Error>;
+pub trait ToRubyType {
+    fn to_ruby_type(&self, ruby: &Ruby) -> Result<Value, Error>;
 }
 
-impl ToSym for ValType {
-    fn to_sym(&self) -> Result<Symbol, Error> {
-        if self.matches(&ValType::EXTERNREF) {
-            return Ok(Symbol::from(*EXTERNREF));
-        }
+impl ToRubyType for ValType {
+    fn to_ruby_type(&self, ruby: &Ruby) -> Result<Value, Error> {
+        let id = match self {
+            ValType::I32 => *I32,
+            ValType::I64 => *I64,
+            ValType::F32 => *F32,
+            ValType::F64 => *F64,
+            ValType::V128 => *V128,
+            ValType::Ref(ref_type) => return ref_type.to_ruby_type(ruby),
+        };
+        Ok(Symbol::from(id).as_value())
+    }
+}
 
-        if self.matches(&ValType::FUNCREF) {
-            return Ok(Symbol::from(*FUNCREF));
-        }
-
-        match self {
-            &ValType::I32 => Ok(Symbol::from(*I32)),
-            &ValType::I64 => Ok(Symbol::from(*I64)),
-            &ValType::F32 => Ok(Symbol::from(*F32)),
-            &ValType::F64 => Ok(Symbol::from(*F64)),
-            &ValType::V128 => Ok(Symbol::from(*V128)),
-            t => Err(error!("Unsupported type {t:?}")),
+impl ToRubyType for RefType {
+    /// A Symbol for nullable abstract types (e.g. +:funcref+), else a
+    /// {Wasmtime::RefType}.
+    fn to_ruby_type(&self, ruby: &Ruby) -> Result<Value, Error> {
+        let id = match self.heap_type() {
+            _ if !self.is_nullable() => None,
+            HeapType::Func => Some(*FUNCREF),
+            HeapType::Extern => Some(*EXTERNREF),
+            HeapType::Any => Some(*ANYREF),
+            HeapType::Eq => Some(*EQREF),
+            HeapType::I31 => Some(*I31REF),
+            HeapType::Struct => Some(*STRUCTREF),
+            HeapType::Array => Some(*ARRAYREF),
+            _ => None,
+        };
+        match id {
+            Some(id) => Ok(Symbol::from(id).as_value()),
+            None => Ok(ruby
+                .obj_wrap(ref_type::RefType::from_inner(self.clone()))
+                .as_value()),
         }
     }
 }
 
-impl ToSym for RefType {
-    fn to_sym(&self) -> Result<Symbol, Error> {
-        if self.matches(&RefType::FUNCREF) {
-            return Ok(Symbol::from(*FUNCREF));
-        }
-        if self.matches(&RefType::EXTERNREF) {
-            return Ok(Symbol::from(*EXTERNREF));
+/// Converts a GC struct field or array element type: a type Symbol,
+/// including the packed +:i8+ and +:i16+, for immutable fields, or
+/// +[:mut, type]+ for mutable ones.
+pub fn to_field_type(value: Value, engine: &Engine) -> Result<FieldType, Error> {
+    let (mutability, storage) = match RArray::from_value(value) {
+        Some(array) => {
+            let (mutable, storage) = <(Symbol, Value)>::try_convert(array.as_value())?;
+            if *MUT != magnus::value::Id::from(mutable) {
+                return err!("expected [:mut, type], got {}", value.inspect());
+            }
+            (Mutability::Var, storage)
         }
-        Err(error!("Unsupported RefType {self:}"))
+        None => (Mutability::Const, value),
+    };
+
+    let storage = match Symbol::from_value(storage).map(magnus::value::Id::from) {
+        Some(id) if *I8 == id => StorageType::I8,
+        Some(id) if *I16 == id => StorageType::I16,
+        _ => StorageType::ValType(storage.to_val_type(engine)?),
+    };
+    Ok(FieldType::new(mutability, storage))
+}
+
+/// The inverse of [`to_field_type`].
+pub fn field_type_to_value(ruby: &Ruby, field: &FieldType) -> Result<Value, Error> {
+    let storage = match field.element_type() {
+        StorageType::I8 => Symbol::from(*I8).as_value(),
+        StorageType::I16 => Symbol::from(*I16).as_value(),
+        StorageType::ValType(ty) => ty.to_ruby_type(ruby)?,
+    };
+    match field.mutability() {
+        Mutability::Var => Ok(ruby
+            .ary_new_from_values(&[Symbol::from(*MUT).as_value(), storage])
+            .as_value()),
+        Mutability::Const => Ok(storage),
     }
 }
 
 pub trait ToValType {
-    fn to_val_type(&self) -> Result<ValType, Error>;
+    /// Converts a type Symbol or a {Wasmtime::RefType} to a [`ValType`] to
+    /// be used with `engine`.
+    fn to_val_type(&self, engine: &Engine) -> Result<ValType, Error>;
 }
 
 impl ToValType for Value {
-    fn to_val_type(&self) -> Result<ValType, Error> {
+    fn to_val_type(&self, engine: &Engine) -> Result<ValType, Error> {
+        if let Ok(ref_type) = <&ref_type::RefType>::try_convert(*self) {
+            return ref_type.for_engine(engine).map(ValType::Ref);
+        }
         VALTYPE_MAPPING.get(*self)
     }
 }
 
-impl ToValType for Symbol {
-    fn to_val_type(&self) -> Result<ValType, Error> {
-        self.as_value().to_val_type()
-    }
-}
-
 pub trait ToValTypeVec {
-    fn to_val_type_vec(&self) -> Result<Vec<ValType>, Error>;
+    fn to_val_type_vec(&self, engine: &Engine) -> Result<Vec<ValType>, Error>;
 }
 
 impl ToValTypeVec for RArray {
-    fn to_val_type_vec(&self) -> Result<Vec<ValType>, Error> {
+    fn to_val_type_vec(&self, engine: &Engine) -> Result<Vec<ValType>, Error> {
         unsafe { self.as_slice() }
             .iter()
-            .map(ToValType::to_val_type)
+            .map(|ty| ty.to_val_type(engine))
             .collect::<Result<Vec<ValType>, Error>>()
     }
 }
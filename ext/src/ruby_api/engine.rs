@@ -1,18 +1,29 @@
-use super::{config::hash_to_config, root};
+use super::{
+    config::{hash_async_support, hash_to_config},
+    errors::base_error,
+    module::Module as RbModule,
+    root,
+};
 use crate::{
     error,
     helpers::{nogvl, Tmplock},
 };
 use magnus::{
     class, function, method, prelude::*, scan_args, typed_data::Obj, value::LazyId, Error, Module,
-    Object, RHash, RString, Ruby, TryConvert, Value,
+    Object, RArray, RHash, RString, Ruby, TryConvert, Value,
 };
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
-    sync::Mutex,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
 };
-use wasmtime::{Config, Engine as EngineImpl};
+use wasmtime::{Config, Engine as EngineImpl, Module as ModuleImpl};
+use wasmtime_environ::wasmparser::WasmFeatures;
 
 #[cfg(feature = "tokio")]
 lazy_static::lazy_static! {
@@ -20,6 +31,7 @@ lazy_static::lazy_static! {
         .thread_name("wasmtime-engine-timers")
         .worker_threads(1)
         .enable_io()
+        .enable_time()
         .build()
         .unwrap();
 }
@@ -27,6 +39,20 @@ lazy_static::lazy_static! {
 /// @yard
 /// Represents a Wasmtime execution engine.
 ///
+/// Engines are frozen when created and can be shared with other Ractors, e.g.
+/// by passing them to +Ractor.new+. All of an engine's methods are safe to
+/// call concurrently from any thread or Ractor. {Store}s and {Linker}s are not
+/// shareable: each Ractor creates its own from a shared engine.
+///
+/// @example Sharing an engine and a module with a Ractor
+///    engine = Wasmtime::Engine.new
+///    mod = Wasmtime::Module.new(engine, wat)
+///
+///    Ractor.new(engine, mod) do |engine, mod|
+///      store = Wasmtime::Store.new(engine)
+///      Wasmtime::Instance.new(store, mod).invoke("run")
+///    end
+///
 /// @example Disabling parallel compilation
 ///    # Many Ruby servers use a pre-forking mechanism to allow parallel request
 ///    # processing. Unfortunately, this can causes processes to deadlock if you
@@ -49,6 +75,12 @@ lazy_static::lazy_static! {
 #[magnus::wrap(class = "Wasmtime::Engine", free_immediately, frozen_shareable)]
 pub struct Engine {
     inner: EngineImpl,
+    /// The interval of the running epoch timer in milliseconds, 0 when stopped.
+    epoch_interval_ms: Arc<AtomicU64>,
+    /// The number of epoch increments made through this engine.
+    epoch: Arc<AtomicU64>,
+    async_support: bool,
+    features: WasmFeatures,
 
     #[cfg(feature = "tokio")]
     timer_task: Mutex<Option<tokio::task::JoinHandle<()>>>,
@@ -68,6 +100,7 @@ impl Engine {
     ///   See the {https://docs.rs/wasmtime/latest/wasmtime/struct.Engine.html +Config+‘s Rust doc} for detailed description of
     ///   the different options and the defaults.
     /// @option config [Boolean] :async_stack_zeroing Configures whether or not stacks used for async futures are zeroed before (re)use.
+    /// @option config [Boolean] :async_support Runs Wasm asynchronously, see {Func#call_async}. Synchronous calls such as {Func#call} are not available with such engines.
     /// @option config [Boolean] :debug_info
     /// @option config [Boolean] :wasm_backtrace_details
     /// @option config [Boolean] :native_unwind_info
@@ -75,9 +108,13 @@ impl Engine {
     /// @option config [Boolean] :epoch_interruption
     /// @option config [Integer] :max_wasm_stack
     /// @option config [Boolean] :wasm_threads
+    /// @option config [Boolean] :shared_memory (false) Whether {SharedMemory} can be created, requires +wasm_threads+.
     /// @option config [Boolean] :wasm_multi_memory
     /// @option config [Boolean] :wasm_memory64
+    /// @option config [Boolean] :wasm_custom_page_sizes Whether memories may use a page size of 1 byte.
     /// @option config [Boolean] :wasm_reference_types
+    /// @option config [Boolean] :wasm_function_references Whether the typed function references proposal is enabled.
+    /// @option config [Boolean] :wasm_gc Whether the GC proposal is enabled, requires +wasm_function_references+.
     /// @option config [Boolean] :wasm_exceptions Whether the WebAssembly exception-handling proposal is enabled.
     /// @option config [Boolean] :parallel_compilation (true) Whether compile Wasm using multiple threads
     /// @option config [Boolean] :generate_address_map Configures whether compiled artifacts will contain information to map native program addresses back to the original wasm module. This configuration option is `true` by default. Disabling this feature can result in considerably smaller serialized modules.
@@ -88,24 +125,41 @@ impl Engine {
     ///
     /// @see https://docs.rs/wasmtime/latest/wasmtime/struct.Engine.html
     ///     Wasmtime's Rust doc for details of the configuration options.
-    pub fn new(args: &[Value]) -> Result<Self, Error> {
+    pub fn new(ruby: &Ruby, args: &[Value]) -> Result<Obj<Self>, Error> {
         let args = scan_args::scan_args::<(), (Option<Value>,), (), (), (), ()>(args)?;
         let (config,) = args.optional;
         let config = config.and_then(|v| if v.is_nil() { None } else { Some(v) });
-        let inner = match config {
+        let (inner, async_support) = match config {
             Some(config) => {
-                let config = RHash::try_convert(config).and_then(hash_to_config)?;
+                let hash = RHash::try_convert(config)?;
+                let config = hash_to_config(hash)?;
 
-                EngineImpl::new(&config).map_err(|e| error!("{}", e))?
+                (
+                    EngineImpl::new(&config).map_err(|e| error!("{}", e))?,
+                    hash_async_support(hash)?,
+                )
             }
-            None => EngineImpl::new(&Config::default()).map_err(|e| error!("{}", e))?,
+            None => (
+                EngineImpl::new(&Config::default()).map_err(|e| error!("{}", e))?,
+                false,
+            ),
         };
+        let features = engine_features(&inner);
 
-        Ok(Self {
+        let engine = ruby.obj_wrap(Self {
             inner,
+            async_support,
+            features,
+            epoch_interval_ms: Default::default(),
+            epoch: Default::default(),
             #[cfg(feature = "tokio")]
             timer_task: Default::default(),
-        })
+        });
+        // Cache the key before freezing, the engine can't be modified after.
+        Self::precompile_compatibility_key(ruby, engine)?;
+        engine.freeze();
+
+        Ok(engine)
     }
 
     /// @yard
@@ -120,6 +174,7 @@ impl Engine {
     pub fn start_epoch_interval(&self, milliseconds: u64) {
         self.stop_epoch_interval();
         let engine = self.inner.clone();
+        let epoch = self.epoch.clone();
 
         let handle = TOKIO_RT.spawn(async move {
             let tick_every = tokio::time::Duration::from_millis(milliseconds);
@@ -127,11 +182,14 @@ impl Engine {
 
             loop {
                 interval.wait().await;
+                epoch.fetch_add(1, Ordering::Relaxed);
                 engine.increment_epoch();
             }
         });
 
         *self.timer_task.lock().unwrap() = Some(handle);
+        self.epoch_interval_ms
+            .store(milliseconds, Ordering::Relaxed);
     }
 
     /// @yard
@@ -145,6 +203,7 @@ impl Engine {
         if let Some(handle) = maybe_handle {
             handle.abort();
         }
+        self.epoch_interval_ms.store(0, Ordering::Relaxed);
     }
 
     /// @yard
@@ -154,6 +213,7 @@ impl Engine {
     /// Using {#start_epoch_interval} is recommended because it sidesteps the GVL.
     /// @return [nil]
     pub fn increment_epoch(&self) {
+        self.epoch.fetch_add(1, Ordering::Relaxed);
         self.inner.increment_epoch();
     }
 
@@ -203,6 +263,66 @@ impl Engine {
             .map_err(|e| error!("{}", e.to_string()))
     }
 
+    /// @yard
+    /// Compiles many WebAssembly modules in parallel across native threads,
+    /// without holding the GVL.
+    ///
+    /// Compilation errors don't fail the whole batch: the {Error} is returned
+    /// in place of the module that failed to compile.
+    ///
+    /// @def compile_many(wat_or_wasms)
+    /// @param wat_or_wasms [Array<String>] The Strings of WAT or Wasm.
+    /// @return [Array<Module, Error>] The compiled modules, in the same order as +wat_or_wasms+.
+    /// @see Module.new
+    pub fn compile_many(
+        ruby: &Ruby,
+        rb_self: Obj<Self>,
+        wat_or_wasms: RArray,
+    ) -> Result<RArray, Error> {
+        let engine = rb_self.inner.clone();
+        let results = compile_parallel(wat_or_wasms, |bytes| ModuleImpl::new(&engine, bytes))?;
+
+        results
+            .into_iter()
+            .try_fold(ruby.ary_new_capa(wat_or_wasms.len()), |array, result| {
+                match result {
+                    Ok(module) => array.push(RbModule::wrap_frozen(ruby, module))?,
+                    Err(e) => array.push(error_value(format!("Could not build module: {e}"))?)?,
+                }
+                Ok(array)
+            })
+    }
+
+    /// @yard
+    /// AoT compiles many WebAssembly modules in parallel across native
+    /// threads, without holding the GVL.
+    ///
+    /// Compilation errors don't fail the whole batch: the {Error} is returned
+    /// in place of the module that failed to compile.
+    ///
+    /// @def precompile_many(wat_or_wasms)
+    /// @param wat_or_wasms [Array<String>] The Strings of WAT or Wasm.
+    /// @return [Array<String, Error>] Binary Strings of the compiled modules, in the same order as +wat_or_wasms+.
+    /// @see #precompile_module
+    pub fn precompile_many(
+        ruby: &Ruby,
+        rb_self: Obj<Self>,
+        wat_or_wasms: RArray,
+    ) -> Result<RArray, Error> {
+        let engine = rb_self.inner.clone();
+        let results = compile_parallel(wat_or_wasms, |bytes| engine.precompile_module(bytes))?;
+
+        results
+            .into_iter()
+            .try_fold(ruby.ary_new_capa(wat_or_wasms.len()), |array, result| {
+                match result {
+                    Ok(bytes) => array.push(ruby.str_from_slice(&bytes))?,
+                    Err(e) => array.push(error_value(format!("Could not build module: {e}"))?)?,
+                }
+                Ok(array)
+            })
+    }
+
     /// @yard
     /// If two engines have a matching {Engine.precompile_compatibility_key},
     /// then serialized modules from one engine can be deserialized by the
@@ -231,6 +351,95 @@ impl Engine {
     pub fn get(&self) -> &EngineImpl {
         &self.inner
     }
+
+    /// Shared handle on the epoch timer's interval, see {#start_epoch_interval}.
+    pub fn epoch_interval_ms(&self) -> Arc<AtomicU64> {
+        self.epoch_interval_ms.clone()
+    }
+
+    /// The Tokio runtime running epoch timers. Async Wasm calls run within
+    /// it too, see [`crate::helpers::block_on`].
+    #[cfg(feature = "tokio")]
+    pub fn tokio_runtime() -> &'static tokio::runtime::Runtime {
+        &TOKIO_RT
+    }
+
+    /// Whether the engine was created with +async_support: true+.
+    pub fn is_async(&self) -> bool {
+        self.async_support
+    }
+
+    /// The Wasm features the engine validates and compiles with.
+    pub fn features(&self) -> WasmFeatures {
+        self.features
+    }
+
+    /// Shared count of the epoch increments made through this engine.
+    pub fn epoch(&self) -> Arc<AtomicU64> {
+        self.epoch.clone()
+    }
+}
+
+/// Runs `compile` on each of the Ruby strings in `inputs` without the GVL,
+/// spreading the work across as many native threads as there are CPUs.
+/// Results are returned in the same order as `inputs`.
+fn compile_parallel<T, F>(inputs: RArray, compile: F) -> Result<Vec<wasmtime::Result<T>>, Error>
+where
+    T: Send,
+    F: Fn(&[u8]) -> wasmtime::Result<T> + Sync,
+{
+    let strings = inputs.to_vec::<RString>()?;
+    let mut guards = Vec::with_capacity(strings.len());
+    let mut slices = Vec::with_capacity(strings.len());
+    for string in &strings {
+        let (slice, guard) = string.as_locked_slice()?;
+        slices.push(slice);
+        guards.push(guard);
+    }
+
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(slices.len());
+    let next = AtomicUsize::new(0);
+
+    let mut results = nogvl(|| {
+        thread::scope(|scope| {
+            let handles = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(slice) = slices.get(index) else {
+                                return results;
+                            };
+                            results.push((index, compile(slice)));
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+
+    results.sort_unstable_by_key(|(index, _)| *index);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+/// Builds an exception object to return, rather than raise, for a failed
+/// item of a batch operation.
+fn error_value(message: String) -> Result<Value, Error> {
+    base_error()
+        .new_instance((message,))
+        .map(|exception| exception.as_value())
 }
 
 pub fn init(ruby: &Ruby) -> Result<(), Error> {
@@ -256,6 +465,8 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
         "precompile_component",
         method!(Engine::precompile_component, 1),
     )?;
+    class.define_method("compile_many", method!(Engine::compile_many, 1))?;
+    class.define_method("precompile_many", method!(Engine::precompile_many, 1))?;
     class.define_method(
         "precompile_compatibility_key",
         method!(Engine::precompile_compatibility_key, 0),
@@ -263,3 +474,16 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// The Wasm features of `engine`, after Wasmtime applied its defaults and
+/// removed the ones its compiler doesn't support (e.g. tail calls on Winch).
+/// Wasmtime only exposes them through `Config`'s `Debug` output, which lists
+/// every `WasmFeatures` flag as `wasm_<name>: <bool>`.
+fn engine_features(engine: &EngineImpl) -> WasmFeatures {
+    let config = format!("{:?}", engine.config());
+
+    WasmFeatures::all()
+        .iter_names()
+        .filter(|(name, _)| config.contains(&format!(" wasm_{}: true", name.to_lowercase())))
+        .fold(WasmFeatures::empty(), |features, (_, flag)| features | flag)
+}
@@ -1,6 +1,7 @@
 use crate::ruby_api::root;
 use magnus::{error::ErrorType, value::Lazy, Error, ExceptionClass, Module, Ruby};
 use std::borrow::Cow;
+use wasmtime_environ::wasmparser::BinaryReaderError;
 
 /// Base error class for all Wasmtime errors.
 pub fn base_error() -> ExceptionClass {
@@ -24,6 +25,13 @@ pub fn conversion_error() -> ExceptionClass {
     ruby.get_inner(&ERR)
 }
 
+/// Raised when a Wasm module or component fails validation.
+pub fn validation_error() -> ExceptionClass {
+    static ERR: Lazy<ExceptionClass> = Lazy::new(|_| root().const_get("ValidationError").unwrap());
+    let ruby = Ruby::get().unwrap();
+    ruby.get_inner(&ERR)
+}
+
 /// Raised when a WASI program terminates early by calling +exit+.
 pub fn wasi_exit_error() -> ExceptionClass {
     static ERR: Lazy<ExceptionClass> = Lazy::new(|_| root().const_get("WasiExit").unwrap());
@@ -83,6 +91,20 @@ impl ExceptionMessage for magnus::Error {
     }
 }
 
+/// Builds a {ValidationError} from a validation failure, extracting the byte
+/// offset when the failure originates from the Wasm parser.
+pub(crate) fn validation_err(error: wasmtime::Error) -> Error {
+    let (message, offset) = match error.downcast_ref::<BinaryReaderError>() {
+        Some(e) => (e.message().to_string(), Some(e.offset())),
+        None => (error.to_string(), None),
+    };
+
+    validation_error()
+        .new_instance((message, offset))
+        .map(Into::into)
+        .unwrap_or_else(|e| e)
+}
+
 pub(crate) fn missing_wasi_ctx_error(callee: &str) -> String {
     missing_wasi_error(callee, "WASI", "P2", "wasi_config")
 }
@@ -116,6 +138,7 @@ pub fn init() -> Result<(), Error> {
     let _ = base_error();
     let _ = result_error();
     let _ = conversion_error();
+    let _ = validation_error();
     let _ = wasi_exit_error();
 
     Ok(())
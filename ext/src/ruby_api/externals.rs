@@ -2,7 +2,7 @@ use super::{
     convert::{WrapWasmtimeExternType, WrapWasmtimeType},
     func::{Func, FuncType},
     global::{Global, GlobalType},
-    memory::{Memory, MemoryType},
+    memory::{Memory, MemoryType, SharedMemory},
     root,
     store::StoreContextValue,
     table::{Table, TableType},
@@ -113,6 +113,7 @@ pub enum Extern<'a> {
     Func(Obj<Func<'a>>),
     Global(Obj<Global<'a>>),
     Memory(Obj<Memory<'a>>),
+    SharedMemory(Obj<SharedMemory>),
     Table(Obj<Table<'a>>),
 }
 
@@ -122,6 +123,7 @@ impl DataTypeFunctions for Extern<'_> {
             Extern::Func(f) => marker.mark(*f),
             Extern::Global(g) => marker.mark(*g),
             Extern::Memory(m) => marker.mark(*m),
+            Extern::SharedMemory(m) => marker.mark(*m),
             Extern::Table(t) => marker.mark(*t),
         }
     }
@@ -172,6 +174,17 @@ impl Extern<'_> {
         }
     }
 
+    /// @yard
+    /// Returns the exported shared memory or raises a `{ConversionError}` when
+    /// the export is not a shared memory.
+    /// @return [SharedMemory] The exported shared memory.
+    pub fn to_shared_memory(ruby: &Ruby, rb_self: Obj<Self>) -> Result<Value, Error> {
+        match *rb_self {
+            Extern::SharedMemory(m) => Ok(m.as_value()),
+            _ => conversion_err!(Self::inner_class(rb_self), SharedMemory::class(ruby)),
+        }
+    }
+
     /// @yard
     /// Returns the exported table or raises a `{ConversionError}` when the export is not a table.
     /// @return [Table] The exported table.
@@ -187,6 +200,7 @@ impl Extern<'_> {
             Extern::Func(f) => f.inspect(),
             Extern::Global(g) => g.inspect(),
             Extern::Memory(m) => m.inspect(),
+            Extern::SharedMemory(m) => m.inspect(),
             Extern::Table(t) => t.inspect(),
         };
 
@@ -202,6 +216,7 @@ impl Extern<'_> {
             Extern::Func(f) => f.class(),
             Extern::Global(g) => g.class(),
             Extern::Memory(m) => m.class(),
+            Extern::SharedMemory(m) => m.class(),
             Extern::Table(t) => t.class(),
         }
     }
@@ -226,9 +241,9 @@ impl<'a> WrapWasmtimeType<'a, Extern<'a>> for wasmtime::Extern {
             wasmtime::Extern::Table(table) => Ok(Extern::Table(
                 ruby.obj_wrap(Table::from_inner(store, *table)),
             )),
-            wasmtime::Extern::SharedMemory(_) => {
-                not_implemented!(ruby, "shared memory not supported")
-            }
+            wasmtime::Extern::SharedMemory(mem) => Ok(Extern::SharedMemory(
+                ruby.obj_wrap(SharedMemory::from_inner(mem.clone())),
+            )),
             wasmtime::Extern::Tag(_) => {
                 not_implemented!(ruby, "exception handling not yet implemented")
             }
@@ -270,6 +285,7 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     class.define_method("to_func", method!(Extern::to_func, -1))?;
     class.define_method("to_global", method!(Extern::to_global, 0))?;
     class.define_method("to_memory", method!(Extern::to_memory, 0))?;
+    class.define_method("to_shared_memory", method!(Extern::to_shared_memory, 0))?;
     class.define_method("to_table", method!(Extern::to_table, 0))?;
     class.define_method("inspect", method!(Extern::inspect, 0))?;
 
@@ -1,22 +1,34 @@
 use super::{
-    convert::{ToRubyValue, ToSym, ToValTypeVec, ToWasmVal},
+    convert::{ToRubyType, ToRubyValue, ToValTypeVec, ToWasmVal},
     engine,
     errors::result_error,
     params::Params,
     root,
-    store::{Store, StoreContextValue, StoreData},
+    store::{self, Store, StoreContextValue, StoreData},
 };
 use crate::{
-    error,
-    helpers::{nogvl, with_gvl},
-    Caller,
+    define_rb_intern, err, error,
+    helpers::{block_on, nogvl, on_host_stack, with_gvl},
+    Caller, Engine,
 };
 use magnus::{
-    block::Proc, class, function, gc::Marker, method, prelude::*, scan_args::scan_args,
-    typed_data::Obj, value::Opaque, DataTypeFunctions, Error, IntoValue, Object, RArray, Ruby,
-    TypedData, Value,
+    block::Proc,
+    class, function,
+    gc::Marker,
+    method,
+    prelude::*,
+    scan_args::{get_kwargs, scan_args},
+    typed_data::Obj,
+    value::Opaque,
+    DataTypeFunctions, Error, IntoValue, Object, RArray, RHash, Ruby, TypedData, Value,
 };
-use wasmtime::{Caller as CallerImpl, Func as FuncImpl, Val};
+use std::{future::Future, sync::Arc};
+use wasmtime::{Caller as CallerImpl, Func as FuncImpl, StoreContextMut, Val};
+
+define_rb_intern!(
+    FUEL => "fuel",
+    GVL => "gvl",
+);
 
 /// @yard
 /// @rename Wasmtime::FuncType
@@ -42,26 +54,48 @@ impl FuncType {
     }
 
     /// @yard
-    /// @return [Array<Symbol>] The function's parameter types.
+    /// Builds a function type, e.g. to be referenced by a {RefType}.
+    ///
+    /// @def new(engine, params, results)
+    /// @param engine [Engine]
+    /// @param params [Array<Symbol, RefType>] The function's parameter types.
+    /// @param results [Array<Symbol, RefType>] The function's result types.
+    /// @return [FuncType]
+    pub fn new(engine: &Engine, params: RArray, results: RArray) -> Result<Self, Error> {
+        let engine = engine.get();
+        let inner = wasmtime::FuncType::new(
+            engine,
+            params.to_val_type_vec(engine)?,
+            results.to_val_type_vec(engine)?,
+        );
+        Ok(Self { inner })
+    }
+
+    /// @yard
+    /// @return [Array<Symbol, RefType>] The function's parameter types.
     pub fn params(ruby: &Ruby, rb_self: Obj<Self>) -> Result<RArray, Error> {
         let len = rb_self.inner.params().len();
         let mut params = rb_self.inner.params();
         params.try_fold(ruby.ary_new_capa(len), |array, p| {
-            array.push(p.to_sym()?)?;
+            array.push(p.to_ruby_type(ruby)?)?;
             Ok(array)
         })
     }
 
     /// @yard
-    /// @return [Array<Symbol>] The function's result types.
+    /// @return [Array<Symbol, RefType>] The function's result types.
     pub fn results(ruby: &Ruby, rb_self: Obj<Self>) -> Result<RArray, Error> {
         let len = rb_self.inner.results().len();
         let mut results = rb_self.inner.results();
         results.try_fold(ruby.ary_new_capa(len), |array, r| {
-            array.push(r.to_sym()?)?;
+            array.push(r.to_ruby_type(ruby)?)?;
             Ok(array)
         })
     }
+
+    pub fn get(&self) -> &wasmtime::FuncType {
+        &self.inner
+    }
 }
 
 impl From<&FuncType> for wasmtime::ExternType {
@@ -102,10 +136,14 @@ impl<'a> Func<'a> {
     /// valid WebAssembly type represented as a symbol. The valid symbols are:
     /// +:i32+, +:i64+, +:f32+, +:f64+, +:v128+, +:funcref+, +:externref+.
     ///
+    /// With an {Engine} created with +async_support: true+, this is the same
+    /// as {Func.new_async}: Ruby code can't run on the Wasm stack of async
+    /// calls.
+    ///
     /// @def new(store, params, results, &block)
     /// @param store [Store]
-    /// @param params [Array<Symbol>] The function's parameters.
-    /// @param results [Array<Symbol>] The function's results.
+    /// @param params [Array<Symbol, RefType>] The function's parameters.
+    /// @param results [Array<Symbol, RefType>] The function's results.
     /// @param block [Block] The function's implementation.
     ///
     /// @yield [caller, *args] The function's body
@@ -132,19 +170,63 @@ impl<'a> Func<'a> {
     pub fn new(args: &[Value]) -> Result<Self, Error> {
         let args = scan_args::<(Obj<Store>, RArray, RArray), (), (), (), (), Proc>(args)?;
         let (store, params, results) = args.required;
-        let callable = args.block;
 
+        Self::define(store, params, results, args.block)
+    }
+
+    /// @yard
+    /// Creates an async WebAssembly function from a Ruby block. The Wasm stack
+    /// is suspended while the block runs, so the block may block on IO or
+    /// +Fiber+ operations: with a +Fiber.scheduler+, other fibers keep running
+    /// until the block returns.
+    ///
+    /// @def new_async(store, params, results, &block)
+    /// @param (see Func.new)
+    /// @yield (see Func.new)
+    /// @yieldparam (see Func.new)
+    /// @yieldreturn (see Func.new)
+    /// @return [Func]
+    /// @raise [Error] if the {Store}'s {Engine} is not configured for async execution
+    /// @see Func#call_async
+    ///
+    /// @example Host function making a non-blocking HTTP request:
+    ///   engine = Wasmtime::Engine.new(async_support: true)
+    ///   store = Wasmtime::Store.new(engine)
+    ///   Wasmtime::Func.new_async(store, [], [:i32]) do |_caller|
+    ///     Net::HTTP.get_response(URI("https://example.com")).code.to_i
+    ///   end
+    pub fn new_async(args: &[Value]) -> Result<Self, Error> {
+        let args = scan_args::<(Obj<Store>, RArray, RArray), (), (), (), (), Proc>(args)?;
+        let (store, params, results) = args.required;
+        if !store.context().data().is_async() {
+            return err!("Func.new_async requires an Engine created with async_support: true");
+        }
+
+        Self::define(store, params, results, args.block)
+    }
+
+    fn define(
+        store: Obj<Store>,
+        params: RArray,
+        results: RArray,
+        callable: Proc,
+    ) -> Result<Self, Error> {
         store.retain(callable.as_value());
 
         let context = store.context_mut();
         let engine = context.engine();
         let ty = wasmtime::FuncType::new(
             engine,
-            params.to_val_type_vec()?,
-            results.to_val_type_vec()?,
+            params.to_val_type_vec(engine)?,
+            results.to_val_type_vec(engine)?,
         );
-        let func_closure = make_func_closure(&ty, callable.into());
-        let inner = wasmtime::Func::new(context, ty, func_closure);
+        let inner = if context.data().is_async() {
+            let func_closure = make_async_func_closure(&ty, callable.into());
+            wasmtime::Func::new_async(context, ty, func_closure)
+        } else {
+            let func_closure = make_func_closure(&ty, callable.into());
+            wasmtime::Func::new(context, ty, func_closure)
+        };
 
         Ok(Self::from_inner(store.into(), inner))
     }
@@ -173,24 +255,95 @@ impl<'a> Func<'a> {
     /// @yard
     /// Calls a Wasm function.
     ///
-    /// @def call(*args)
+    /// @def call(*args, fuel: nil, gvl: true)
     /// @param args [Object]
     ///   The arguments to send to the Wasm function. Raises if the arguments do
     ///   not conform to the Wasm function's parameters.
+    /// @param fuel [Integer, nil]
+    ///   The fuel budget for this call only. The {Store}'s fuel is set to
+    ///   +fuel+ for the call and restored afterwards; the fuel the call consumes
+    ///   is still counted in {Store#fuel_consumed}. Requires +consume_fuel+ to
+    ///   be enabled on the {Engine}.
+    /// @param gvl [Boolean]
+    ///   When +false+, releases the GVL while Wasm runs so other Ruby threads
+    ///   make progress. Host functions and {Store} callbacks re-acquire the GVL
+    ///   before running Ruby code. Defaults to +true+, or to +false+ for
+    ///   functions returned by +to_func(gvl: false)+.
+    ///
+    ///   Each thread must use its own {Store}: calling into a {Store} from
+    ///   several threads with +gvl: false+ is undefined behavior.
     ///
     /// @return [nil, Object, Array<Object>] The return type depends on the function's results arity:
     ///   * 0 => +nil+
     ///   * 1 => +Object+
     ///   * > 1 => +Array<Object>+
+    ///
+    ///   When +fuel+ is given, returns a 2-element +Array+ of the above and
+    ///   the unused fuel instead.
     /// @example
     ///   store = Wasmtime::Store.new(Wasmtime::Engine.new)
     ///   func = Wasmtime::Func.new(store, [:i32, :i32], [:i32, :i32]) do |_caller, arg1, arg2|
     ///     [arg1.succ, arg2.succ]
     ///   end
     ///   func.call(1, 2) # => [2, 3]
+    ///   func.call(1, 2, fuel: 1_000) # => [[2, 3], 1_000]
+    ///   func.call(1, 2, gvl: false) # => [2, 3]
     pub fn call(&self, args: &[Value]) -> Result<Value, Error> {
         let ruby = Ruby::get().unwrap();
-        Self::invoke(&ruby, &self.store, &self.inner, self.gvl, args)
+        let args = scan_args::<(), (), RArray, (), RHash, ()>(args)?;
+        let (fuel, gvl) = Self::call_kwargs(args.keywords)?;
+        let params = args.splat.to_vec::<Value>()?;
+
+        let gvl = gvl.unwrap_or(self.gvl);
+        Self::invoke(&ruby, &self.store, &self.inner, gvl, &params, fuel)
+    }
+
+    /// Extracts the +fuel:+ and +gvl:+ keywords accepted by {Func#call} and
+    /// {Instance#invoke}.
+    pub fn call_kwargs(keywords: RHash) -> Result<(Option<u64>, Option<bool>), Error> {
+        let kw =
+            get_kwargs::<_, (), (Option<u64>, Option<bool>), ()>(keywords, &[], &[*FUEL, *GVL])?;
+        Ok(kw.optional)
+    }
+
+    /// Extracts the +fuel:+ keyword accepted by {Func#call_async} and
+    /// {Instance#invoke_async}.
+    pub fn fuel_kwarg(keywords: RHash) -> Result<Option<u64>, Error> {
+        let kw = get_kwargs::<_, (), (Option<u64>,), ()>(keywords, &[], &[*FUEL])?;
+        Ok(kw.optional.0)
+    }
+
+    /// @yard
+    /// Calls a Wasm function on a fiber, with an {Engine} created with
+    /// +async_support: true+.
+    ///
+    /// The calling Ruby thread is blocked until the call completes, but
+    /// control is given to the current +Fiber.scheduler+ whenever Wasm yields,
+    /// see {Store#epoch_deadline_async_yield_and_update} and
+    /// {Store#fuel_async_yield_interval=}. Host functions run on the calling
+    /// Ruby fiber, and non-blocking IO in them also yields to the scheduler.
+    ///
+    /// {Store#on_epoch_deadline}, {Store#call_hook} and
+    /// {Store#resource_limiter=} are not supported during async calls.
+    ///
+    /// @def call_async(*args, fuel: nil)
+    /// @param args (see Func#call)
+    /// @param fuel (see Func#call)
+    /// @return (see Func#call)
+    /// @raise [Error] if the {Engine} is not configured for async execution
+    /// @example
+    ///   engine = Wasmtime::Engine.new(async_support: true, epoch_interruption: true)
+    ///   store = Wasmtime::Store.new(engine)
+    ///   store.epoch_deadline_async_yield_and_update(1)
+    ///   engine.start_epoch_interval(10)
+    ///   Fiber.schedule { instance.export("run").to_func.call_async }
+    pub fn call_async(&self, args: &[Value]) -> Result<Value, Error> {
+        let ruby = Ruby::get().unwrap();
+        let args = scan_args::<(), (), RArray, (), RHash, ()>(args)?;
+        let fuel = Self::fuel_kwarg(args.keywords)?;
+        let params = args.splat.to_vec::<Value>()?;
+
+        Self::invoke_async(&ruby, &self.store, &self.inner, &params, fuel)
     }
 
     pub fn inner(&self) -> &FuncImpl {
@@ -198,25 +351,25 @@ impl<'a> Func<'a> {
     }
 
     /// @yard
-    /// @return [Array<Symbol>] The function's parameter types.
+    /// @return [Array<Symbol, RefType>] The function's parameter types.
     pub fn params(ruby: &Ruby, rb_self: Obj<Self>) -> Result<RArray, Error> {
         let ty = rb_self.inner.ty(rb_self.store.context()?);
         let len = ty.params().len();
         let mut params = ty.params();
         params.try_fold(ruby.ary_new_capa(len), |array, p| {
-            array.push(p.to_sym()?)?;
+            array.push(p.to_ruby_type(ruby)?)?;
             Ok(array)
         })
     }
 
     /// @yard
-    /// @return [Array<Symbol>] The function's result types.
+    /// @return [Array<Symbol, RefType>] The function's result types.
     pub fn results(ruby: &Ruby, rb_self: Obj<Self>) -> Result<RArray, Error> {
         let ty = rb_self.inner.ty(rb_self.store.context()?);
         let len = ty.results().len();
         let mut results = ty.results();
         results.try_fold(ruby.ary_new_capa(len), |array, r| {
-            array.push(r.to_sym()?)?;
+            array.push(r.to_ruby_type(ruby)?)?;
             Ok(array)
         })
     }
@@ -227,16 +380,74 @@ impl<'a> Func<'a> {
         func: &wasmtime::Func,
         gvl: bool,
         args: &[Value],
+        fuel: Option<u64>,
+    ) -> Result<Value, Error> {
+        if store.context()?.data().is_async() {
+            return err!("Func#call is not available with async engines, use Func#call_async");
+        }
+
+        Self::invoke_with(ruby, store, func, args, fuel, |context, params, results| {
+            if gvl {
+                Ok(func.call(context, params, results))
+            } else {
+                Ok(nogvl(|| func.call(context, params, results)))
+            }
+        })
+    }
+
+    pub fn invoke_async(
+        ruby: &Ruby,
+        store: &StoreContextValue,
+        func: &wasmtime::Func,
+        args: &[Value],
+        fuel: Option<u64>,
+    ) -> Result<Value, Error> {
+        let context = store.context()?;
+        if !context.data().is_async() {
+            return err!("Func#call_async requires an Engine created with async_support: true");
+        }
+        context.data().check_async_callbacks()?;
+
+        Self::invoke_with(ruby, store, func, args, fuel, |context, params, results| {
+            block_on(ruby, func.call_async(context, params, results))
+        })
+    }
+
+    fn invoke_with(
+        ruby: &Ruby,
+        store: &StoreContextValue,
+        func: &wasmtime::Func,
+        args: &[Value],
+        fuel: Option<u64>,
+        call: impl FnOnce(
+            &mut StoreContextMut<'_, StoreData>,
+            &[Val],
+            &mut [Val],
+        ) -> Result<wasmtime::Result<()>, Error>,
     ) -> Result<Value, Error> {
         let mut context = store.context_mut()?;
         let func_ty = func.ty(&mut context);
         let params = Params::new(ruby, &func_ty, args)?.to_vec(ruby, store)?;
         let mut results = vec![Val::null_func_ref(); func_ty.results().len()];
 
-        let call_result = if gvl {
-            func.call(&mut context, &params, &mut results)
-        } else {
-            nogvl(|| func.call(&mut context, &params, &mut results))
+        let previous_fuel = match fuel {
+            Some(fuel) => {
+                let previous = context.get_fuel().map_err(|e| error!("{}", e))?;
+                store::set_fuel(&mut context, fuel).map_err(|e| error!("{}", e))?;
+                Some(previous)
+            }
+            None => None,
+        };
+
+        let call_result = call(&mut context, &params, &mut results)?;
+
+        let remaining_fuel = match previous_fuel {
+            Some(previous) => {
+                let remaining = context.get_fuel().map_err(|e| error!("{}", e))?;
+                store::set_fuel(&mut context, previous).map_err(|e| error!("{}", e))?;
+                Some(remaining)
+            }
+            None => None,
         };
         call_result.map_err(|e| store.handle_wasm_error(ruby, e))?;
 
@@ -245,17 +456,24 @@ impl<'a> Func<'a> {
             return Err(error);
         }
 
-        match results.as_slice() {
-            [] => Ok(().into_value_with(ruby)),
-            [result] => result.to_ruby_value(ruby, store),
+        let value = match results.as_slice() {
+            [] => ().into_value_with(ruby),
+            [result] => result.to_ruby_value(ruby, store)?,
             _ => {
                 let ary = ruby.ary_new_capa(results.len());
                 for result in results {
                     let val = result.to_ruby_value(ruby, store)?;
                     ary.push(val)?;
                 }
-                Ok(ary.into_value_with(ruby))
+                ary.into_value_with(ruby)
             }
+        };
+
+        match remaining_fuel {
+            Some(remaining) => Ok(ruby
+                .ary_new_from_values(&[value, remaining.into_value_with(ruby)])
+                .into_value_with(ruby)),
+            None => Ok(value),
         }
     }
 }
@@ -266,14 +484,27 @@ impl From<&Func<'_>> for wasmtime::Extern {
     }
 }
 
+// A buffer still locked at the end of the call must be reported over any
+// other error: it maps memory that may move or be freed once the call returns.
 macro_rules! caller_error {
     ($store:expr, $caller:expr, $error:expr) => {{
-        $store.set_last_error($error);
-        $caller.expire();
+        $store.set_last_error($caller.expire().err().unwrap_or($error));
         Err(wasmtime::Error::msg(""))
     }};
 }
 
+macro_rules! expire_caller {
+    ($store:expr, $caller:expr) => {{
+        match $caller.expire() {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                $store.set_last_error(error);
+                Err(wasmtime::Error::msg(""))
+            }
+        }
+    }};
+}
+
 macro_rules! result_error {
     ($store:expr, $caller:expr, $msg:expr) => {{
         let error = Error::new(result_error(), $msg);
@@ -281,6 +512,33 @@ macro_rules! result_error {
     }};
 }
 
+type HostFuture<'a> = Box<dyn Future<Output = wasmtime::Result<()>> + Send + 'a>;
+
+/// Same as [`make_func_closure`], for async stores: the Ruby block runs on
+/// Ruby's stack while the Wasm fiber is suspended, see [`block_on`].
+pub fn make_async_func_closure(
+    ty: &wasmtime::FuncType,
+    callable: Opaque<Proc>,
+) -> impl for<'a> Fn(CallerImpl<'a, StoreData>, &'a [Val], &'a mut [Val]) -> HostFuture<'a>
+       + Send
+       + Sync
+       + 'static {
+    let func = Arc::new(make_func_closure(ty, callable));
+
+    async_host_func(move |caller, params, results| {
+        let func = func.clone();
+        Box::new(on_host_stack(move || func(caller, params, results)))
+    })
+}
+
+/// Ties the lifetimes of an async host function's arguments to its future.
+fn async_host_func<F>(func: F) -> F
+where
+    F: for<'a> Fn(CallerImpl<'a, StoreData>, &'a [Val], &'a mut [Val]) -> HostFuture<'a>,
+{
+    func
+}
+
 pub fn make_func_closure(
     ty: &wasmtime::FuncType,
     callable: Opaque<Proc>,
@@ -318,10 +576,7 @@ pub fn make_func_closure(
             let callable = ruby.get_inner(callable);
 
             match (callable.call(rparams), results.len()) {
-                (Ok(_proc_result), 0) => {
-                    wrapped_caller.expire();
-                    Ok(())
-                }
+                (Ok(_proc_result), 0) => expire_caller!(store_context, wrapped_caller),
                 (Ok(proc_result), n) => {
                     // For len=1, accept both `val` and `[val]`
                     let Ok(proc_result) = RArray::to_ary(proc_result) else {
@@ -363,8 +618,7 @@ pub fn make_func_closure(
                         }
                     }
 
-                    wrapped_caller.expire();
-                    Ok(())
+                    expire_caller!(store_context, wrapped_caller)
                 }
                 (Err(e), _) => {
                     caller_error!(store_context, wrapped_caller, e)
@@ -376,12 +630,15 @@ pub fn make_func_closure(
 
 pub fn init(ruby: &Ruby) -> Result<(), Error> {
     let func_type = root().define_class("FuncType", ruby.class_object())?;
+    func_type.define_singleton_method("new", function!(FuncType::new, 3))?;
     func_type.define_method("params", method!(FuncType::params, 0))?;
     func_type.define_method("results", method!(FuncType::results, 0))?;
 
     let func = root().define_class("Func", ruby.class_object())?;
     func.define_singleton_method("new", function!(Func::new, -1))?;
+    func.define_singleton_method("new_async", function!(Func::new_async, -1))?;
     func.define_method("call", method!(Func::call, -1))?;
+    func.define_method("call_async", method!(Func::call_async, -1))?;
     func.define_method("params", method!(Func::params, 0))?;
     func.define_method("results", method!(Func::results, 0))?;
 
@@ -1,12 +1,12 @@
 use super::{
-    convert::{ToRubyValue, ToSym, ToValType, ToWasmVal},
+    convert::{ToRubyType, ToRubyValue, ToValType, ToWasmVal},
     root,
     store::{Store, StoreContextValue},
 };
 use crate::error;
 use magnus::{
     class, function, gc::Marker, method, prelude::*, typed_data::Obj, DataTypeFunctions, Error,
-    Object, Ruby, Symbol, TypedData, Value,
+    Object, Ruby, TypedData, Value,
 };
 use wasmtime::{Extern, Global as GlobalImpl, Mutability};
 
@@ -46,9 +46,9 @@ impl GlobalType {
 
     /// @yard
     /// @def type
-    /// @return [Symbol] The Wasm type of the global‘s content.
-    pub fn type_(&self) -> Result<Symbol, Error> {
-        self.inner.content().to_sym()
+    /// @return [Symbol, RefType] The Wasm type of the global‘s content.
+    pub fn type_(ruby: &Ruby, rb_self: &Self) -> Result<Value, Error> {
+        rb_self.inner.content().to_ruby_type(ruby)
     }
 }
 
@@ -79,30 +79,30 @@ impl<'a> Global<'a> {
     /// @yard
     /// @def const(store, type, default)
     /// @param store [Store]
-    /// @param type [Symbol] The WebAssembly type of the value held by this global.
+    /// @param type [Symbol, RefType] The WebAssembly type of the value held by this global.
     /// @param default [Object] The default value of this global.
     /// @return [Global] A constant global.
-    pub fn const_(store: Obj<Store>, value_type: Symbol, default: Value) -> Result<Self, Error> {
+    pub fn const_(store: Obj<Store>, value_type: Value, default: Value) -> Result<Self, Error> {
         Self::new(store, value_type, default, Mutability::Const)
     }
 
     /// @yard
     /// @def var(store, type, default:)
     /// @param store [Store]
-    /// @param type [Symbol] The WebAssembly type of the value held by this global.
+    /// @param type [Symbol, RefType] The WebAssembly type of the value held by this global.
     /// @param default [Object] The default value of this global.
     /// @return [Global] A variable global.
-    pub fn var(store: Obj<Store>, value_type: Symbol, default: Value) -> Result<Self, Error> {
+    pub fn var(store: Obj<Store>, value_type: Value, default: Value) -> Result<Self, Error> {
         Self::new(store, value_type, default, Mutability::Var)
     }
 
     fn new(
         store: Obj<Store>,
-        value_type: Symbol,
+        value_type: Value,
         default: Value,
         mutability: Mutability,
     ) -> Result<Self, Error> {
-        let wasm_type = value_type.to_val_type()?;
+        let wasm_type = value_type.to_val_type(store.context().engine())?;
         let wasm_default = default.to_wasm_val(&store.into(), wasm_type.clone())?;
         let inner = GlobalImpl::new(
             store.context_mut(),
@@ -141,9 +141,9 @@ impl<'a> Global<'a> {
 
     /// @yard
     /// @def type
-    /// @return [Symbol] The Wasm type of the global‘s content.
-    pub fn type_(&self) -> Result<Symbol, Error> {
-        self.ty()?.content().to_sym()
+    /// @return [Symbol, RefType] The Wasm type of the global‘s content.
+    pub fn type_(ruby: &Ruby, rb_self: &Self) -> Result<Value, Error> {
+        rb_self.ty()?.content().to_ruby_type(ruby)
     }
 
     /// @yard
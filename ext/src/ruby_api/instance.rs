@@ -1,11 +1,12 @@
 use super::{
     convert::{ToExtern, WrapWasmtimeType},
     func::Func,
+    instance_snapshot::InstanceSnapshot,
     module::Module,
     root,
     store::{Store, StoreContextValue, StoreData},
 };
-use crate::err;
+use crate::{err, helpers::block_on};
 use magnus::{
     class, function, gc::Marker, method, prelude::*, scan_args, typed_data::Obj, DataTypeFunctions,
     Error, Object, RArray, RHash, RString, Ruby, TryConvert, TypedData, Value,
@@ -32,6 +33,9 @@ impl DataTypeFunctions for Instance {
 
 impl Instance {
     /// @yard
+    /// With an {Engine} created with +async_support: true+, the module's
+    /// start function runs on a fiber, like {Func#call_async}.
+    ///
     /// @def new(store, mod, imports = [])
     /// @param store [Store] The store to instantiate the module in.
     /// @param mod [Module] The module to instantiate.
@@ -63,7 +67,13 @@ impl Instance {
         };
 
         let module = module.get();
-        let inner = InstanceImpl::new(context, module, &imports)
+        let result = if context.data().is_async() {
+            context.data().check_async_callbacks()?;
+            block_on(ruby, InstanceImpl::new_async(context, module, &imports))?
+        } else {
+            InstanceImpl::new(context, module, &imports)
+        };
+        let inner = result
             .map_err(|e| StoreContextValue::from(wrapped_store).handle_wasm_error(ruby, e))?;
 
         Ok(Self {
@@ -125,21 +135,86 @@ impl Instance {
     /// Retrieves a Wasm function from the instance and calls it.
     /// Essentially a shortcut for +instance.export(name).call(...)+.
     ///
-    /// @def invoke(name, *args)
+    /// @def invoke(name, *args, fuel: nil, gvl: true)
     /// @param name [String] The name of function  to run.
     /// @param (see Func#call)
     /// @return (see Func#call)
     /// @see Func#call
     pub fn invoke(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<Value, Error> {
-        let name = RString::try_convert(*args.first().ok_or_else(|| {
-            Error::new(
-                ruby.exception_type_error(),
-                "wrong number of arguments (given 0, expected 1+)",
-            )
-        })?)?;
+        let args = scan_args::scan_args::<(RString,), (), RArray, (), RHash, ()>(args)?;
+        let (name,) = args.required;
+        let (fuel, gvl) = Func::call_kwargs(args.keywords)?;
+        let params = args.splat.to_vec::<Value>()?;
 
         let func = rb_self.get_func(rb_self.store.context_mut(), unsafe { name.as_str()? })?;
-        Func::invoke(ruby, &rb_self.store.into(), &func, true, &args[1..])
+        let gvl = gvl.unwrap_or(true);
+        Func::invoke(ruby, &rb_self.store.into(), &func, gvl, &params, fuel)
+    }
+
+    /// @yard
+    /// Retrieves a Wasm function from the instance and calls it on a fiber.
+    /// Essentially a shortcut for +instance.export(name).to_func.call_async(...)+.
+    ///
+    /// @def invoke_async(name, *args, fuel: nil)
+    /// @param name [String] The name of function  to run.
+    /// @param (see Func#call_async)
+    /// @return (see Func#call_async)
+    /// @see Func#call_async
+    pub fn invoke_async(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<Value, Error> {
+        let args = scan_args::scan_args::<(RString,), (), RArray, (), RHash, ()>(args)?;
+        let (name,) = args.required;
+        let fuel = Func::fuel_kwarg(args.keywords)?;
+        let params = args.splat.to_vec::<Value>()?;
+
+        let func = rb_self.get_func(rb_self.store.context_mut(), unsafe { name.as_str()? })?;
+        Func::invoke_async(ruby, &rb_self.store.into(), &func, &params, fuel)
+    }
+
+    /// @yard
+    /// Captures the state of the instance's exported memories, mutable
+    /// globals and tables, to roll the instance back to it later with
+    /// {#restore}. This lets a single instance serve many isolated requests
+    /// without being re-instantiated.
+    ///
+    /// Only state reachable through the instance's exports is captured:
+    /// non-exported memories, globals and tables, shared memories, WASI state
+    /// and {Store#data} are left as is. Exported memories are copied in full,
+    /// so taking and restoring a snapshot costs time and space proportional
+    /// to their size.
+    ///
+    /// @return [InstanceSnapshot]
+    /// @raise [Error] if an exported global or table holds GC references
+    ///   other than +funcref+ or +externref+.
+    /// @example
+    ///   instance = Wasmtime::Instance.new(store, mod)
+    ///   snapshot = instance.snapshot
+    ///   requests.each do |request|
+    ///     instance.invoke("handle", request)
+    ///     instance.restore(snapshot)
+    ///   end
+    pub fn snapshot(ruby: &Ruby, rb_self: Obj<Self>) -> Result<InstanceSnapshot, Error> {
+        InstanceSnapshot::capture(ruby, rb_self.as_value(), rb_self.inner, rb_self.store)
+    }
+
+    /// @yard
+    /// Rolls the instance back to a snapshot taken with {#snapshot}.
+    ///
+    /// Memories are copied back in place. Memories and tables that grew since
+    /// the snapshot keep their size, but their new pages and elements are
+    /// zeroed and nulled.
+    ///
+    /// Ruby objects retained by the {Store}, e.g. host functions or values
+    /// set with {Global#set}, are not released: Wasm or Ruby code may still
+    /// reference them. They live as long as the {Store}. The snapshot's own
+    /// +externref+ values are retained on its first restore only, so that
+    /// restoring it repeatedly doesn't grow the {Store}.
+    ///
+    /// @def restore(snapshot)
+    /// @param snapshot [InstanceSnapshot]
+    /// @return [nil]
+    /// @raise [Error] if +snapshot+ was taken from another instance.
+    pub fn restore(rb_self: Obj<Self>, snapshot: &InstanceSnapshot) -> Result<(), Error> {
+        snapshot.restore(rb_self.as_value(), rb_self.store)
     }
 
     fn get_func(
@@ -162,8 +237,11 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
 
     class.define_singleton_method("new", function!(Instance::new, -1))?;
     class.define_method("invoke", method!(Instance::invoke, -1))?;
+    class.define_method("invoke_async", method!(Instance::invoke_async, -1))?;
     class.define_method("exports", method!(Instance::exports, 0))?;
     class.define_method("export", method!(Instance::export, 1))?;
+    class.define_method("snapshot", method!(Instance::snapshot, 0))?;
+    class.define_method("restore", method!(Instance::restore, 1))?;
 
     Ok(())
 }
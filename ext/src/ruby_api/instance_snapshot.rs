@@ -0,0 +1,208 @@
+use super::{
+    convert::{ToRubyValue, ToWasmVal},
+    root,
+    store::{Store, StoreContextValue},
+};
+use crate::{err, error};
+use magnus::{
+    gc::Marker, prelude::*, rb_sys::AsRawValue, typed_data::Obj, DataTypeFunctions, Error, Ruby,
+    TypedData, Value,
+};
+use rb_sys::tracking_allocator::ManuallyTracked;
+use std::cell::Cell;
+use wasmtime::{
+    Extern, Global as GlobalImpl, Instance as InstanceImpl, Memory as MemoryImpl, Ref,
+    Table as TableImpl, Val, ValType,
+};
+
+/// A value captured in a snapshot. Externrefs are kept as their Ruby
+/// payload: rooted Wasm references can't outlive the current scope.
+enum SavedVal {
+    Wasm(Val),
+    ExternRef(Value),
+}
+
+impl SavedVal {
+    fn capture(ruby: &Ruby, store: &StoreContextValue, val: Val) -> Result<Self, Error> {
+        match val {
+            Val::ExternRef(Some(_)) => Ok(Self::ExternRef(val.to_ruby_value(ruby, store)?)),
+            Val::AnyRef(Some(_)) | Val::ExnRef(Some(_)) | Val::ContRef(Some(_)) => {
+                err!("cannot snapshot value: {val:?}")
+            }
+            val => Ok(Self::Wasm(val)),
+        }
+    }
+
+    fn to_val(&self, store: &StoreContextValue) -> Result<Val, Error> {
+        match self {
+            Self::Wasm(val) => Ok(val.clone()),
+            Self::ExternRef(value) => value.to_wasm_val(store, ValType::EXTERNREF),
+        }
+    }
+
+    fn extern_ref(&self) -> Option<Value> {
+        match self {
+            Self::Wasm(_) => None,
+            Self::ExternRef(value) => Some(*value),
+        }
+    }
+
+    fn to_ref(&self, store: &StoreContextValue) -> Result<Ref, Error> {
+        self.to_val(store)?
+            .ref_()
+            .ok_or_else(|| error!("expected a reference value"))
+    }
+
+    fn mark(&self, marker: &Marker) {
+        if let Self::ExternRef(value) = self {
+            marker.mark(*value);
+        }
+    }
+}
+
+/// @yard
+/// A copy of an {Instance}'s exported state, created with {Instance#snapshot}
+/// and restored with {Instance#restore}.
+#[derive(TypedData)]
+#[magnus(class = "Wasmtime::InstanceSnapshot", size, mark, free_immediately)]
+pub struct InstanceSnapshot {
+    instance: Value,
+    memories: Vec<(MemoryImpl, Vec<u8>)>,
+    globals: Vec<(GlobalImpl, SavedVal)>,
+    tables: Vec<(TableImpl, Vec<SavedVal>)>,
+    /// Whether the store retains the snapshot's externrefs, which it must
+    /// once they're restored: Wasm may keep them after the snapshot is gone.
+    retained: Cell<bool>,
+    _track_memory_usage: ManuallyTracked<()>,
+}
+
+// Needed for ManuallyTracked
+unsafe impl Send for InstanceSnapshot {}
+
+impl DataTypeFunctions for InstanceSnapshot {
+    fn mark(&self, marker: &Marker) {
+        marker.mark(self.instance);
+        for (_, val) in &self.globals {
+            val.mark(marker);
+        }
+        for (_, elements) in &self.tables {
+            elements.iter().for_each(|val| val.mark(marker));
+        }
+    }
+}
+
+impl InstanceSnapshot {
+    pub fn capture(
+        ruby: &Ruby,
+        instance: Value,
+        inner: InstanceImpl,
+        store: Obj<Store>,
+    ) -> Result<Self, Error> {
+        let store_context_value = StoreContextValue::from(store);
+        let mut context = store.context_mut();
+        let exports = inner
+            .exports(&mut context)
+            .map(|export| export.into_extern())
+            .collect::<Vec<_>>();
+
+        let mut memories = Vec::new();
+        let mut globals = Vec::new();
+        let mut tables = Vec::new();
+        for export in exports {
+            match export {
+                Extern::Memory(memory) => {
+                    memories.push((memory, memory.data(&context).to_vec()));
+                }
+                Extern::Global(global) if global.ty(&context).mutability().is_var() => {
+                    let val = global.get(&mut context);
+                    globals.push((global, SavedVal::capture(ruby, &store_context_value, val)?));
+                }
+                Extern::Table(table) => {
+                    let size = table.size(&context);
+                    let mut elements = Vec::with_capacity(size as usize);
+                    for index in 0..size {
+                        let element = table
+                            .get(&mut context, index)
+                            .ok_or_else(|| error!("table index out of bounds: {index}"))?;
+                        elements.push(SavedVal::capture(
+                            ruby,
+                            &store_context_value,
+                            element.into(),
+                        )?);
+                    }
+                    tables.push((table, elements));
+                }
+                _ => {}
+            }
+        }
+
+        let size = memories.iter().map(|(_, bytes)| bytes.len()).sum();
+        Ok(Self {
+            instance,
+            memories,
+            globals,
+            tables,
+            retained: Cell::new(false),
+            _track_memory_usage: ManuallyTracked::new(size),
+        })
+    }
+
+    pub fn restore(&self, instance: Value, store: Obj<Store>) -> Result<(), Error> {
+        if instance.as_raw() != self.instance.as_raw() {
+            return err!("snapshot was taken from another instance");
+        }
+
+        let store_context_value = StoreContextValue::from(store);
+        if !self.retained.replace(true) {
+            self.extern_refs().for_each(|value| store.retain(value));
+        }
+
+        let mut context = store.context_mut();
+        for (memory, bytes) in &self.memories {
+            let data = memory.data_mut(&mut context);
+            let (snapshotted, grown) = data.split_at_mut(bytes.len());
+            snapshotted.copy_from_slice(bytes);
+            grown.fill(0);
+        }
+
+        for (global, val) in &self.globals {
+            let val = val.to_val(&store_context_value)?;
+            global
+                .set(store.context_mut(), val)
+                .map_err(|e| error!("{e}"))?;
+        }
+
+        for (table, elements) in &self.tables {
+            for (index, element) in elements.iter().enumerate() {
+                let element = element.to_ref(&store_context_value)?;
+                table
+                    .set(store.context_mut(), index as u64, element)
+                    .map_err(|e| error!("{e}"))?;
+            }
+
+            let len = elements.len() as u64;
+            let grown = table.size(store.context()) - len;
+            let ty = table.ty(store.context());
+            if grown > 0 && ty.element().is_nullable() {
+                let null = Ref::null(ty.element().heap_type());
+                table
+                    .fill(store.context_mut(), len, null, grown)
+                    .map_err(|e| error!("{e}"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn extern_refs(&self) -> impl Iterator<Item = Value> + '_ {
+        let globals = self.globals.iter().map(|(_, val)| val);
+        let elements = self.tables.iter().flat_map(|(_, elements)| elements);
+        globals.chain(elements).filter_map(SavedVal::extern_ref)
+    }
+}
+
+pub fn init(ruby: &Ruby) -> Result<(), Error> {
+    root().define_class("InstanceSnapshot", ruby.class_object())?;
+
+    Ok(())
+}
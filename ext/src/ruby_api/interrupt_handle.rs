@@ -0,0 +1,57 @@
+use super::root;
+use magnus::{method, Error, Module as _, Ruby};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use wasmtime::Engine as EngineImpl;
+
+/// @yard
+/// A handle to interrupt Wasm running in a {Store}, obtained with
+/// {Store#interrupt_handle}. It is frozen and shareable across Ractors.
+#[magnus::wrap(
+    class = "Wasmtime::InterruptHandle",
+    free_immediately,
+    frozen_shareable
+)]
+pub struct InterruptHandle {
+    interrupted: Arc<AtomicBool>,
+    engine: EngineImpl,
+}
+
+impl InterruptHandle {
+    pub fn new(interrupted: Arc<AtomicBool>, engine: EngineImpl) -> Self {
+        Self {
+            interrupted,
+            engine,
+        }
+    }
+
+    /// @yard
+    /// Interrupts the Wasm running in the {Store}: it traps with
+    /// {Trap::INTERRUPT} at its next epoch check, typically the next loop
+    /// iteration or function entry. If no Wasm is running, the next call into
+    /// Wasm is interrupted instead. No epoch timer is needed.
+    ///
+    /// Only this handle's {Store} is interrupted, other {Store}s of the
+    /// {Engine} are unaffected: their epoch deadlines and timeouts don't
+    /// move. This method never needs the GVL, so it can be called from
+    /// another Ractor while Wasm runs.
+    /// @def interrupt!
+    /// @return [nil]
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::SeqCst);
+        // Wasmtime only checks for interruptions once its epoch passes the
+        // store's deadline, which is at most one tick away. Bumping Wasmtime's
+        // epoch alone, not the engine's tick count, makes the other stores'
+        // deadline callback run early and carry on, see `epoch_deadline_reached`.
+        self.engine.increment_epoch();
+    }
+}
+
+pub fn init(ruby: &Ruby) -> Result<(), Error> {
+    let class = root().define_class("InterruptHandle", ruby.class_object())?;
+    class.define_method("interrupt!", method!(InterruptHandle::interrupt, 0))?;
+
+    Ok(())
+}
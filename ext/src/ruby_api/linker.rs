@@ -9,7 +9,7 @@ use super::{
     root,
     store::{Store, StoreContextValue, StoreData},
 };
-use crate::{err, error, ruby_api::errors};
+use crate::{define_rb_intern, err, error, helpers::block_on, ruby_api::errors};
 use magnus::{
     block::Proc, class, function, gc::Marker, method, prelude::*, scan_args, scan_args::scan_args,
     typed_data::Obj, DataTypeFunctions, Error, Object, RArray, RHash, RString, Ruby, TypedData,
@@ -18,6 +18,10 @@ use magnus::{
 use std::cell::RefCell;
 use wasmtime::Linker as LinkerImpl;
 
+define_rb_intern!(
+    GVL => "gvl",
+);
+
 /// @yard
 /// @see https://docs.rs/wasmtime/latest/wasmtime/struct.Linker.html Wasmtime's Rust doc
 #[derive(TypedData)]
@@ -26,6 +30,7 @@ pub struct Linker {
     inner: RefCell<LinkerImpl<StoreData>>,
     refs: RefCell<Vec<Value>>,
     has_wasi: RefCell<bool>,
+    is_async: bool,
 }
 
 unsafe impl Send for Linker {}
@@ -47,6 +52,7 @@ impl Linker {
             inner: RefCell::new(inner),
             refs: Default::default(),
             has_wasi: RefCell::new(false),
+            is_async: engine.is_async(),
         })
     }
 
@@ -84,7 +90,7 @@ impl Linker {
     /// @param store [Store]
     /// @param mod [String] Module name
     /// @param name [String] Import name
-    /// @param item [Func, Memory] The item to define.
+    /// @param item [Func, Memory, SharedMemory, Table, Global] The item to define.
     /// @return [void]
     pub fn define(
         ruby: &Ruby,
@@ -110,19 +116,54 @@ impl Linker {
     }
 
     /// @yard
-    /// Define a function in this linker.
+    /// Define a function in this linker. With an {Engine} created with
+    /// +async_support: true+, this is the same as {#func_new_async}: Ruby
+    /// code can't run on the Wasm stack of async calls.
     ///
     /// @see Wasmtime::Func.new
     ///
     /// @def func_new(mod, name, params, results, &block)
     /// @param mod [String] Module name
     /// @param name [String] Import name
-    /// @param params [Array<Symbol>] The function's parameters.
-    /// @param results [Array<Symbol>] The function's results.
+    /// @param params [Array<Symbol, RefType>] The function's parameters.
+    /// @param results [Array<Symbol, RefType>] The function's results.
     /// @param block [Block] See {Func.new} for block argument details.
     /// @return [void]
     /// @see Func.new
     pub fn func_new(&self, args: &[Value]) -> Result<(), Error> {
+        self.define_func(args)
+    }
+
+    /// @yard
+    /// Define an async function in this linker. The Wasm stack is suspended
+    /// while the block runs, so the block may block on IO or +Fiber+
+    /// operations: with a +Fiber.scheduler+, other fibers keep running until
+    /// the block returns.
+    ///
+    /// Requires an {Engine} created with +async_support: true+; the linked
+    /// module must then be called with {Func#call_async}. Such calls are
+    /// driven within the Tokio runtime that runs {Engine#start_epoch_interval}.
+    ///
+    /// @def func_new_async(mod, name, params, results, &block)
+    /// @param mod [String] Module name
+    /// @param name [String] Import name
+    /// @param params [Array<Symbol, RefType>] The function's parameters.
+    /// @param results [Array<Symbol, RefType>] The function's results.
+    /// @param block [Block] See {Func.new} for block argument details.
+    /// @return [void]
+    /// @raise [Error] if the {Engine} is not configured for async execution
+    /// @see Func.new_async
+    pub fn func_new_async(&self, args: &[Value]) -> Result<(), Error> {
+        if !self.is_async {
+            return err!(
+                "Linker#func_new_async requires an Engine created with async_support: true"
+            );
+        }
+
+        self.define_func(args)
+    }
+
+    fn define_func(&self, args: &[Value]) -> Result<(), Error> {
         let args = scan_args::<(RString, RString, RArray, RArray), (), (), (), RHash, Proc>(args)?;
         let (module, name, params, results) = args.required;
         let callable = args.block;
@@ -130,22 +171,23 @@ impl Linker {
         let engine = inner_mut.engine();
         let ty = wasmtime::FuncType::new(
             engine,
-            params.to_val_type_vec()?,
-            results.to_val_type_vec()?,
+            params.to_val_type_vec(engine)?,
+            results.to_val_type_vec(engine)?,
         );
-        let func_closure = func::make_func_closure(&ty, callable.into());
+        let module = unsafe { module.as_str() }?;
+        let name = unsafe { name.as_str() }?;
 
         self.refs.borrow_mut().push(callable.as_value());
 
-        inner_mut
-            .func_new(
-                unsafe { module.as_str() }?,
-                unsafe { name.as_str() }?,
-                ty,
-                func_closure,
-            )
-            .map_err(|e| error!("{}", e))
-            .map(|_| ())
+        if self.is_async {
+            let func_closure = func::make_async_func_closure(&ty, callable.into());
+            inner_mut.func_new_async(module, name, ty, func_closure)
+        } else {
+            let func_closure = func::make_func_closure(&ty, callable.into());
+            inner_mut.func_new(module, name, ty, func_closure)
+        }
+        .map_err(|e| error!("{}", e))
+        .map(|_| ())
     }
 
     /// @yard
@@ -210,6 +252,10 @@ impl Linker {
     /// @param mod [Module]
     /// @return [void]
     pub fn module(&self, store: &Store, name: RString, module: &Module) -> Result<(), Error> {
+        if self.is_async {
+            return err!("Linker#module is not supported with async engines");
+        }
+
         self.inner
             .borrow_mut()
             .module(store.context_mut(), unsafe { name.as_str()? }, module.get())
@@ -262,6 +308,8 @@ impl Linker {
 
     /// @yard
     /// Instantiates a {Module} in a {Store} using the defined imports in the linker.
+    /// With an async {Engine}, the module's start function runs on a fiber,
+    /// like {Func#call_async}.
     /// @def instantiate(store, mod)
     /// @param store [Store]
     /// @param mod [Module]
@@ -276,10 +324,22 @@ impl Linker {
             return err!("{}", errors::missing_wasi_p1_ctx_error());
         }
 
-        rb_self
-            .inner
-            .borrow_mut()
-            .instantiate(store.context_mut(), module.get())
+        let result = if rb_self.is_async {
+            store.context().data().check_async_callbacks()?;
+            // Cloned so that host functions may use this linker during instantiation.
+            let inner = rb_self.inner.borrow().clone();
+            block_on(
+                ruby,
+                inner.instantiate_async(store.context_mut(), module.get()),
+            )?
+        } else {
+            rb_self
+                .inner
+                .borrow_mut()
+                .instantiate(store.context_mut(), module.get())
+        };
+
+        result
             .map_err(|e| StoreContextValue::from(store).handle_wasm_error(ruby, e))
             .map(|instance| {
                 rb_self
@@ -293,16 +353,28 @@ impl Linker {
 
     /// @yard
     /// Returns the “default export” of a module.
-    /// @def get_default(store, mod)
+    /// @def get_default(store, mod, gvl: true)
     /// @param store [Store]
     /// @param mod [String] Module name
+    /// @param gvl [Boolean] When +false+, the returned {Func} releases the GVL
+    ///   during calls, see {Func#call}.
     /// @return [Func]
-    pub fn get_default(&self, store: Obj<Store>, module: RString) -> Result<Func<'_>, Error> {
-        self.inner
+    pub fn get_default(&self, args: &[Value]) -> Result<Func<'_>, Error> {
+        let args = scan_args::<(Obj<Store>, RString), (), (), (), RHash, ()>(args)?;
+        let kw = scan_args::get_kwargs::<_, (), (Option<bool>,), ()>(args.keywords, &[], &[*GVL])?;
+        let (store, module) = args.required;
+
+        let func = self
+            .inner
             .borrow()
             .get_default(store.context_mut(), unsafe { module.as_str() }?)
             .map(|func| Func::from_inner(store.into(), func))
-            .map_err(|e| error!("{}", e))
+            .map_err(|e| error!("{}", e))?;
+
+        match kw.optional.0 {
+            Some(false) => Ok(func.without_gvl()),
+            _ => Ok(func),
+        }
     }
 
     /// @yard
@@ -336,13 +408,14 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     )?;
     class.define_method("define", method!(Linker::define, 4))?;
     class.define_method("func_new", method!(Linker::func_new, -1))?;
+    class.define_method("func_new_async", method!(Linker::func_new_async, -1))?;
     class.define_method("get", method!(Linker::get, 3))?;
     class.define_method("instance", method!(Linker::instance, 3))?;
     class.define_method("module", method!(Linker::module, 3))?;
     class.define_method("alias", method!(Linker::alias, 4))?;
     class.define_method("alias_module", method!(Linker::alias_module, 2))?;
     class.define_method("instantiate", method!(Linker::instantiate, 2))?;
-    class.define_method("get_default", method!(Linker::get_default, 2))?;
+    class.define_method("get_default", method!(Linker::get_default, -1))?;
     class.define_method(
         "use_deterministic_scheduling_functions",
         method!(Linker::use_deterministic_scheduling_functions, 0),
@@ -1,24 +1,37 @@
+mod element_type;
+mod io_buffer;
+mod memory_io;
+mod shared_memory;
 mod unsafe_slice;
 
+use self::element_type::{ElementType, Endian, Field};
+pub use self::io_buffer::IoBuffers;
+use self::memory_io::MemoryIO;
+pub use self::shared_memory::SharedMemory;
 use self::unsafe_slice::UnsafeSlice;
 use super::{
     root,
     store::{Store, StoreContextValue},
 };
-use crate::{define_rb_intern, error};
+use crate::{define_rb_intern, err, error};
 use magnus::{
-    class, function, gc::Marker, method, r_string::RString, scan_args, typed_data::Obj,
+    class, function, gc::Marker, method, prelude::*, r_string::RString, scan_args, typed_data::Obj,
     DataTypeFunctions, Error, Module as _, Object, Ruby, TypedData, Value,
 };
 
 use rb_sys::tracking_allocator::ManuallyTracked;
 use wasmtime::{Extern, Memory as MemoryImpl};
 
-const WASM_PAGE_SIZE: u32 = wasmtime_environ::Memory::DEFAULT_PAGE_SIZE;
-
 define_rb_intern!(
     MIN_SIZE => "min_size",
     MAX_SIZE => "max_size",
+    MEMORY64 => "memory64",
+    PAGE_SIZE_LOG2 => "page_size_log2",
+    WRITABLE => "writable",
+    ENDIAN => "endian",
+    LOSSY => "lossy",
+    PACKED => "packed",
+    FROM => "from",
 );
 
 #[derive(TypedData)]
@@ -52,6 +65,30 @@ impl MemoryType {
     pub fn max_size(&self) -> Option<u64> {
         self.inner.maximum()
     }
+
+    /// @yard
+    /// @return [Boolean] Whether the memory is shared between threads.
+    pub fn is_shared(&self) -> bool {
+        self.inner.is_shared()
+    }
+
+    /// @yard
+    /// @return [Boolean] Whether the memory is indexed with 64-bit addresses (memory64).
+    pub fn is_64(&self) -> bool {
+        self.inner.is_64()
+    }
+
+    /// @yard
+    /// @return [Integer] The size of a page in bytes.
+    pub fn page_size(&self) -> u64 {
+        self.inner.page_size()
+    }
+
+    /// @yard
+    /// @return [Integer] The base-2 logarithm of the page size.
+    pub fn page_size_log2(&self) -> u8 {
+        self.inner.page_size_log2()
+    }
 }
 
 impl From<&MemoryType> for wasmtime::ExternType {
@@ -80,22 +117,40 @@ unsafe impl Send for Memory<'_> {}
 
 impl<'a> Memory<'a> {
     /// @yard
-    /// @def new(store, min_size:, max_size: nil)
+    /// @def new(store, min_size:, max_size: nil, memory64: false, page_size_log2: 16)
     /// @param store [Store]
     /// @param min_size [Integer] The minimum memory pages.
     /// @param max_size [Integer, nil] The maximum memory pages.
+    /// @param memory64 [Boolean] Whether the memory is indexed with 64-bit
+    ///   addresses, requires an {Engine} with +wasm_memory64: true+.
+    /// @param page_size_log2 [Integer] The base-2 logarithm of the page size:
+    ///   +16+ (64KiB, the default) or +0+ (1 byte), which requires an {Engine}
+    ///   with +wasm_custom_page_sizes: true+.
     pub fn new(args: &[Value]) -> Result<Self, Error> {
         let args = scan_args::scan_args::<(Obj<Store>,), (), (), (), _, ()>(args)?;
-        let kw = scan_args::get_kwargs::<_, (u32,), (Option<u32>,), ()>(
+        let kw = scan_args::get_kwargs::<
+            _,
+            (u64,),
+            (Option<Option<u64>>, Option<bool>, Option<u8>),
+            (),
+        >(
             args.keywords,
             &[*MIN_SIZE],
-            &[*MAX_SIZE],
+            &[*MAX_SIZE, *MEMORY64, *PAGE_SIZE_LOG2],
         )?;
         let (store,) = args.required;
         let (min,) = kw.required;
-        let (max,) = kw.optional;
+        let (max, memory64, page_size_log2) = kw.optional;
 
-        let memtype = wasmtime::MemoryType::new(min, max);
+        let mut builder = wasmtime::MemoryTypeBuilder::new();
+        builder
+            .min(min)
+            .max(max.flatten())
+            .memory64(memory64.unwrap_or(false));
+        if let Some(page_size_log2) = page_size_log2 {
+            builder.page_size_log2(page_size_log2);
+        }
+        let memtype = builder.build().map_err(|e| error!("{}", e))?;
 
         let inner = MemoryImpl::new(store.context_mut(), memtype).map_err(|e| error!("{}", e))?;
         let memsize = inner.data_size(store.context_mut());
@@ -178,6 +233,80 @@ impl<'a> Memory<'a> {
             .map(|s| ruby.str_new(s))
     }
 
+    /// @yard
+    /// Read +length+ UTF-16 code units starting at +offset+, as used by
+    /// JavaScript-style strings. Result is a UTF-8 encoded string.
+    ///
+    /// @def read_utf16(offset, length, endian: :little, lossy: false)
+    /// @param offset [Integer]
+    /// @param length [Integer] The number of 16-bit code units.
+    /// @param endian [Symbol] The byte order in memory, +:little+ or +:big+.
+    /// @param lossy [Boolean] Whether to replace unpaired surrogates with
+    ///   U+FFFD rather than raising. JavaScript strings may contain them.
+    /// @return [String] UTF-8 +String+ of the memory.
+    /// @raise [Wasmtime::Error] on unpaired surrogates, unless +lossy+.
+    pub fn read_utf16(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<RString, Error> {
+        let args = scan_args::scan_args::<(usize, usize), (), (), (), _, ()>(args)?;
+        let kw = scan_args::get_kwargs::<_, (), (Option<Value>, Option<bool>), ()>(
+            args.keywords,
+            &[],
+            &[*ENDIAN, *LOSSY],
+        )?;
+        let (offset, length) = args.required;
+        let (endian, lossy) = kw.optional;
+        let endian = Endian::from_kwarg(endian)?;
+
+        let data = rb_self.data()?;
+        let units = length
+            .checked_mul(2)
+            .and_then(|size| data.get(offset..)?.get(..size))
+            .ok_or_else(|| error!("out of bounds memory access"))?
+            .chunks_exact(2)
+            .map(|unit| match endian {
+                Endian::Little => u16::from_le_bytes([unit[0], unit[1]]),
+                Endian::Big => u16::from_be_bytes([unit[0], unit[1]]),
+            })
+            .collect::<Vec<_>>();
+
+        if lossy.unwrap_or(false) {
+            return Ok(ruby.str_new(&String::from_utf16_lossy(&units)));
+        }
+        String::from_utf16(&units)
+            .map(|s| ruby.str_new(&s))
+            .map_err(|e| error!("{}", e))
+    }
+
+    /// @yard
+    /// Write +value+ as UTF-16 starting at +offset+, without terminator.
+    ///
+    /// @def write_utf16(offset, value, endian: :little)
+    /// @param offset [Integer]
+    /// @param value [String]
+    /// @param endian [Symbol] The byte order in memory, +:little+ or +:big+.
+    /// @return [Integer] The number of 16-bit code units written.
+    pub fn write_utf16(rb_self: Obj<Self>, args: &[Value]) -> Result<usize, Error> {
+        let args = scan_args::scan_args::<(usize, RString), (), (), (), _, ()>(args)?;
+        let kw =
+            scan_args::get_kwargs::<_, (), (Option<Value>,), ()>(args.keywords, &[], &[*ENDIAN])?;
+        let (offset, value) = args.required;
+        let endian = Endian::from_kwarg(kw.optional.0)?;
+
+        let bytes = value
+            .to_string()?
+            .encode_utf16()
+            .flat_map(|unit| match endian {
+                Endian::Little => unit.to_le_bytes(),
+                Endian::Big => unit.to_be_bytes(),
+            })
+            .collect::<Vec<_>>();
+
+        rb_self
+            .get_wasmtime_memory()
+            .write(rb_self.store.context_mut()?, offset, &bytes)
+            .map_err(|e| error!("{}", e))?;
+        Ok(bytes.len() / 2)
+    }
+
     /// @yard
     /// Read +size+ bytes starting at +offset+ into an {UnsafeSlice}. This
     /// provides a way to read a slice of memory without copying the underlying
@@ -211,6 +340,133 @@ impl<'a> Memory<'a> {
         Ok(ruby.obj_wrap(UnsafeSlice::new(rb_self, offset..(offset + size))?))
     }
 
+    /// @yard
+    /// Opens +length+ bytes of the memory starting at +offset+ as an IO-like
+    /// object, to stream them to and from Ruby libraries expecting an IO.
+    ///
+    /// @def open(offset = 0, length = nil)
+    /// @param offset [Integer]
+    /// @param length [Integer, nil] Defaults to the rest of the memory.
+    /// @return [Wasmtime::MemoryIO]
+    /// @example Decompress gzipped data from memory
+    ///   Zlib::GzipReader.new(memory.open(ptr, len)).read
+    pub fn open(
+        ruby: &Ruby,
+        rb_self: Obj<Self>,
+        args: &[Value],
+    ) -> Result<Obj<MemoryIO<'a>>, Error> {
+        let args =
+            scan_args::scan_args::<(), (Option<usize>, Option<Option<usize>>), (), (), (), ()>(
+                args,
+            )?;
+        let (offset, length) = args.optional;
+        let offset = offset.unwrap_or(0);
+        let length = match length.flatten() {
+            Some(length) => length,
+            None => rb_self.data_size()?.saturating_sub(offset),
+        };
+
+        Ok(ruby.obj_wrap(MemoryIO::new(rb_self, offset, length)?))
+    }
+
+    /// @yard
+    /// Returns an +IO::Buffer+ mapping +length+ bytes of the memory starting
+    /// at +offset+, without copying them.
+    ///
+    /// Growing a memory may move it, so the buffer is released (see
+    /// +IO::Buffer#free+) before any memory of the store grows, be it from
+    /// Ruby or from Wasm. When the memory was obtained through a {Caller},
+    /// the buffer is also released at the end of the host call. A released
+    /// buffer is +null?+ and raises on access. Growth fails while the buffer
+    /// is locked, e.g. by an ongoing IO operation, and so does the host call
+    /// if the buffer is still locked when it returns.
+    ///
+    /// Slices of the buffer (+IO::Buffer#slice+) are not released: they must
+    /// not be used after the memory grows.
+    ///
+    /// @def to_io_buffer(offset = 0, length = nil, writable: false)
+    /// @param offset [Integer]
+    /// @param length [Integer, nil] The number of bytes, defaults to the rest
+    ///   of the memory.
+    /// @param writable [Boolean] Whether the buffer can be written to.
+    /// @return [IO::Buffer]
+    pub fn to_io_buffer(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<Value, Error> {
+        let args = scan_args::scan_args::<(), (Option<usize>, Option<usize>), (), (), _, ()>(args)?;
+        let kw =
+            scan_args::get_kwargs::<_, (), (Option<bool>,), ()>(args.keywords, &[], &[*WRITABLE])?;
+        let (offset, length) = args.optional;
+        let (writable,) = kw.optional;
+        let offset = offset.unwrap_or(0);
+        let length = match length {
+            Some(length) => length,
+            None => rb_self.data_size()?.saturating_sub(offset),
+        };
+
+        let ptr = rb_self.data_ptr(offset, length)?;
+        // SAFETY: the buffer is released before the memory grows.
+        let buffer = unsafe {
+            io_buffer::new(
+                ruby,
+                ptr,
+                length,
+                writable.unwrap_or(false),
+                rb_self.as_value(),
+            )
+        };
+        rb_self.store.track_io_buffer(buffer)?;
+        Ok(buffer)
+    }
+
+    /// @yard
+    /// Copies +length+ bytes starting at +offset+ into +buffer+, without
+    /// intermediate +String+.
+    ///
+    /// @def read_into(offset, buffer, length = buffer.size)
+    /// @param offset [Integer]
+    /// @param buffer [IO::Buffer] A writable buffer of at least +length+ bytes.
+    /// @param length [Integer]
+    /// @return [Integer] The number of bytes copied.
+    pub fn read_into(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<usize, Error> {
+        let args = scan_args::scan_args::<(usize, Value), (Option<usize>,), (), (), (), ()>(args)?;
+        let (offset, buffer) = args.required;
+        let (length,) = args.optional;
+        io_buffer::expect(ruby, buffer)?;
+        let length = match length {
+            Some(length) => length,
+            None => buffer.funcall("size", ())?,
+        };
+
+        rb_self.with_io_buffer(ruby, offset, length, false, |view| {
+            buffer.funcall::<_, _, Value>("copy", (view,))
+        })?;
+        Ok(length)
+    }
+
+    /// @yard
+    /// Copies +length+ bytes from +buffer+ into the memory starting at
+    /// +offset+, without intermediate +String+.
+    ///
+    /// @def write_from(offset, buffer, length = buffer.size)
+    /// @param offset [Integer]
+    /// @param buffer [IO::Buffer] A buffer of at least +length+ bytes.
+    /// @param length [Integer]
+    /// @return [Integer] The number of bytes copied.
+    pub fn write_from(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<usize, Error> {
+        let args = scan_args::scan_args::<(usize, Value), (Option<usize>,), (), (), (), ()>(args)?;
+        let (offset, buffer) = args.required;
+        let (length,) = args.optional;
+        io_buffer::expect(ruby, buffer)?;
+        let length = match length {
+            Some(length) => length,
+            None => buffer.funcall("size", ())?,
+        };
+
+        rb_self.with_io_buffer(ruby, offset, length, true, |view| {
+            view.funcall::<_, _, Value>("copy", (buffer, 0, length))
+        })?;
+        Ok(length)
+    }
+
     /// @yard
     /// Write +value+ starting at +offset+.
     ///
@@ -352,6 +608,153 @@ impl<'a> Memory<'a> {
         self.write_fixed(offset, value.to_le_bytes())
     }
 
+    /// @yard
+    /// Read +count+ elements of +type+ starting at +offset+.
+    ///
+    /// @def read_array(type, offset, count, endian: :little, packed: false)
+    /// @param type [Symbol] One of +:i8+, +:u8+, +:i16+, +:u16+, +:i32+,
+    ///   +:u32+, +:i64+, +:u64+, +:f32+ or +:f64+.
+    /// @param offset [Integer]
+    /// @param count [Integer]
+    /// @param endian [Symbol] The byte order in memory, +:little+ or +:big+.
+    /// @param packed [Boolean] Whether to return the elements' bytes as a
+    ///   binary +String+ instead of an +Array+, i.e. as if packed with
+    ///   +Array#pack+.
+    /// @return [Array<Integer, Float>, String]
+    ///
+    /// @example Read 3 little-endian +u32+ as an Array
+    ///   memory.read_array(:u32, 0, 3) # => [1, 2, 3]
+    pub fn read_array(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<Value, Error> {
+        let args = scan_args::scan_args::<(Value, usize, usize), (), (), (), _, ()>(args)?;
+        let kw = scan_args::get_kwargs::<_, (), (Option<Value>, Option<bool>), ()>(
+            args.keywords,
+            &[],
+            &[*ENDIAN, *PACKED],
+        )?;
+        let (ty, offset, count) = args.required;
+        let (endian, packed) = kw.optional;
+        let ty = ElementType::from_value(ty)?;
+        let endian = Endian::from_kwarg(endian)?;
+
+        let data = rb_self.data()?;
+        let bytes = count
+            .checked_mul(ty.size())
+            .and_then(|len| data.get(offset..)?.get(..len))
+            .ok_or_else(|| error!("out of bounds memory access"))?;
+
+        if packed.unwrap_or(false) {
+            Ok(ruby.str_from_slice(bytes).as_value())
+        } else {
+            ty.read_array(ruby, bytes, endian)
+                .map(|array| array.as_value())
+        }
+    }
+
+    /// @yard
+    /// Write +values+ as elements of +type+ starting at +offset+. Nothing is
+    /// written when a value can't be converted.
+    ///
+    /// @def write_array(type, offset, values, endian: :little)
+    /// @param type [Symbol] See {#read_array}.
+    /// @param offset [Integer]
+    /// @param values [Array<Integer, Float>, String] The values, or their
+    ///   bytes as a binary +String+ which is written as-is.
+    /// @param endian [Symbol] The byte order in memory, +:little+ or +:big+.
+    /// @return [void]
+    pub fn write_array(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<(), Error> {
+        let args = scan_args::scan_args::<(Value, usize, Value), (), (), (), _, ()>(args)?;
+        let kw =
+            scan_args::get_kwargs::<_, (), (Option<Value>,), ()>(args.keywords, &[], &[*ENDIAN])?;
+        let (ty, offset, values) = args.required;
+        let (endian,) = kw.optional;
+        let ty = ElementType::from_value(ty)?;
+        let endian = Endian::from_kwarg(endian)?;
+
+        let bytes = match RString::from_value(values) {
+            Some(string) => {
+                let bytes = unsafe { string.as_slice() }.to_vec();
+                if bytes.len() % ty.size() != 0 {
+                    return Err(Error::new(
+                        ruby.exception_arg_error(),
+                        format!(
+                            "string size ({}) is not a multiple of the element size ({})",
+                            bytes.len(),
+                            ty.size()
+                        ),
+                    ));
+                }
+                bytes
+            }
+            None => {
+                let values = RArray::try_convert(values)?;
+                let mut bytes = vec![0; values.len() * ty.size()];
+                for (value, out) in values.into_iter().zip(bytes.chunks_exact_mut(ty.size())) {
+                    ty.write(value, out, endian)?;
+                }
+                bytes
+            }
+        };
+
+        rb_self
+            .get_wasmtime_memory()
+            .write(rb_self.store.context_mut()?, offset, &bytes)
+            .map_err(|e| error!("{}", e))
+    }
+
+    /// @yard
+    /// Read a C struct starting at +offset+. Fields are laid out in order,
+    /// each aligned to the size of its type as C compilers do for Wasm
+    /// targets, unless +packed+ is true.
+    ///
+    /// @def read_struct(offset, layout, endian: :little, packed: false)
+    /// @param offset [Integer]
+    /// @param layout [Hash{Symbol => Symbol, Array(Symbol, Integer)}] Maps
+    ///   field names to either a type (see {#read_array}) or a
+    ///   +[type, count]+ pair for fixed-size arrays.
+    /// @param endian [Symbol] The byte order in memory, +:little+ or +:big+.
+    /// @param packed [Boolean] Whether fields are laid out without padding.
+    /// @return [Hash] The fields' values, keyed by name. Fixed-size arrays
+    ///   are returned as Arrays.
+    ///
+    /// @example Read a +struct { uint8_t tag; uint32_t ptr; uint32_t len; }+
+    ///   memory.read_struct(8, { tag: :u8, ptr: :u32, len: :u32 })
+    ///   # => { tag: 1, ptr: 1024, len: 5 }
+    pub fn read_struct(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<RHash, Error> {
+        let args = scan_args::scan_args::<(usize, RHash), (), (), (), _, ()>(args)?;
+        let kw = scan_args::get_kwargs::<_, (), (Option<Value>, Option<bool>), ()>(
+            args.keywords,
+            &[],
+            &[*ENDIAN, *PACKED],
+        )?;
+        let (offset, layout) = args.required;
+        let (endian, packed) = kw.optional;
+        let endian = Endian::from_kwarg(endian)?;
+        let packed = packed.unwrap_or(false);
+        let fields = Field::parse_layout(layout)?;
+
+        let data = rb_self.data()?.get(offset..).unwrap_or_default();
+        let result = ruby.hash_new();
+        let mut position = 0usize;
+        for field in fields {
+            if !packed {
+                position = position.next_multiple_of(field.ty().size());
+            }
+            let bytes = field
+                .size()
+                .and_then(|size| data.get(position..)?.get(..size))
+                .ok_or_else(|| error!("out of bounds memory access"))?;
+
+            let value = match field.count() {
+                Some(_) => field.ty().read_array(ruby, bytes, endian)?.as_value(),
+                None => field.ty().read(ruby, bytes, endian),
+            };
+            result.aset(field.name(), value)?;
+            position += bytes.len();
+        }
+
+        Ok(result)
+    }
+
     /// @yard
     /// Read a NUL-terminated C string starting at +offset+ as an ASCII-8BIT
     /// (binary) +String+.
@@ -403,6 +806,105 @@ impl<'a> Memory<'a> {
         Ok(())
     }
 
+    /// @yard
+    /// Copy +len+ bytes from +src+ to +dst+, like +memmove+: the ranges may
+    /// overlap.
+    ///
+    /// @def copy(dst, src, len)
+    /// @param dst [Integer]
+    /// @param src [Integer]
+    /// @param len [Integer]
+    /// @return [void]
+    pub fn copy(&self, dst: usize, src: usize, len: usize) -> Result<(), Error> {
+        let mut context = self.store.context_mut()?;
+        let data = self.get_wasmtime_memory().data_mut(&mut context);
+        let in_bounds =
+            |offset: usize| offset.checked_add(len).is_some_and(|end| end <= data.len());
+        if !in_bounds(src) || !in_bounds(dst) {
+            return err!("out of bounds memory access");
+        }
+
+        data.copy_within(src..src + len, dst);
+        Ok(())
+    }
+
+    /// @yard
+    /// Set +len+ bytes starting at +offset+ to +byte+, like +memset+.
+    ///
+    /// @def fill(offset, byte, len)
+    /// @param offset [Integer]
+    /// @param byte [Integer]
+    /// @param len [Integer]
+    /// @return [void]
+    pub fn fill(&self, offset: usize, byte: u8, len: usize) -> Result<(), Error> {
+        let mut context = self.store.context_mut()?;
+        self.get_wasmtime_memory()
+            .data_mut(&mut context)
+            .get_mut(offset..)
+            .and_then(|s| s.get_mut(..len))
+            .ok_or_else(|| error!("out of bounds memory access"))?
+            .fill(byte);
+        Ok(())
+    }
+
+    /// @yard
+    /// Compare +len+ bytes at +offset1+ and +offset2+, like +memcmp+.
+    ///
+    /// @def compare(offset1, offset2, len)
+    /// @param offset1 [Integer]
+    /// @param offset2 [Integer]
+    /// @param len [Integer]
+    /// @return [Integer] -1, 0 or 1 when the bytes at +offset1+ are
+    ///   respectively lower than, equal to or greater than those at +offset2+.
+    pub fn compare(&self, offset1: usize, offset2: usize, len: usize) -> Result<i32, Error> {
+        let data = self.data()?;
+        let slice = |offset: usize| data.get(offset..).and_then(|s| s.get(..len));
+        match (slice(offset1), slice(offset2)) {
+            (Some(a), Some(b)) => Ok(a.cmp(b) as i32),
+            _ => err!("out of bounds memory access"),
+        }
+    }
+
+    /// @yard
+    /// Find the first occurrence of +needle+ starting at +from+, like
+    /// +memchr+ or +memmem+.
+    ///
+    /// @def find(needle, from: 0)
+    /// @param needle [Integer, String] A byte or a sequence of bytes.
+    /// @param from [Integer] The offset to start searching at.
+    /// @return [Integer, nil] The offset of the occurrence, or +nil+ when
+    ///   not found.
+    pub fn find(&self, args: &[Value]) -> Result<Option<usize>, Error> {
+        let args = scan_args::scan_args::<(Value,), (), (), (), _, ()>(args)?;
+        let kw =
+            scan_args::get_kwargs::<_, (), (Option<usize>,), ()>(args.keywords, &[], &[*FROM])?;
+        let (needle,) = args.required;
+        let from = kw.optional.0.unwrap_or(0);
+
+        let haystack = self
+            .data()?
+            .get(from..)
+            .ok_or_else(|| error!("out of bounds memory access"))?;
+        let position = match RString::from_value(needle) {
+            Some(needle) => {
+                let needle = unsafe { needle.as_slice() };
+                if needle.is_empty() {
+                    Some(0)
+                } else {
+                    haystack
+                        .windows(needle.len())
+                        .position(|window| window == needle)
+                }
+            }
+            None => {
+                let byte = u8::try_convert(needle)?;
+                haystack.iter().position(|b| *b == byte)
+            }
+        };
+
+        Ok(position.map(|position| from + position))
+    }
+
     /// @yard
     /// Grows a memory by +delta+ pages.
     /// Raises if the memory grows beyond its limit.
@@ -411,13 +913,15 @@ impl<'a> Memory<'a> {
     /// @param delta [Integer] The number of pages to grow by.
     /// @return [Integer] The number of pages the memory had before being resized.
     pub fn grow(&self, delta: usize) -> Result<u64, Error> {
+        let ruby = Ruby::get().unwrap();
+        let page_size = self.get_wasmtime_memory().page_size(self.store.context()?);
         let ret = self
             .get_wasmtime_memory()
             .grow(self.store.context_mut()?, delta as _)
-            .map_err(|e| error!("{}", e));
+            .map_err(|e| self.store.handle_wasm_error(&ruby, e));
 
         self.inner
-            .increase_memory_usage(delta * (WASM_PAGE_SIZE as usize));
+            .increase_memory_usage(delta * (page_size as usize));
 
         ret
     }
@@ -438,6 +942,37 @@ impl<'a> Memory<'a> {
         self.inner.get()
     }
 
+    fn data_ptr(&self, offset: usize, length: usize) -> Result<*mut u8, Error> {
+        let mut context = self.store.context_mut()?;
+
+        self.get_wasmtime_memory()
+            .data_mut(&mut context)
+            .get_mut(offset..)
+            .and_then(|s| s.get_mut(..length))
+            .map(|s| s.as_mut_ptr())
+            .ok_or_else(|| error!("out of bounds memory access"))
+    }
+
+    /// Calls `f` with a short-lived +IO::Buffer+ mapping part of the memory.
+    /// `f` must only pass the view to `IO::Buffer` methods, see
+    /// [`io_buffer::expect`]: any other object could retain it.
+    fn with_io_buffer<T>(
+        &self,
+        ruby: &Ruby,
+        offset: usize,
+        length: usize,
+        writable: bool,
+        f: impl FnOnce(Value) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let ptr = self.data_ptr(offset, length)?;
+        // SAFETY: the buffer is freed before returning, and `f` can't grow
+        // the memory: copying between buffers doesn't call into Wasm.
+        let view = unsafe { io_buffer::new(ruby, ptr, length, writable, ruby.qnil().as_value()) };
+        let result = f(view);
+        io_buffer::free(view)?;
+        result
+    }
+
     fn data(&self) -> Result<&[u8], Error> {
         Ok(self.get_wasmtime_memory().data(self.store.context()?))
     }
@@ -470,6 +1005,10 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     let type_class = root().define_class("MemoryType", ruby.class_object())?;
     type_class.define_method("min_size", method!(MemoryType::min_size, 0))?;
     type_class.define_method("max_size", method!(MemoryType::max_size, 0))?;
+    type_class.define_method("shared?", method!(MemoryType::is_shared, 0))?;
+    type_class.define_method("is_64?", method!(MemoryType::is_64, 0))?;
+    type_class.define_method("page_size", method!(MemoryType::page_size, 0))?;
+    type_class.define_method("page_size_log2", method!(MemoryType::page_size_log2, 0))?;
 
     let class = root().define_class("Memory", ruby.class_object())?;
     class.define_singleton_method("new", function!(Memory::new, -1))?;
@@ -494,10 +1033,25 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     class.define_method("size", method!(Memory::size, 0))?;
     class.define_method("data_size", method!(Memory::data_size, 0))?;
     class.define_method("read_unsafe_slice", method!(Memory::read_unsafe_slice, 2))?;
+    class.define_method("open", method!(Memory::open, -1))?;
     class.define_method("read_cstring", method!(Memory::read_cstring, 1))?;
+    class.define_method("read_utf16", method!(Memory::read_utf16, -1))?;
+    class.define_method("write_utf16", method!(Memory::write_utf16, -1))?;
+    class.define_method("copy", method!(Memory::copy, 3))?;
+    class.define_method("fill", method!(Memory::fill, 3))?;
+    class.define_method("compare", method!(Memory::compare, 3))?;
+    class.define_method("find", method!(Memory::find, -1))?;
+    class.define_method("read_array", method!(Memory::read_array, -1))?;
+    class.define_method("write_array", method!(Memory::write_array, -1))?;
+    class.define_method("read_struct", method!(Memory::read_struct, -1))?;
+    class.define_method("to_io_buffer", method!(Memory::to_io_buffer, -1))?;
+    class.define_method("read_into", method!(Memory::read_into, -1))?;
+    class.define_method("write_from", method!(Memory::write_from, -1))?;
     class.define_method("write_cstring", method!(Memory::write_cstring, 2))?;
 
     unsafe_slice::init(ruby)?;
+    memory_io::init(ruby)?;
+    shared_memory::init(ruby)?;
 
     Ok(())
 }
@@ -0,0 +1,202 @@
+use crate::{define_rb_intern, helpers::SymbolEnum};
+use lazy_static::lazy_static;
+use magnus::{
+    prelude::*, r_hash::ForEach, Error, IntoValue, RArray, RHash, Ruby, TryConvert, Value,
+};
+
+define_rb_intern!(
+    I8 => "i8",
+    U8 => "u8",
+    I16 => "i16",
+    U16 => "u16",
+    I32 => "i32",
+    U32 => "u32",
+    I64 => "i64",
+    U64 => "u64",
+    F32 => "f32",
+    F64 => "f64",
+    LITTLE => "little",
+    BIG => "big",
+);
+
+lazy_static! {
+    static ref ELEMENT_TYPE_MAPPING: SymbolEnum<'static, ElementType> = {
+        let mapping = vec![
+            (*I8, ElementType::I8),
+            (*U8, ElementType::U8),
+            (*I16, ElementType::I16),
+            (*U16, ElementType::U16),
+            (*I32, ElementType::I32),
+            (*U32, ElementType::U32),
+            (*I64, ElementType::I64),
+            (*U64, ElementType::U64),
+            (*F32, ElementType::F32),
+            (*F64, ElementType::F64),
+        ];
+
+        SymbolEnum::new("type", mapping)
+    };
+    static ref ENDIAN_MAPPING: SymbolEnum<'static, Endian> = {
+        let mapping = vec![(*LITTLE, Endian::Little), (*BIG, Endian::Big)];
+
+        SymbolEnum::new(":endian", mapping)
+    };
+}
+
+#[derive(Clone, Copy)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    /// Reads an +endian:+ keyword argument, defaulting to little-endian like
+    /// Wasm itself.
+    pub fn from_kwarg(value: Option<Value>) -> Result<Self, Error> {
+        value.map_or(Ok(Self::Little), |value| ENDIAN_MAPPING.get(value))
+    }
+}
+
+/// The type of the elements read and written by {Memory#read_array} and
+/// friends.
+#[derive(Clone, Copy)]
+pub enum ElementType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+}
+
+macro_rules! decode {
+    ($ty:ty, $bytes:expr, $endian:expr) => {{
+        let bytes = $bytes.try_into().unwrap();
+        match $endian {
+            Endian::Little => <$ty>::from_le_bytes(bytes),
+            Endian::Big => <$ty>::from_be_bytes(bytes),
+        }
+    }};
+}
+
+macro_rules! encode {
+    ($ty:ty, $value:expr, $endian:expr, $out:expr) => {{
+        let value = <$ty>::try_convert($value)?;
+        let bytes = match $endian {
+            Endian::Little => value.to_le_bytes(),
+            Endian::Big => value.to_be_bytes(),
+        };
+        $out.copy_from_slice(&bytes);
+    }};
+}
+
+impl ElementType {
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        ELEMENT_TYPE_MAPPING.get(value)
+    }
+
+    /// The size in bytes, which is also the alignment.
+    pub fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::I64 | Self::U64 | Self::F64 => 8,
+        }
+    }
+
+    /// Decodes one element from `bytes`, which must be [`Self::size`] long.
+    pub fn read(self, ruby: &Ruby, bytes: &[u8], endian: Endian) -> Value {
+        match self {
+            Self::I8 => decode!(i8, bytes, endian).into_value_with(ruby),
+            Self::U8 => decode!(u8, bytes, endian).into_value_with(ruby),
+            Self::I16 => decode!(i16, bytes, endian).into_value_with(ruby),
+            Self::U16 => decode!(u16, bytes, endian).into_value_with(ruby),
+            Self::I32 => decode!(i32, bytes, endian).into_value_with(ruby),
+            Self::U32 => decode!(u32, bytes, endian).into_value_with(ruby),
+            Self::I64 => decode!(i64, bytes, endian).into_value_with(ruby),
+            Self::U64 => decode!(u64, bytes, endian).into_value_with(ruby),
+            Self::F32 => decode!(f32, bytes, endian).into_value_with(ruby),
+            Self::F64 => decode!(f64, bytes, endian).into_value_with(ruby),
+        }
+    }
+
+    /// Encodes `value` into `out`, which must be [`Self::size`] long.
+    pub fn write(self, value: Value, out: &mut [u8], endian: Endian) -> Result<(), Error> {
+        match self {
+            Self::I8 => encode!(i8, value, endian, out),
+            Self::U8 => encode!(u8, value, endian, out),
+            Self::I16 => encode!(i16, value, endian, out),
+            Self::U16 => encode!(u16, value, endian, out),
+            Self::I32 => encode!(i32, value, endian, out),
+            Self::U32 => encode!(u32, value, endian, out),
+            Self::I64 => encode!(i64, value, endian, out),
+            Self::U64 => encode!(u64, value, endian, out),
+            Self::F32 => encode!(f32, value, endian, out),
+            Self::F64 => encode!(f64, value, endian, out),
+        }
+        Ok(())
+    }
+
+    /// Decodes consecutive elements from `bytes` into an Array.
+    pub fn read_array(self, ruby: &Ruby, bytes: &[u8], endian: Endian) -> Result<RArray, Error> {
+        let array = ruby.ary_new_capa(bytes.len() / self.size());
+        for chunk in bytes.chunks_exact(self.size()) {
+            array.push(self.read(ruby, chunk, endian))?;
+        }
+        Ok(array)
+    }
+}
+
+/// A field of a struct layout given to {Memory#read_struct}: either a
+/// scalar or a fixed-size array.
+pub struct Field {
+    name: Value,
+    ty: ElementType,
+    count: Option<usize>,
+}
+
+impl Field {
+    /// Parses a layout Hash, mapping field names to either a type Symbol or
+    /// a +[type, count]+ Array.
+    pub fn parse_layout(layout: RHash) -> Result<Vec<Self>, Error> {
+        let mut fields = Vec::with_capacity(layout.len());
+        layout.foreach(|name: Value, ty: Value| {
+            let (ty, count) = match RArray::from_value(ty) {
+                Some(array) => {
+                    let (ty, count) = <(Value, usize)>::try_convert(array.as_value())?;
+                    (ty, Some(count))
+                }
+                None => (ty, None),
+            };
+            fields.push(Self {
+                name,
+                ty: ElementType::from_value(ty)?,
+                count,
+            });
+            Ok(ForEach::Continue)
+        })?;
+        Ok(fields)
+    }
+
+    pub fn name(&self) -> Value {
+        self.name
+    }
+
+    pub fn ty(&self) -> ElementType {
+        self.ty
+    }
+
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    /// The size in bytes of the field, `None` on overflow.
+    pub fn size(&self) -> Option<usize> {
+        self.ty.size().checked_mul(self.count.unwrap_or(1))
+    }
+}
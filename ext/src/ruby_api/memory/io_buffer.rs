@@ -0,0 +1,132 @@
+use crate::define_rb_intern;
+use magnus::{
+    gc::Marker,
+    prelude::*,
+    rb_sys::{AsRawId, AsRawValue, FromRawValue},
+    value::{IntoId, Lazy},
+    Error, RClass, RModule, Ruby, Value,
+};
+use rb_sys::{rb_ivar_set, VALUE};
+use std::ffi::{c_int, c_void};
+
+extern "C" {
+    fn rb_io_buffer_new(base: *mut c_void, size: usize, flags: c_int) -> VALUE;
+}
+
+// Values of `enum rb_io_buffer_flags`, which were renumbered in Ruby 3.2.
+#[cfg(ruby_gte_3_2)]
+const RB_IO_BUFFER_EXTERNAL: c_int = 1;
+#[cfg(ruby_gte_3_2)]
+const RB_IO_BUFFER_READONLY: c_int = 128;
+#[cfg(not(ruby_gte_3_2))]
+const RB_IO_BUFFER_EXTERNAL: c_int = 0;
+#[cfg(not(ruby_gte_3_2))]
+const RB_IO_BUFFER_READONLY: c_int = 64;
+
+define_rb_intern!(
+    IVAR_NAME => "__memory__",
+    SET => "[]=",
+);
+
+/// Wraps `len` bytes at `ptr` in an external `IO::Buffer`, which keeps
+/// `owner` alive.
+///
+/// # Safety
+///
+/// The bytes must remain valid until the buffer is freed, see [`free`].
+pub unsafe fn new(ruby: &Ruby, ptr: *mut u8, len: usize, writable: bool, owner: Value) -> Value {
+    let flags = match writable {
+        true => RB_IO_BUFFER_EXTERNAL,
+        false => RB_IO_BUFFER_EXTERNAL | RB_IO_BUFFER_READONLY,
+    };
+    let id = IVAR_NAME.into_id_with(ruby);
+    let buffer = rb_io_buffer_new(ptr as _, len, flags);
+    rb_ivar_set(buffer, id.as_raw(), owner.as_raw());
+
+    Value::from_raw(buffer)
+}
+
+/// Releases an `IO::Buffer`: it becomes null and raises on access. Raises
+/// when the buffer is locked.
+pub fn free(buffer: Value) -> Result<(), Error> {
+    buffer.funcall::<_, _, Value>("free", ()).map(|_| ())
+}
+
+fn io_buffer_class(ruby: &Ruby) -> RClass {
+    static CLASS: Lazy<RClass> = Lazy::new(|ruby| {
+        let io: RClass = ruby.class_object().const_get("IO").unwrap();
+        io.const_get("Buffer").unwrap()
+    });
+    ruby.get_inner(&CLASS)
+}
+
+/// Raises a +TypeError+ unless `value` is an `IO::Buffer`, whose methods
+/// can be trusted not to retain a view passed to them.
+pub fn expect(ruby: &Ruby, value: Value) -> Result<(), Error> {
+    if value.is_kind_of(io_buffer_class(ruby)) {
+        return Ok(());
+    }
+
+    Err(Error::new(
+        ruby.exception_type_error(),
+        format!("wrong argument type {} (expected IO::Buffer)", unsafe {
+            value.classname()
+        }),
+    ))
+}
+
+fn weak_map_class(ruby: &Ruby) -> RClass {
+    static CLASS: Lazy<RClass> = Lazy::new(|ruby| {
+        let object_space: RModule = ruby.class_object().const_get("ObjectSpace").unwrap();
+        object_space.const_get("WeakMap").unwrap()
+    });
+    ruby.get_inner(&CLASS)
+}
+
+/// The `IO::Buffer`s mapping a store's memories. They're held weakly so
+/// that dropped buffers can be collected.
+#[derive(Debug, Default)]
+pub struct IoBuffers {
+    buffers: Option<Value>,
+}
+
+impl IoBuffers {
+    pub fn track(&mut self, buffer: Value) -> Result<(), Error> {
+        let buffers = match self.buffers {
+            Some(buffers) => buffers,
+            None => {
+                let ruby = Ruby::get_with(buffer);
+                let buffers = weak_map_class(&ruby).new_instance(())?;
+                *self.buffers.insert(buffers)
+            }
+        };
+        buffers.funcall::<_, _, Value>(*SET, (buffer, true))?;
+        Ok(())
+    }
+
+    /// Frees all tracked buffers, and returns the first error if any of
+    /// them could not be freed. Buffers remain tracked until they are all
+    /// freed: freeing a buffer twice is harmless.
+    pub fn release(&mut self) -> Result<(), Error> {
+        let Some(buffers) = self.buffers else {
+            return Ok(());
+        };
+
+        let mut result = Ok(());
+        for buffer in buffers.funcall::<_, _, Vec<Value>>("keys", ())? {
+            if let Err(e) = free(buffer) {
+                result = result.and(Err(e));
+            }
+        }
+        if result.is_ok() {
+            self.buffers = None;
+        }
+        result
+    }
+
+    pub fn mark(&self, marker: &Marker) {
+        if let Some(buffers) = self.buffers {
+            marker.mark(buffers);
+        }
+    }
+}
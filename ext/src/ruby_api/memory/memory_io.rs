@@ -0,0 +1,414 @@
+use super::Memory;
+use crate::{define_rb_intern, err, error, helpers::SymbolEnum, root};
+use lazy_static::lazy_static;
+use magnus::{
+    gc::Marker, method, prelude::*, scan_args, typed_data::Obj, value::Opaque, DataTypeFunctions,
+    Error, ExceptionClass, Integer, RModule, RString, Ruby, TypedData, Value,
+};
+use std::cell::Cell;
+
+define_rb_intern!(
+    SET => "SET",
+    CUR => "CUR",
+    END => "END",
+    CHOMP => "chomp",
+);
+
+lazy_static! {
+    static ref WHENCE_MAPPING: SymbolEnum<'static, Whence> = {
+        let mapping = vec![
+            (*SET, Whence::Set),
+            (*CUR, Whence::Cur),
+            (*END, Whence::End),
+        ];
+
+        SymbolEnum::new("whence", mapping)
+    };
+}
+
+#[derive(Clone, Copy)]
+enum Whence {
+    Set,
+    Cur,
+    End,
+}
+
+impl Whence {
+    /// Accepts both +IO::SEEK_*+ constants and their Symbol equivalents.
+    fn from_value(value: Value) -> Result<Self, Error> {
+        match Integer::from_value(value) {
+            Some(whence) => match whence.to_i32()? {
+                0 => Ok(Self::Set),
+                1 => Ok(Self::Cur),
+                2 => Ok(Self::End),
+                _ => Err(WHENCE_MAPPING.error(value)),
+            },
+            None => WHENCE_MAPPING.get(value),
+        }
+    }
+}
+
+/// @yard
+/// @rename Wasmtime::MemoryIO
+/// An IO-like view of a range of a {Memory}, created with {Memory#open}.
+/// Reads and writes go straight to the memory, so it can be handed to
+/// parsers expecting an IO (e.g. +CSV+, +Zlib::GzipReader+ or
+/// +MessagePack::Unpacker+) without copying the range first.
+///
+/// Strings read are binary (ASCII-8BIT). The range is fixed when opening:
+/// reads stop at its end and writes past it raise.
+#[derive(TypedData)]
+#[magnus(class = "Wasmtime::MemoryIO", free_immediately, mark, unsafe_generics)]
+pub struct MemoryIO<'a> {
+    memory: Opaque<Obj<Memory<'a>>>,
+    start: usize,
+    len: usize,
+    pos: Cell<usize>,
+}
+
+impl DataTypeFunctions for MemoryIO<'_> {
+    fn mark(&self, marker: &Marker) {
+        marker.mark(self.memory)
+    }
+}
+
+unsafe impl Send for MemoryIO<'_> {}
+
+impl<'a> MemoryIO<'a> {
+    pub fn new(memory: Obj<Memory<'a>>, start: usize, len: usize) -> Result<Self, Error> {
+        let data = memory.data()?;
+        start
+            .checked_add(len)
+            .and_then(|end| data.get(start..end))
+            .ok_or_else(|| error!("out of bounds memory access"))?;
+
+        Ok(Self {
+            memory: memory.into(),
+            start,
+            len,
+            pos: Cell::new(0),
+        })
+    }
+
+    /// @yard
+    /// Reads +length+ bytes, or until the end when +length+ is +nil+.
+    /// @def read(length = nil, outbuf = nil)
+    /// @param length [Integer, nil]
+    /// @param outbuf [String, nil] A String to read into.
+    /// @return [String, nil] +nil+ at the end when +length+ is positive.
+    pub fn read(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<Option<RString>, Error> {
+        let args =
+            scan_args::scan_args::<(), (Option<Option<usize>>, Option<RString>), (), (), (), ()>(
+                args,
+            )?;
+        let (length, outbuf) = args.optional;
+        let remaining = rb_self.remaining()?;
+
+        let bytes = match length.flatten() {
+            None => remaining,
+            Some(length) if length > 0 && remaining.is_empty() => {
+                return rb_self.output(ruby, &[], outbuf).map(|_| None);
+            }
+            Some(length) => &remaining[..length.min(remaining.len())],
+        };
+        rb_self.output(ruby, bytes, outbuf).map(Some)
+    }
+
+    /// @yard
+    /// Reads at most +maxlen+ bytes.
+    /// @def readpartial(maxlen, outbuf = nil)
+    /// @param maxlen [Integer]
+    /// @param outbuf [String, nil] A String to read into.
+    /// @return [String]
+    /// @raise [EOFError] at the end.
+    pub fn readpartial(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<RString, Error> {
+        let args = scan_args::scan_args::<(usize,), (Option<RString>,), (), (), (), ()>(args)?;
+        let (maxlen,) = args.required;
+        let (outbuf,) = args.optional;
+        let remaining = rb_self.remaining()?;
+
+        if maxlen > 0 && remaining.is_empty() {
+            return Err(Error::new(
+                ruby.exception_eof_error(),
+                "end of file reached",
+            ));
+        }
+        rb_self.output(ruby, &remaining[..maxlen.min(remaining.len())], outbuf)
+    }
+
+    /// @yard
+    /// Reads the next line, separated by +sep+. Also accepts a +limit+ in
+    /// bytes as only argument.
+    /// @def gets(sep = "\n", limit = nil, chomp: false)
+    /// @param sep [String, nil] The line separator, +nil+ to read until the
+    ///   end, or +""+ to read paragraphs separated by blank lines.
+    /// @param limit [Integer, nil] The maximum number of bytes to read.
+    /// @param chomp [Boolean] Whether to remove the separator from the line.
+    /// @return [String, nil] +nil+ at the end.
+    pub fn gets(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<Option<RString>, Error> {
+        let (sep, limit, chomp) = Self::line_args(args)?;
+        rb_self.next_line(ruby, sep.as_deref(), limit, chomp)
+    }
+
+    /// @yard
+    /// Yields each line, see {#gets} for the arguments.
+    /// @def each_line(sep = "\n", limit = nil, chomp: false)
+    /// @yield [line]
+    /// @yieldparam line [String]
+    /// @return [MemoryIO, Enumerator] +self+, or an Enumerator without a block.
+    pub fn each_line(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<Value, Error> {
+        if !ruby.block_given() {
+            return Ok(rb_self.enumeratorize("each_line", args).as_value());
+        }
+
+        let (sep, limit, chomp) = Self::line_args(args)?;
+        if limit == Some(0) {
+            return Err(Error::new(
+                ruby.exception_arg_error(),
+                "invalid limit: 0 for each_line",
+            ));
+        }
+
+        while let Some(line) = rb_self.next_line(ruby, sep.as_deref(), limit, chomp)? {
+            let _: Value = ruby.yield_value(line)?;
+        }
+        Ok(rb_self.as_value())
+    }
+
+    /// @yard
+    /// Reads one byte.
+    /// @return [Integer, nil] +nil+ at the end.
+    pub fn getbyte(&self) -> Result<Option<u8>, Error> {
+        let byte = self.remaining()?.first().copied();
+        if byte.is_some() {
+            self.advance(1);
+        }
+        Ok(byte)
+    }
+
+    /// @yard
+    /// Writes the given objects, converted with +to_s+, at the current
+    /// position.
+    /// @def write(*objects)
+    /// @return [Integer] The number of bytes written.
+    /// @raise [Wasmtime::Error] when writing past the end of the range.
+    pub fn write(&self, objects: &[Value]) -> Result<usize, Error> {
+        let mut written = 0;
+        for object in objects {
+            let string = object.to_r_string()?;
+            let bytes = unsafe { string.as_slice() };
+            let pos = self.pos.get();
+            match pos.checked_add(bytes.len()) {
+                Some(end) if end <= self.len => {}
+                _ => return err!("out of bounds memory access"),
+            }
+
+            let memory = self.memory();
+            memory
+                .get_wasmtime_memory()
+                .write(memory.store.context_mut()?, self.start + pos, bytes)
+                .map_err(|e| error!("{}", e))?;
+            self.advance(bytes.len());
+            written += bytes.len();
+        }
+        Ok(written)
+    }
+
+    /// @yard
+    /// Moves the position, like +IO#seek+.
+    /// @def seek(offset, whence = IO::SEEK_SET)
+    /// @param offset [Integer]
+    /// @param whence [Integer, Symbol] +IO::SEEK_SET+, +IO::SEEK_CUR+,
+    ///   +IO::SEEK_END+, or the matching +:SET+, +:CUR+ or +:END+.
+    /// @return [0]
+    pub fn seek(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<i32, Error> {
+        let args = scan_args::scan_args::<(i64,), (Option<Value>,), (), (), (), ()>(args)?;
+        let (offset,) = args.required;
+        let (whence,) = args.optional;
+
+        let base = match whence.map(Whence::from_value).transpose()? {
+            None | Some(Whence::Set) => 0,
+            Some(Whence::Cur) => rb_self.pos.get(),
+            Some(Whence::End) => rb_self.len,
+        };
+
+        let pos = (base as i64).checked_add(offset).filter(|pos| *pos >= 0);
+        match pos {
+            Some(pos) => {
+                rb_self.pos.set(pos as usize);
+                Ok(0)
+            }
+            None => {
+                let errno: RModule = ruby.class_object().const_get("Errno")?;
+                let einval: ExceptionClass = errno.const_get("EINVAL")?;
+                Err(Error::new(einval, "Invalid argument"))
+            }
+        }
+    }
+
+    /// @yard
+    /// @return [Integer] The current position, relative to the start of the range.
+    pub fn pos(&self) -> usize {
+        self.pos.get()
+    }
+
+    /// @yard
+    /// @def pos=(pos)
+    /// @param pos [Integer]
+    pub fn set_pos(&self, pos: usize) {
+        self.pos.set(pos);
+    }
+
+    /// @yard
+    /// Moves the position to the start of the range.
+    /// @return [0]
+    pub fn rewind(&self) -> usize {
+        self.pos.set(0);
+        0
+    }
+
+    /// @yard
+    /// @return [Boolean] Whether the position is at the end of the range.
+    pub fn is_eof(&self) -> bool {
+        self.pos.get() >= self.len
+    }
+
+    /// @yard
+    /// @return [Integer] The size of the range.
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    fn memory(&self) -> &Memory<'a> {
+        let ruby = Ruby::get().unwrap();
+        ruby.get_inner_ref(&self.memory)
+    }
+
+    /// The bytes between the current position and the end of the range.
+    fn remaining(&self) -> Result<&[u8], Error> {
+        let pos = self.pos.get().min(self.len);
+        self.memory()
+            .data()?
+            .get(self.start + pos..self.start + self.len)
+            .ok_or_else(|| error!("out of bounds memory access"))
+    }
+
+    fn advance(&self, len: usize) {
+        if len > 0 {
+            self.pos.set(self.pos.get() + len);
+        }
+    }
+
+    /// Returns `bytes` as a binary String, or in `outbuf` when given, and
+    /// moves the position past them.
+    fn output(&self, ruby: &Ruby, bytes: &[u8], outbuf: Option<RString>) -> Result<RString, Error> {
+        let string = ruby.str_from_slice(bytes);
+        self.advance(bytes.len());
+        match outbuf {
+            Some(outbuf) => {
+                outbuf.replace(string)?;
+                Ok(outbuf)
+            }
+            None => Ok(string),
+        }
+    }
+
+    fn line_args(args: &[Value]) -> Result<(Option<Vec<u8>>, Option<usize>, bool), Error> {
+        let args = scan_args::scan_args::<(), (Option<Value>, Option<Value>), (), (), _, ()>(args)?;
+        let kw =
+            scan_args::get_kwargs::<_, (), (Option<bool>,), ()>(args.keywords, &[], &[*CHOMP])?;
+        let chomp = kw.optional.0.unwrap_or(false);
+
+        let (sep, limit) = match args.optional {
+            (Some(limit), None) if Integer::from_value(limit).is_some() => {
+                (Some(b"\n".to_vec()), Some(limit))
+            }
+            (None, _) => (Some(b"\n".to_vec()), None),
+            (Some(sep), limit) => {
+                let sep = Option::<RString>::try_convert(sep)?
+                    .map(|sep| unsafe { sep.as_slice() }.to_vec());
+                (sep, limit)
+            }
+        };
+        let limit = limit
+            .filter(|limit| !limit.is_nil())
+            .map(usize::try_convert)
+            .transpose()?;
+
+        Ok((sep, limit, chomp))
+    }
+
+    fn next_line(
+        &self,
+        ruby: &Ruby,
+        sep: Option<&[u8]>,
+        limit: Option<usize>,
+        chomp: bool,
+    ) -> Result<Option<RString>, Error> {
+        if limit == Some(0) {
+            return Ok(Some(ruby.str_new("")));
+        }
+
+        // Paragraph mode: skips blank lines, and splits on the next ones.
+        let paragraph = sep.is_some_and(<[u8]>::is_empty);
+        if paragraph {
+            self.advance(leading_newlines(self.remaining()?));
+        }
+        let sep = if paragraph { Some(&b"\n\n"[..]) } else { sep };
+
+        let remaining = self.remaining()?;
+        if remaining.is_empty() {
+            return Ok(None);
+        }
+
+        let mut end = match sep {
+            Some(sep) if !sep.is_empty() => remaining
+                .windows(sep.len())
+                .position(|window| window == sep)
+                .map_or(remaining.len(), |position| position + sep.len()),
+            _ => remaining.len(),
+        };
+        if let Some(limit) = limit {
+            end = end.min(limit);
+        }
+
+        let line = &remaining[..end];
+        self.advance(line.len());
+        if paragraph && line.ends_with(b"\n\n") {
+            self.advance(leading_newlines(&remaining[end..]));
+        }
+        let line = match sep {
+            Some(_) if chomp && paragraph => {
+                let newlines = line.iter().rev().take_while(|b| **b == b'\n').count();
+                &line[..line.len() - newlines]
+            }
+            Some(sep) if chomp && !sep.is_empty() => line.strip_suffix(sep).unwrap_or(line),
+            _ => line,
+        };
+        Ok(Some(ruby.str_from_slice(line)))
+    }
+}
+
+fn leading_newlines(bytes: &[u8]) -> usize {
+    bytes.iter().take_while(|b| **b == b'\n').count()
+}
+
+pub fn init(ruby: &Ruby) -> Result<(), Error> {
+    let class = root().define_class("MemoryIO", ruby.class_object())?;
+    class.define_method("read", method!(MemoryIO::read, -1))?;
+    class.define_method("readpartial", method!(MemoryIO::readpartial, -1))?;
+    class.define_method("gets", method!(MemoryIO::gets, -1))?;
+    class.define_method("each_line", method!(MemoryIO::each_line, -1))?;
+    class.define_method("getbyte", method!(MemoryIO::getbyte, 0))?;
+    class.define_method("write", method!(MemoryIO::write, -1))?;
+    class.define_method("seek", method!(MemoryIO::seek, -1))?;
+    class.define_method("pos", method!(MemoryIO::pos, 0))?;
+    class.define_method("tell", method!(MemoryIO::pos, 0))?;
+    class.define_method("pos=", method!(MemoryIO::set_pos, 1))?;
+    class.define_method("rewind", method!(MemoryIO::rewind, 0))?;
+    class.define_method("eof?", method!(MemoryIO::is_eof, 0))?;
+    class.define_method("eof", method!(MemoryIO::is_eof, 0))?;
+    class.define_method("size", method!(MemoryIO::size, 0))?;
+
+    Ok(())
+}
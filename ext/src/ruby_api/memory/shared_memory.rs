@@ -0,0 +1,294 @@
+use super::{MemoryType, MAX_SIZE, MIN_SIZE};
+use crate::{define_rb_intern, error, helpers::nogvl, root, Engine};
+use magnus::{
+    class, function, method, prelude::*, scan_args, typed_data::Obj, Error, Integer, RString, Ruby,
+    Symbol, Value,
+};
+use std::time::{Duration, Instant};
+use wasmtime::{Extern, SharedMemory as SharedMemoryImpl, WaitResult};
+
+define_rb_intern!(
+    TIMEOUT => "timeout",
+    OK => "ok",
+    MISMATCH => "mismatch",
+    TIMED_OUT => "timed_out",
+);
+
+/// @yard
+/// @rename Wasmtime::SharedMemory
+/// Represents a WebAssembly shared memory, from the threads proposal. Unlike
+/// a {Memory}, it belongs to an {Engine} rather than a {Store}: it can be
+/// imported into instances of different stores, used from different threads.
+///
+/// Requires an {Engine} created with +wasm_threads: true+ and
+/// +shared_memory: true+.
+///
+/// Reads and writes from Ruby are not atomic. Synchronize with the guest
+/// through {#atomic_wait32}, {#atomic_wait64} and {#atomic_notify}.
+/// @see https://docs.rs/wasmtime/latest/wasmtime/struct.SharedMemory.html Wasmtime's Rust doc
+#[magnus::wrap(class = "Wasmtime::SharedMemory", free_immediately, size)]
+pub struct SharedMemory {
+    inner: SharedMemoryImpl,
+}
+
+impl SharedMemory {
+    /// @yard
+    /// @def new(engine, min_size:, max_size:)
+    /// @param engine [Engine]
+    /// @param min_size [Integer] The minimum memory pages.
+    /// @param max_size [Integer] The maximum memory pages, required for
+    ///   shared memories.
+    pub fn new(args: &[Value]) -> Result<Self, Error> {
+        let args = scan_args::scan_args::<(&Engine,), (), (), (), _, ()>(args)?;
+        let kw = scan_args::get_kwargs::<_, (u32, u32), (), ()>(
+            args.keywords,
+            &[*MIN_SIZE, *MAX_SIZE],
+            &[],
+        )?;
+        let (engine,) = args.required;
+        let (min, max) = kw.required;
+
+        let memtype = wasmtime::MemoryType::shared(min, max);
+        let inner = SharedMemoryImpl::new(engine.get(), memtype).map_err(|e| error!("{}", e))?;
+
+        Ok(Self { inner })
+    }
+
+    pub fn from_inner(inner: SharedMemoryImpl) -> Self {
+        Self { inner }
+    }
+
+    /// @yard
+    /// @return [Integer] The minimum number of memory pages.
+    pub fn min_size(&self) -> u64 {
+        self.inner.ty().minimum()
+    }
+
+    /// @yard
+    /// @return [Integer] The maximum number of memory pages.
+    pub fn max_size(&self) -> Option<u64> {
+        self.inner.ty().maximum()
+    }
+
+    /// @yard
+    /// @def type
+    /// @return [MemoryType]
+    pub fn type_(ruby: &Ruby, rb_self: &Self) -> Obj<MemoryType> {
+        ruby.obj_wrap(MemoryType::from_inner(rb_self.inner.ty()))
+    }
+
+    /// @yard
+    /// Read +size+ bytes starting at +offset+. Result is a ASCII-8BIT encoded string.
+    ///
+    /// @def read(offset, size)
+    /// @param offset [Integer]
+    /// @param size [Integer]
+    /// @return [String] Binary +String+ of the memory.
+    pub fn read(ruby: &Ruby, rb_self: &Self, offset: usize, size: usize) -> Result<RString, Error> {
+        let data = rb_self
+            .inner
+            .data()
+            .get(offset..)
+            .and_then(|s| s.get(..size))
+            .ok_or_else(|| error!("out of bounds memory access"))?;
+
+        let buffer: Vec<u8> = data.iter().map(|cell| unsafe { *cell.get() }).collect();
+        Ok(ruby.str_from_slice(&buffer))
+    }
+
+    /// @yard
+    /// Write +value+ starting at +offset+.
+    ///
+    /// @def write(offset, value)
+    /// @param offset [Integer]
+    /// @param value [String]
+    /// @return [void]
+    pub fn write(&self, offset: usize, value: RString) -> Result<(), Error> {
+        let slice = unsafe { value.as_slice() };
+        let data = self
+            .inner
+            .data()
+            .get(offset..)
+            .and_then(|s| s.get(..slice.len()))
+            .ok_or_else(|| error!("out of bounds memory access"))?;
+
+        for (cell, byte) in data.iter().zip(slice) {
+            unsafe { *cell.get() = *byte };
+        }
+        Ok(())
+    }
+
+    /// @yard
+    /// Grows a memory by +delta+ pages.
+    /// Raises if the memory grows beyond its limit.
+    ///
+    /// @def grow(delta)
+    /// @param delta [Integer] The number of pages to grow by.
+    /// @return [Integer] The number of pages the memory had before being resized.
+    pub fn grow(&self, delta: u64) -> Result<u64, Error> {
+        self.inner.grow(delta).map_err(|e| error!("{}", e))
+    }
+
+    /// @yard
+    /// @return [Integer] The number of pages of the memory.
+    pub fn size(&self) -> u64 {
+        self.inner.size()
+    }
+
+    /// @yard
+    /// @return [Integer] The number of bytes of the memory.
+    pub fn data_size(&self) -> usize {
+        self.inner.data_size()
+    }
+
+    /// @yard
+    /// Wakes up to +count+ threads waiting on +offset+, like the
+    /// +memory.atomic.notify+ instruction.
+    ///
+    /// @def atomic_notify(offset, count)
+    /// @param offset [Integer] A 4-byte aligned address.
+    /// @param count [Integer] The maximum number of waiters to wake up.
+    /// @return [Integer] The number of waiters woken up.
+    pub fn atomic_notify(&self, offset: u64, count: u32) -> Result<u32, Error> {
+        self.inner
+            .atomic_notify(offset, count)
+            .map_err(|e| error!("{}", e))
+    }
+
+    /// @yard
+    /// Blocks until notified when the +i32+ at +offset+ equals +expected+,
+    /// like the +memory.atomic.wait32+ instruction. The GVL is released
+    /// while waiting, and the wait can be interrupted, e.g. by +Thread#raise+
+    /// or +Timeout.timeout+.
+    ///
+    /// @def atomic_wait32(offset, expected, timeout: nil)
+    /// @param offset [Integer] A 4-byte aligned address.
+    /// @param expected [Integer]
+    /// @param timeout [Float, nil] The maximum number of seconds to wait.
+    /// @return [Symbol] +:ok+ when notified, +:mismatch+ when the value
+    ///   differs from +expected+, or +:timed_out+.
+    pub fn atomic_wait32(&self, args: &[Value]) -> Result<Symbol, Error> {
+        let (offset, expected, timeout) = wait_args(args)?;
+        let expected = expected
+            .to_u32()
+            .or_else(|_| expected.to_i32().map(|e| e as u32))?;
+        wait_interruptibly(timeout, |slice| {
+            self.inner.atomic_wait32(offset, expected, Some(slice))
+        })
+    }
+
+    /// @yard
+    /// Blocks until notified when the +i64+ at +offset+ equals +expected+,
+    /// like the +memory.atomic.wait64+ instruction. The GVL is released
+    /// while waiting, and the wait can be interrupted, e.g. by +Thread#raise+
+    /// or +Timeout.timeout+.
+    ///
+    /// @def atomic_wait64(offset, expected, timeout: nil)
+    /// @param offset [Integer] An 8-byte aligned address.
+    /// @param expected [Integer]
+    /// @param timeout [Float, nil] The maximum number of seconds to wait.
+    /// @return [Symbol] +:ok+ when notified, +:mismatch+ when the value
+    ///   differs from +expected+, or +:timed_out+.
+    pub fn atomic_wait64(&self, args: &[Value]) -> Result<Symbol, Error> {
+        let (offset, expected, timeout) = wait_args(args)?;
+        let expected = expected
+            .to_u64()
+            .or_else(|_| expected.to_i64().map(|e| e as u64))?;
+        wait_interruptibly(timeout, |slice| {
+            self.inner.atomic_wait64(offset, expected, Some(slice))
+        })
+    }
+
+    pub fn get(&self) -> &SharedMemoryImpl {
+        &self.inner
+    }
+}
+
+impl From<&SharedMemory> for Extern {
+    fn from(memory: &SharedMemory) -> Self {
+        Self::SharedMemory(memory.get().clone())
+    }
+}
+
+/// Parses the +(offset, expected, timeout: nil)+ arguments of the
+/// +atomic_wait+ methods. +expected+ may be signed or unsigned, as Wasm
+/// integers are sign-agnostic.
+fn wait_args(args: &[Value]) -> Result<(u64, Integer, Option<Duration>), Error> {
+    let args = scan_args::scan_args::<(u64, Integer), (), (), (), _, ()>(args)?;
+    let kw = scan_args::get_kwargs::<_, (), (Option<Option<f64>>,), ()>(
+        args.keywords,
+        &[],
+        &[*TIMEOUT],
+    )?;
+    let (offset, expected) = args.required;
+    let timeout = kw
+        .optional
+        .0
+        .flatten()
+        .map(Duration::try_from_secs_f64)
+        .transpose()
+        .map_err(|e| {
+            let ruby = Ruby::get().unwrap();
+            Error::new(ruby.exception_arg_error(), format!("invalid timeout: {e}"))
+        })?;
+
+    Ok((offset, expected, timeout))
+}
+
+/// The longest a wait runs without the GVL before checking for interrupts.
+const WAIT_SLICE: Duration = Duration::from_millis(50);
+
+/// Runs +wait+ in slices of at most [`WAIT_SLICE`] with the GVL released
+/// until it returns or +timeout+ elapses. Wasmtime's waits can't be
+/// unblocked without notifying the guest's waiters too, so pending interrupts
+/// are handled between slices instead. A notification sent between two
+/// slices is missed, but the next slice returns +:mismatch+ when the notifier
+/// updated the value first, as it usually does.
+fn wait_interruptibly<F>(timeout: Option<Duration>, wait: F) -> Result<Symbol, Error>
+where
+    F: Fn(Duration) -> Result<WaitResult, wasmtime::Error>,
+{
+    let ruby = Ruby::get().unwrap();
+    let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+
+    loop {
+        let slice = deadline.map_or(WAIT_SLICE, |deadline| {
+            deadline
+                .saturating_duration_since(Instant::now())
+                .min(WAIT_SLICE)
+        });
+
+        match nogvl(|| wait(slice)).map_err(|e| error!("{}", e))? {
+            WaitResult::TimedOut if deadline.is_none_or(|d| Instant::now() < d) => {
+                ruby.thread_check_ints()?
+            }
+            result => return Ok(wait_result(result)),
+        }
+    }
+}
+
+fn wait_result(result: WaitResult) -> Symbol {
+    match result {
+        WaitResult::Ok => Symbol::from(*OK),
+        WaitResult::Mismatch => Symbol::from(*MISMATCH),
+        WaitResult::TimedOut => Symbol::from(*TIMED_OUT),
+    }
+}
+
+pub fn init(ruby: &Ruby) -> Result<(), Error> {
+    let class = root().define_class("SharedMemory", ruby.class_object())?;
+    class.define_singleton_method("new", function!(SharedMemory::new, -1))?;
+    class.define_method("min_size", method!(SharedMemory::min_size, 0))?;
+    class.define_method("max_size", method!(SharedMemory::max_size, 0))?;
+    class.define_method("type", method!(SharedMemory::type_, 0))?;
+    class.define_method("read", method!(SharedMemory::read, 2))?;
+    class.define_method("write", method!(SharedMemory::write, 2))?;
+    class.define_method("grow", method!(SharedMemory::grow, 1))?;
+    class.define_method("size", method!(SharedMemory::size, 0))?;
+    class.define_method("data_size", method!(SharedMemory::data_size, 0))?;
+    class.define_method("atomic_notify", method!(SharedMemory::atomic_notify, 2))?;
+    class.define_method("atomic_wait32", method!(SharedMemory::atomic_wait32, -1))?;
+    class.define_method("atomic_wait64", method!(SharedMemory::atomic_wait64, -1))?;
+
+    Ok(())
+}
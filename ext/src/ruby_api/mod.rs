@@ -9,6 +9,7 @@
 #![allow(unused_imports)]
 use magnus::{function, value::Lazy, Error, RModule, RString, Ruby};
 
+mod array_ref;
 mod caller;
 mod component;
 mod config;
@@ -19,12 +20,17 @@ mod externals;
 mod func;
 mod global;
 mod instance;
+mod instance_snapshot;
+mod interrupt_handle;
 mod linker;
 mod memory;
 mod module;
 mod params;
 mod pooling_allocation_config;
+mod preinitialize;
+mod ref_type;
 mod store;
+mod struct_ref;
 mod table;
 mod trap;
 mod wasi;
@@ -34,6 +40,8 @@ pub use caller::Caller;
 pub use engine::Engine;
 pub use func::Func;
 pub use instance::Instance;
+pub use instance_snapshot::InstanceSnapshot;
+pub use interrupt_handle::InterruptHandle;
 pub use linker::Linker;
 pub use memory::Memory;
 pub use module::Module;
@@ -72,6 +80,7 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     let wasmtime = root();
 
     wasmtime.define_module_function("wat2wasm", function!(Wasmtime::wat2wasm, 1))?;
+    wasmtime.define_module_function("preinitialize", function!(Wasmtime::preinitialize, -1))?;
 
     errors::init()?;
     trap::init()?;
@@ -79,6 +88,8 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     module::init(ruby)?;
     store::init(ruby)?;
     instance::init(ruby)?;
+    instance_snapshot::init(ruby)?;
+    interrupt_handle::init(ruby)?;
     func::init(ruby)?;
     caller::init(ruby)?;
     memory::init(ruby)?;
@@ -88,6 +99,9 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     wasi_config::init(ruby)?;
     table::init(ruby)?;
     global::init(ruby)?;
+    struct_ref::init(ruby)?;
+    array_ref::init(ruby)?;
+    ref_type::init(ruby)?;
     pooling_allocation_config::init(ruby)?;
     component::init(ruby)?;
 
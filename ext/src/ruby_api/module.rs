@@ -7,6 +7,7 @@ use std::{
 use super::{
     convert::{WrapWasmtimeExternType, WrapWasmtimeType},
     engine::Engine,
+    errors::validation_err,
     root,
 };
 use crate::{
@@ -14,8 +15,8 @@ use crate::{
     helpers::{nogvl, Tmplock},
 };
 use magnus::{
-    class, function, method, rb_sys::AsRawValue, typed_data::Obj, Error, Module as _, Object,
-    RArray, RHash, RString, Ruby,
+    class, function, method, rb_sys::AsRawValue, typed_data::Obj, value::ReprValue, Error,
+    Module as _, Object, RArray, RHash, RString, Ruby,
 };
 use rb_sys::{
     rb_str_locktmp, rb_str_unlocktmp, tracking_allocator::ManuallyTracked, RSTRING_LEN, RSTRING_PTR,
@@ -24,6 +25,10 @@ use wasmtime::{ImportType, Module as ModuleImpl};
 
 /// @yard
 /// Represents a WebAssembly module.
+///
+/// Modules are frozen when created and can be shared with other Ractors, e.g.
+/// by passing them to +Ractor.new+. A module can be instantiated concurrently
+/// from any thread or Ractor, each with its own {Store}.
 /// @see https://docs.rs/wasmtime/latest/wasmtime/struct.Module.html Wasmtime's Rust doc
 #[derive(Clone)]
 #[magnus::wrap(class = "Wasmtime::Module", size, free_immediately, frozen_shareable)]
@@ -41,13 +46,13 @@ impl Module {
     /// @param engine [Wasmtime::Engine]
     /// @param wat_or_wasm [String] The String of WAT or Wasm.
     /// @return [Wasmtime::Module]
-    pub fn new(engine: &Engine, wat_or_wasm: RString) -> Result<Self, Error> {
+    pub fn new(ruby: &Ruby, engine: &Engine, wat_or_wasm: RString) -> Result<Obj<Self>, Error> {
         let eng = engine.get();
         let (locked_slice, _locked_slice_guard) = wat_or_wasm.as_locked_slice()?;
         let module = nogvl(|| ModuleImpl::new(eng, locked_slice))
             .map_err(|e| error!("Could not build module: {}", e))?;
 
-        Ok(module.into())
+        Ok(Self::wrap_frozen(ruby, module))
     }
 
     /// @yard
@@ -55,14 +60,30 @@ impl Module {
     /// @param engine [Wasmtime::Engine]
     /// @param path [String]
     /// @return [Wasmtime::Module]
-    pub fn from_file(engine: &Engine, path: RString) -> Result<Self, Error> {
+    pub fn from_file(ruby: &Ruby, engine: &Engine, path: RString) -> Result<Obj<Self>, Error> {
         let eng = engine.get();
         let (path, _locked_str_guard) = path.as_locked_str()?;
         // SAFETY: this string is immediately copied and never moved off the stack
         let module = nogvl(|| ModuleImpl::from_file(eng, path))
             .map_err(|e| error!("Could not build module from file: {}", e))?;
 
-        Ok(module.into())
+        Ok(Self::wrap_frozen(ruby, module))
+    }
+
+    /// @yard
+    /// Validates a Wasm binary without compiling it. This is considerably
+    /// cheaper than {.new} and uses the features enabled on +engine+.
+    ///
+    /// @def validate(engine, wasm)
+    /// @param engine [Wasmtime::Engine]
+    /// @param wasm [String] The binary String of Wasm. The text format is not accepted.
+    /// @return [nil]
+    /// @raise [Wasmtime::ValidationError] if +wasm+ is not a valid module.
+    pub fn validate(engine: &Engine, wasm: RString) -> Result<(), Error> {
+        let eng = engine.get();
+        let (locked_slice, _locked_slice_guard) = wasm.as_locked_slice()?;
+
+        nogvl(|| ModuleImpl::validate(eng, locked_slice)).map_err(validation_err)
     }
 
     /// @yard
@@ -76,10 +97,14 @@ impl Module {
     /// @param engine [Wasmtime::Engine]
     /// @param compiled [String] String obtained with either {Wasmtime::Engine#precompile_module} or {#serialize}.
     /// @return [Wasmtime::Module]
-    pub fn deserialize(engine: &Engine, compiled: RString) -> Result<Self, Error> {
+    pub fn deserialize(
+        ruby: &Ruby,
+        engine: &Engine,
+        compiled: RString,
+    ) -> Result<Obj<Self>, Error> {
         // SAFETY: this string is immediately copied and never moved off the stack
         unsafe { ModuleImpl::deserialize(engine.get(), compiled.as_slice()) }
-            .map(Into::into)
+            .map(|module| Self::wrap_frozen(ruby, module))
             .map_err(|e| error!("Could not deserialize module: {}", e))
     }
 
@@ -91,9 +116,13 @@ impl Module {
     /// @param path [String]
     /// @return [Wasmtime::Module]
     /// @see .deserialize
-    pub fn deserialize_file(engine: &Engine, path: RString) -> Result<Self, Error> {
+    pub fn deserialize_file(
+        ruby: &Ruby,
+        engine: &Engine,
+        path: RString,
+    ) -> Result<Obj<Self>, Error> {
         unsafe { ModuleImpl::deserialize_file(engine.get(), path.as_str()?) }
-            .map(Into::into)
+            .map(|module| Self::wrap_frozen(ruby, module))
             .map_err(|e| error!("Could not deserialize module from file: {}", e))
     }
 
@@ -114,6 +143,13 @@ impl Module {
         &self.inner
     }
 
+    /// Wraps `inner` in a frozen, and thus Ractor-shareable, Ruby object.
+    pub fn wrap_frozen(ruby: &Ruby, inner: ModuleImpl) -> Obj<Self> {
+        let module = ruby.obj_wrap(Self::from(inner));
+        module.freeze();
+        module
+    }
+
     /// @yard
     /// Returns the list of imports that this Module has and must be satisfied.
     /// @return [Array<Hash>] An array of hashes containing import information
@@ -159,6 +195,7 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
 
     class.define_singleton_method("new", function!(Module::new, 2))?;
     class.define_singleton_method("from_file", function!(Module::from_file, 2))?;
+    class.define_singleton_method("validate", function!(Module::validate, 2))?;
     class.define_singleton_method("deserialize", function!(Module::deserialize, 2))?;
     class.define_singleton_method("deserialize_file", function!(Module::deserialize_file, 2))?;
     class.define_method("serialize", method!(Module::serialize, 0))?;
@@ -0,0 +1,538 @@
+use super::{engine::Engine, linker::Linker, module::Module, store::Store, Wasmtime};
+use crate::{define_rb_intern, err, error, helpers::nogvl};
+use magnus::{
+    prelude::*,
+    scan_args::{get_kwargs, scan_args},
+    typed_data::Obj,
+    Error, RString, Ruby, TryConvert, TypedData, Value,
+};
+use std::{convert::Infallible, ops::Range};
+use wasm_encoder::{
+    reencode::{self, utils, Reencode, RoundtripReencoder},
+    ConstExpr, DataSection, ExportKind, ExportSection, GlobalSection, Ieee32, Ieee64,
+    MemorySection, SectionId,
+};
+use wasmtime::{Module as ModuleImpl, Val};
+use wasmtime_environ::wasmparser::{
+    BinaryReader, BinaryReaderError, Data, DataKind, DataSectionReader, Encoding, Export,
+    ExportSectionReader, ExternalKind, Global, GlobalType, MemorySectionReader, MemoryType, Parser,
+    Payload, TypeRef, ValType,
+};
+
+define_rb_intern!(
+    INIT_FUNC => "init_func",
+    LINKER => "linker",
+    STORE => "store",
+);
+
+const DEFAULT_INIT_FUNC: &str = "wizer.initialize";
+const EXPORT_PREFIX: &str = "__wasmtime_rb_preinitialize";
+
+/// Zero-filled gaps up to this size are kept inside a data segment rather
+/// than splitting it, since each segment costs a few bytes of encoding.
+const MAX_DATA_GAP: usize = 16;
+
+/// The number of data segments a module may have, Wasmtime refuses to
+/// compile modules with more (wasmparser's `MAX_WASM_DATA_SEGMENTS`).
+const MAX_DATA_SEGMENTS: usize = 100_000;
+
+impl Wasmtime {
+    /// @yard
+    /// Pre-initializes a module, Wizer-style: the module is instantiated, its
+    /// +init_func+ export is called, and the resulting state of its memories
+    /// and globals is written back into a new Wasm binary. Instantiating the
+    /// returned binary starts from that state without running the
+    /// initialization again.
+    ///
+    /// Only memories and mutable globals defined by the module are snapshotted.
+    /// Imported memories and globals, as well as tables, are left as they are
+    /// in the original module; shared memories are not supported. Reference
+    /// typed globals can only be snapshotted while null: Wasm has no constant
+    /// expression for e.g. a +funcref+ obtained at runtime, so a mutable
+    /// global holding a non-null reference raises. The +init_func+ export and
+    /// the start function are removed from the result.
+    ///
+    /// @def preinitialize(engine, wasm, init_func: "wizer.initialize", linker: nil, store: nil)
+    /// @param engine [Engine]
+    /// @param wasm [String] The String of WAT or Wasm.
+    /// @param init_func [String] The name of the exported initialization function.
+    /// @param linker [Linker, nil] The linker used to instantiate the module.
+    ///   Defaults to an empty linker.
+    /// @param store [Store, nil] The store used to instantiate the module.
+    ///   Defaults to a new store.
+    /// @return [String] The pre-initialized module as a binary +String+,
+    ///   usable with {Module.new} or {Engine#precompile_module}.
+    /// @raise [Error] if a mutable global holds a non-null reference after
+    ///   initialization.
+    ///
+    /// @example
+    ///   wasm = Wasmtime.preinitialize(engine, File.binread("app.wasm"))
+    ///   mod = Wasmtime::Module.new(engine, wasm)
+    pub fn preinitialize(ruby: &Ruby, args: &[Value]) -> Result<RString, Error> {
+        let args = scan_args::<(Obj<Engine>, RString), (), (), (), _, ()>(args)?;
+        let kw = get_kwargs::<_, (), (Option<String>, Option<Obj<Linker>>, Option<Obj<Store>>), ()>(
+            args.keywords,
+            &[],
+            &[*INIT_FUNC, *LINKER, *STORE],
+        )?;
+        let (engine, wasm) = args.required;
+        let (init_func, linker, store) = kw.optional;
+        let init_func = init_func.unwrap_or_else(|| DEFAULT_INIT_FUNC.to_string());
+
+        let wasm = wat::parse_bytes(unsafe { wasm.as_slice() })
+            .map_err(|e| error!("{}", e))?
+            .into_owned();
+        let module = ModuleInfo::parse(&wasm)?;
+
+        let instrumented = module.instrument()?;
+        let eng = engine.get();
+        let compiled = nogvl(|| ModuleImpl::new(eng, &instrumented))
+            .map_err(|e| error!("Could not build module: {}", e))?;
+        let compiled = Module::wrap_frozen(ruby, compiled);
+
+        let linker = match linker {
+            Some(linker) => linker,
+            None => ruby.obj_wrap(Linker::new(&engine)?),
+        };
+        let store = match store {
+            Some(store) => store,
+            None => Obj::<Store>::try_convert(Store::class(ruby).new_instance((engine,))?)?,
+        };
+        let instance = ruby.obj_wrap(Linker::instantiate(ruby, linker, store, &compiled)?);
+        let _: Value = instance.funcall("invoke", (init_func.as_str(),))?;
+
+        let inner = instance.get();
+        let mut context = store.context_mut();
+        let mut memories = Vec::with_capacity(module.memories.len());
+        for (index, _) in module.defined_memories() {
+            let memory = inner
+                .get_memory(&mut context, &export_name("memory", index))
+                .ok_or_else(|| error!("memory {index} was not exported"))?;
+            memories.push((memory.size(&context), memory.data(&context).to_vec()));
+        }
+        let mut globals = Vec::with_capacity(module.globals.len());
+        for (index, ty) in module.defined_globals() {
+            let init = if ty.mutable {
+                let global = inner
+                    .get_global(&mut context, &export_name("global", index))
+                    .ok_or_else(|| error!("global {index} was not exported"))?;
+                Some(const_expr(
+                    index,
+                    &global.get(&mut context),
+                    ty.content_type,
+                )?)
+            } else {
+                None
+            };
+            globals.push(init);
+        }
+
+        let snapshot = module.snapshot(&init_func, &memories, globals)?;
+        Ok(ruby.str_from_slice(&snapshot))
+    }
+}
+
+fn parse_err(e: BinaryReaderError) -> Error {
+    error!("Could not parse module: {}", e)
+}
+
+fn export_name(kind: &str, index: u32) -> String {
+    format!("{EXPORT_PREFIX}_{kind}_{index}")
+}
+
+/// The parts of a core module that pre-initialization reads or rewrites.
+struct ModuleInfo<'a> {
+    wasm: &'a [u8],
+    imported_memories: u32,
+    imported_globals: u32,
+    memories: Vec<MemoryType>,
+    globals: Vec<GlobalType>,
+    /// The number of data segments, all kept by the snapshot.
+    data_segments: usize,
+}
+
+impl<'a> ModuleInfo<'a> {
+    fn parse(wasm: &'a [u8]) -> Result<Self, Error> {
+        let mut info = Self {
+            wasm,
+            imported_memories: 0,
+            imported_globals: 0,
+            memories: Vec::new(),
+            globals: Vec::new(),
+            data_segments: 0,
+        };
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload.map_err(parse_err)? {
+                Payload::Version {
+                    encoding: Encoding::Component,
+                    ..
+                } => return err!("expected a core Wasm module"),
+                Payload::ImportSection(section) => {
+                    for import in section.into_imports() {
+                        match import.map_err(parse_err)?.ty {
+                            TypeRef::Memory(_) => info.imported_memories += 1,
+                            TypeRef::Global(_) => info.imported_globals += 1,
+                            _ => {}
+                        }
+                    }
+                }
+                Payload::MemorySection(section) => {
+                    for ty in section {
+                        let ty = ty.map_err(parse_err)?;
+                        if ty.shared {
+                            return err!("shared memories are not supported");
+                        }
+                        info.memories.push(ty);
+                    }
+                }
+                Payload::GlobalSection(section) => {
+                    for global in section {
+                        info.globals.push(global.map_err(parse_err)?.ty);
+                    }
+                }
+                Payload::DataSection(section) => info.data_segments = section.count() as usize,
+                _ => {}
+            }
+        }
+
+        Ok(info)
+    }
+
+    fn defined_memories(&self) -> impl Iterator<Item = (u32, &MemoryType)> {
+        (self.imported_memories..).zip(&self.memories)
+    }
+
+    fn defined_globals(&self) -> impl Iterator<Item = (u32, &GlobalType)> {
+        (self.imported_globals..).zip(&self.globals)
+    }
+
+    /// Returns the module with every defined memory and mutable global
+    /// exported, so their state can be read after initialization.
+    fn instrument(&self) -> Result<Vec<u8>, Error> {
+        let mut exports = Vec::new();
+        for (index, _) in self.defined_memories() {
+            exports.push((export_name("memory", index), ExportKind::Memory, index));
+        }
+        for (index, ty) in self.defined_globals() {
+            if ty.mutable {
+                exports.push((export_name("global", index), ExportKind::Global, index));
+            }
+        }
+
+        let mut instrument = Instrument {
+            exports,
+            written: false,
+        };
+        reencode_module(&mut instrument, self.wasm)
+    }
+
+    /// Returns the original module with the given state baked in: memories
+    /// grown to their current size with their contents as data segments,
+    /// and mutable globals initialized to their current value.
+    fn snapshot(
+        &self,
+        init_func: &str,
+        memories: &[(u64, Vec<u8>)],
+        globals: Vec<Option<ConstExpr>>,
+    ) -> Result<Vec<u8>, Error> {
+        // The original segments are kept, the memories share what's left.
+        let max_segments =
+            MAX_DATA_SEGMENTS.saturating_sub(self.data_segments) / memories.len().max(1);
+        let mut segments = Vec::new();
+        for ((index, ty), (_, bytes)) in self.defined_memories().zip(memories) {
+            for range in data_ranges(bytes, max_segments.max(1)) {
+                let offset = if ty.memory64 {
+                    ConstExpr::i64_const(range.start as i64)
+                } else {
+                    ConstExpr::i32_const(range.start as u32 as i32)
+                };
+                segments.push((index, offset, &bytes[range]));
+            }
+        }
+
+        let mut snapshot = Snapshot {
+            init_func,
+            imported_memories: self.imported_memories,
+            pages: memories.iter().map(|(pages, _)| *pages).collect(),
+            globals,
+            next_global: 0,
+            segments,
+            data_written: false,
+        };
+        reencode_module(&mut snapshot, &self.without_start_section()?)
+    }
+
+    /// Returns the module without its start section, which already ran
+    /// during initialization.
+    fn without_start_section(&self) -> Result<Vec<u8>, Error> {
+        let mut reader = BinaryReader::new(self.wasm, 0);
+        let mut wasm = reader.read_bytes(8).map_err(parse_err)?.to_vec();
+        while !reader.eof() {
+            let start = reader.original_position();
+            let id = reader.read_u8().map_err(parse_err)?;
+            let size = reader.read_var_u32().map_err(parse_err)?;
+            reader.read_bytes(size as usize).map_err(parse_err)?;
+            if id != SectionId::Start as u8 {
+                wasm.extend_from_slice(&self.wasm[start..reader.original_position()]);
+            }
+        }
+        Ok(wasm)
+    }
+}
+
+fn reencode_module(
+    reencoder: &mut impl Reencode<Error = Infallible>,
+    wasm: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let mut module = wasm_encoder::Module::new();
+    reencoder
+        .parse_core_module(&mut module, Parser::new(0), wasm)
+        .map_err(|e| error!("Could not rewrite module: {}", e))?;
+    Ok(module.finish())
+}
+
+/// Adds the exports needed to read the module's state after initialization.
+struct Instrument {
+    exports: Vec<(String, ExportKind, u32)>,
+    written: bool,
+}
+
+impl Instrument {
+    fn add_exports(&mut self, section: &mut ExportSection) {
+        for (name, kind, index) in &self.exports {
+            section.export(name, *kind, *index);
+        }
+        self.written = true;
+    }
+}
+
+impl Reencode for Instrument {
+    type Error = Infallible;
+
+    fn parse_export_section(
+        &mut self,
+        exports: &mut ExportSection,
+        section: ExportSectionReader<'_>,
+    ) -> Result<(), reencode::Error> {
+        utils::parse_export_section(self, exports, section)?;
+        self.add_exports(exports);
+        Ok(())
+    }
+
+    fn intersperse_section_hook(
+        &mut self,
+        module: &mut wasm_encoder::Module,
+        _after: Option<SectionId>,
+        before: Option<SectionId>,
+    ) -> Result<(), reencode::Error> {
+        if !self.written && comes_after(before, SectionId::Export) {
+            let mut exports = ExportSection::new();
+            self.add_exports(&mut exports);
+            module.section(&exports);
+        }
+        Ok(())
+    }
+}
+
+/// Bakes the module's state after initialization into the module.
+struct Snapshot<'a> {
+    init_func: &'a str,
+    imported_memories: u32,
+    /// The size in pages of each defined memory.
+    pages: Vec<u64>,
+    /// The initializer of each defined global, `None` for immutable ones.
+    globals: Vec<Option<ConstExpr>>,
+    next_global: usize,
+    /// The contents of the memories, as active data segments.
+    segments: Vec<(u32, ConstExpr, &'a [u8])>,
+    data_written: bool,
+}
+
+impl Snapshot<'_> {
+    fn add_segments(&mut self, section: &mut DataSection) {
+        for (memory, offset, bytes) in &self.segments {
+            section.active(*memory, offset, bytes.iter().copied());
+        }
+        self.data_written = true;
+    }
+}
+
+impl Reencode for Snapshot<'_> {
+    type Error = Infallible;
+
+    fn parse_memory_section(
+        &mut self,
+        memories: &mut MemorySection,
+        section: MemorySectionReader<'_>,
+    ) -> Result<(), reencode::Error> {
+        for (ty, pages) in section.into_iter().zip(&self.pages) {
+            let mut ty = RoundtripReencoder.memory_type(ty?)?;
+            ty.minimum = *pages;
+            memories.memory(ty);
+        }
+        Ok(())
+    }
+
+    fn parse_global(
+        &mut self,
+        globals: &mut GlobalSection,
+        global: Global<'_>,
+    ) -> Result<(), reencode::Error> {
+        let init = self.globals[self.next_global].take();
+        self.next_global += 1;
+        match init {
+            Some(init) => {
+                globals.global(self.global_type(global.ty)?, &init);
+                Ok(())
+            }
+            None => utils::parse_global(self, globals, global),
+        }
+    }
+
+    fn parse_export(
+        &mut self,
+        exports: &mut ExportSection,
+        export: Export<'_>,
+    ) -> Result<(), reencode::Error> {
+        if export.kind == ExternalKind::Func && export.name == self.init_func {
+            return Ok(());
+        }
+        utils::parse_export(self, exports, export)
+    }
+
+    fn parse_data_section(
+        &mut self,
+        data: &mut DataSection,
+        section: DataSectionReader<'_>,
+    ) -> Result<(), reencode::Error> {
+        utils::parse_data_section(self, data, section)?;
+        self.add_segments(data);
+        Ok(())
+    }
+
+    /// Active segments for defined memories are now part of the snapshot.
+    /// They're turned into empty passive segments to keep indices stable.
+    fn parse_data(
+        &mut self,
+        data: &mut DataSection,
+        datum: Data<'_>,
+    ) -> Result<(), reencode::Error> {
+        match datum.kind {
+            DataKind::Active { memory_index, .. } if memory_index >= self.imported_memories => {
+                data.passive(std::iter::empty());
+                Ok(())
+            }
+            _ => utils::parse_data(self, data, datum),
+        }
+    }
+
+    fn data_count(&mut self, count: u32) -> Result<u32, reencode::Error> {
+        Ok(count + self.segments.len() as u32)
+    }
+
+    fn intersperse_section_hook(
+        &mut self,
+        module: &mut wasm_encoder::Module,
+        _after: Option<SectionId>,
+        before: Option<SectionId>,
+    ) -> Result<(), reencode::Error> {
+        if !self.data_written && !self.segments.is_empty() && before.is_none() {
+            let mut data = DataSection::new();
+            self.add_segments(&mut data);
+            module.section(&data);
+        }
+        Ok(())
+    }
+}
+
+/// Whether section `id` comes after section `other` in a module, `None`
+/// standing for the end of the module.
+fn comes_after(id: Option<SectionId>, other: SectionId) -> bool {
+    id.is_none_or(|id| section_rank(id) > section_rank(other))
+}
+
+/// The position of a non-custom section in the binary order, which differs
+/// from section ids for the tag and data count sections.
+fn section_rank(id: SectionId) -> u8 {
+    match id {
+        SectionId::Custom => 0,
+        SectionId::Type => 1,
+        SectionId::Import => 2,
+        SectionId::Function => 3,
+        SectionId::Table => 4,
+        SectionId::Memory => 5,
+        SectionId::Tag => 6,
+        SectionId::Global => 7,
+        SectionId::Export => 8,
+        SectionId::Start => 9,
+        SectionId::Element => 10,
+        SectionId::DataCount => 11,
+        SectionId::Code => 12,
+        SectionId::Data => 13,
+    }
+}
+
+/// Returns the ranges of non-zero bytes of a memory, merging small gaps.
+/// Past `max_ranges`, the smallest remaining gaps are merged too, so that
+/// a fragmented memory still fits in the module's data segment limit.
+fn data_ranges(bytes: &[u8], max_ranges: usize) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == 0 {
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+        while index < bytes.len() && bytes[index] != 0 {
+            index += 1;
+        }
+        match ranges.last_mut() {
+            Some(last) if start - last.end <= MAX_DATA_GAP => last.end = index,
+            _ => ranges.push(start..index),
+        }
+    }
+
+    if ranges.len() <= max_ranges {
+        return ranges;
+    }
+
+    // Keep the `max_ranges - 1` largest gaps, in case of ties the first ones.
+    let mut gaps: Vec<usize> = (1..ranges.len()).collect();
+    gaps.sort_by_key(|&i| std::cmp::Reverse(ranges[i].start - ranges[i - 1].end));
+    let mut splits = vec![false; ranges.len()];
+    for &i in &gaps[..max_ranges - 1] {
+        splits[i] = true;
+    }
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(max_ranges);
+    for (range, split) in ranges.into_iter().zip(splits) {
+        match merged.last_mut() {
+            Some(last) if !split => last.end = range.end,
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Returns the constant expression initializing global `index` of type `ty`
+/// to `val`. Wasm can't express non-null references as constants.
+fn const_expr(index: u32, val: &Val, ty: ValType) -> Result<ConstExpr, Error> {
+    let expr = match (val, ty) {
+        (Val::I32(v), _) => ConstExpr::i32_const(*v),
+        (Val::I64(v), _) => ConstExpr::i64_const(*v),
+        (Val::F32(bits), _) => ConstExpr::f32_const(Ieee32::new(*bits)),
+        (Val::F64(bits), _) => ConstExpr::f64_const(Ieee64::new(*bits)),
+        (Val::V128(v), _) => ConstExpr::v128_const(v.as_u128() as i128),
+        (val, ValType::Ref(ty)) if val.ref_().is_some_and(|r| r.is_null()) => {
+            let heap_type = RoundtripReencoder
+                .heap_type(ty.heap_type())
+                .map_err(|e| error!("{}", e))?;
+            ConstExpr::ref_null(heap_type)
+        }
+        _ => return err!("cannot snapshot global {index}: it holds a non-null reference"),
+    };
+    Ok(expr)
+}
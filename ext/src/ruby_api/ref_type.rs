@@ -0,0 +1,210 @@
+use super::{array_ref::ArrayType, func::FuncType, root, struct_ref::StructType};
+use crate::{define_rb_intern, err, helpers::SymbolEnum};
+use lazy_static::lazy_static;
+use magnus::{
+    function, method, prelude::*, scan_args, DataTypeFunctions, Error, Object, Ruby, Symbol,
+    TryConvert, TypedData, Value,
+};
+use wasmtime::{HeapType, RefType as RefTypeImpl};
+
+define_rb_intern!(
+    NULLABLE => "nullable",
+    FUNC => "func",
+    NOFUNC => "nofunc",
+    EXTERN => "extern",
+    NOEXTERN => "noextern",
+    ANY => "any",
+    EQ => "eq",
+    I31 => "i31",
+    STRUCT => "struct",
+    ARRAY => "array",
+    NONE => "none",
+);
+
+lazy_static! {
+    static ref HEAP_TYPE_MAPPING: SymbolEnum<'static, HeapType> = {
+        let mapping = vec![
+            (*FUNC, HeapType::Func),
+            (*NOFUNC, HeapType::NoFunc),
+            (*EXTERN, HeapType::Extern),
+            (*NOEXTERN, HeapType::NoExtern),
+            (*ANY, HeapType::Any),
+            (*EQ, HeapType::Eq),
+            (*I31, HeapType::I31),
+            (*STRUCT, HeapType::Struct),
+            (*ARRAY, HeapType::Array),
+            (*NONE, HeapType::None),
+        ];
+
+        SymbolEnum::new("heap type", mapping)
+    };
+}
+
+/// @yard
+/// @rename Wasmtime::RefType
+/// Represents a WebAssembly reference type, from the typed function
+/// references and GC proposals. Can be used wherever a type Symbol is
+/// accepted, e.g. in {Func.new}, {FuncType.new}, {Table.new} or
+/// {Global.var}.
+///
+/// Nullable abstract reference types are reported as Symbols, e.g.
+/// +:funcref+ rather than +RefType.new(:func)+.
+///
+/// @example A non-nullable reference to a function taking and returning an i32
+///   func_type = Wasmtime::FuncType.new(engine, [:i32], [:i32])
+///   Wasmtime::RefType.new(func_type, nullable: false)
+/// @see https://docs.rs/wasmtime/latest/wasmtime/struct.RefType.html Wasmtime's Rust doc
+#[derive(TypedData)]
+#[magnus(
+    class = "Wasmtime::RefType",
+    size,
+    mark,
+    free_immediately,
+    unsafe_generics
+)]
+pub struct RefType {
+    inner: RefTypeImpl,
+}
+
+impl DataTypeFunctions for RefType {}
+
+impl RefType {
+    /// @yard
+    /// @def new(heap_type, nullable: true)
+    /// @param heap_type [Symbol, FuncType, StructType, ArrayType] One of
+    ///   +:func+, +:nofunc+, +:extern+, +:noextern+, +:any+, +:eq+, +:i31+,
+    ///   +:struct+, +:array+ or +:none+, or a concrete type.
+    /// @param nullable [Boolean] Whether +nil+ is a valid value.
+    /// @return [RefType]
+    pub fn new(args: &[Value]) -> Result<Self, Error> {
+        let args = scan_args::scan_args::<(Value,), (), (), (), _, ()>(args)?;
+        let kw =
+            scan_args::get_kwargs::<_, (), (Option<bool>,), ()>(args.keywords, &[], &[*NULLABLE])?;
+        let (heap_type,) = args.required;
+        let nullable = kw.optional.0.unwrap_or(true);
+
+        Ok(Self {
+            inner: RefTypeImpl::new(nullable, to_heap_type(heap_type)?),
+        })
+    }
+
+    pub fn from_inner(inner: RefTypeImpl) -> Self {
+        Self { inner }
+    }
+
+    /// @yard
+    /// @def nullable?
+    /// @return [Boolean]
+    pub fn is_nullable(&self) -> bool {
+        self.inner.is_nullable()
+    }
+
+    /// @yard
+    /// @return [Symbol, FuncType, StructType, ArrayType] The referenced heap
+    ///   type, see {.new}.
+    pub fn heap_type(ruby: &Ruby, rb_self: &Self) -> Result<Value, Error> {
+        let heap_type = rb_self.inner.heap_type();
+        let id = match heap_type {
+            HeapType::ConcreteFunc(ty) => {
+                return Ok(ruby.obj_wrap(FuncType::from_inner(ty.clone())).as_value())
+            }
+            HeapType::ConcreteStruct(ty) => {
+                return Ok(ruby.obj_wrap(StructType::from_inner(ty.clone())).as_value())
+            }
+            HeapType::ConcreteArray(ty) => {
+                return Ok(ruby.obj_wrap(ArrayType::from_inner(ty.clone())).as_value())
+            }
+            HeapType::Func => *FUNC,
+            HeapType::NoFunc => *NOFUNC,
+            HeapType::Extern => *EXTERN,
+            HeapType::NoExtern => *NOEXTERN,
+            HeapType::Any => *ANY,
+            HeapType::Eq => *EQ,
+            HeapType::I31 => *I31,
+            HeapType::Struct => *STRUCT,
+            HeapType::Array => *ARRAY,
+            HeapType::None => *NONE,
+            _ => return err!("unsupported heap type {heap_type}"),
+        };
+        Ok(Symbol::from(id).as_value())
+    }
+
+    /// @yard
+    /// @def ==(other)
+    /// @param other [Object]
+    /// @return [Boolean] Whether +other+ is the same reference type.
+    pub fn eq(&self, other: Value) -> bool {
+        let Ok(other) = <&Self>::try_convert(other) else {
+            return false;
+        };
+        // Wasmtime panics when comparing concrete types from different engines.
+        if let (Some(a), Some(b)) = (
+            concrete_engine(self.inner.heap_type()),
+            concrete_engine(other.inner.heap_type()),
+        ) {
+            if !wasmtime::Engine::same(a, b) {
+                return false;
+            }
+        }
+        RefTypeImpl::eq(&self.inner, &other.inner)
+    }
+
+    /// @yard
+    /// @return [String] The type in the WebAssembly text format, e.g.
+    ///   +(ref null func)+.
+    pub fn to_s(&self) -> String {
+        self.inner.to_string()
+    }
+
+    /// @yard
+    /// @return [String]
+    pub fn inspect(&self) -> String {
+        format!("#<Wasmtime::RefType {}>", self.inner)
+    }
+
+    /// Returns the reference type for use with `engine`, raising when it
+    /// refers to a concrete type of another engine, which Wasmtime would
+    /// panic on.
+    pub fn for_engine(&self, engine: &wasmtime::Engine) -> Result<RefTypeImpl, Error> {
+        match concrete_engine(self.inner.heap_type()) {
+            Some(other) if !wasmtime::Engine::same(engine, other) => {
+                err!("type used with wrong engine")
+            }
+            _ => Ok(self.inner.clone()),
+        }
+    }
+}
+
+fn to_heap_type(value: Value) -> Result<HeapType, Error> {
+    if let Ok(ty) = <&FuncType>::try_convert(value) {
+        return Ok(HeapType::ConcreteFunc(ty.get().clone()));
+    }
+    if let Ok(ty) = <&StructType>::try_convert(value) {
+        return Ok(HeapType::ConcreteStruct(ty.get().clone()));
+    }
+    if let Ok(ty) = <&ArrayType>::try_convert(value) {
+        return Ok(HeapType::ConcreteArray(ty.get().clone()));
+    }
+    HEAP_TYPE_MAPPING.get(value)
+}
+
+fn concrete_engine(heap_type: &HeapType) -> Option<&wasmtime::Engine> {
+    match heap_type {
+        HeapType::ConcreteFunc(ty) => Some(ty.engine()),
+        HeapType::ConcreteStruct(ty) => Some(ty.engine()),
+        HeapType::ConcreteArray(ty) => Some(ty.engine()),
+        _ => None,
+    }
+}
+
+pub fn init(ruby: &Ruby) -> Result<(), Error> {
+    let class = root().define_class("RefType", ruby.class_object())?;
+    class.define_singleton_method("new", function!(RefType::new, -1))?;
+    class.define_method("nullable?", method!(RefType::is_nullable, 0))?;
+    class.define_method("heap_type", method!(RefType::heap_type, 0))?;
+    class.define_method("==", method!(RefType::eq, 1))?;
+    class.define_method("to_s", method!(RefType::to_s, 0))?;
+    class.define_method("inspect", method!(RefType::inspect, 0))?;
+
+    Ok(())
+}
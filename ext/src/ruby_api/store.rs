@@ -1,7 +1,11 @@
 use super::errors::wasi_exit_error;
-use super::{caller::Caller, engine::Engine, root, trap::Trap};
+use super::{
+    caller::Caller, convert::V128Format, engine::Engine, interrupt_handle::InterruptHandle,
+    memory::IoBuffers, root, trap::Trap,
+};
+use crate::helpers::{with_gvl, StaticId};
 use crate::ruby_api::wasi_config::WasiRetainedData;
-use crate::{define_rb_intern, error, WasiConfig};
+use crate::{define_rb_intern, err, error, WasiConfig};
 use magnus::value::ReprValue;
 use magnus::value::StaticSymbol;
 use magnus::{
@@ -9,18 +13,22 @@ use magnus::{
     gc::{Compactor, Marker},
     method, scan_args,
     typed_data::Obj,
-    value::Opaque,
-    DataTypeFunctions, Error, ExceptionClass, IntoValue, Module, Object, Ruby, TryConvert,
-    TypedData, Value,
+    value::{Id, Opaque},
+    DataTypeFunctions, Error, ExceptionClass, IntoValue, Module, Object, RArray, Ruby, Symbol,
+    TryConvert, TypedData, Value,
 };
 use magnus::{Class, RHash};
 use rb_sys::tracking_allocator::{ManuallyTracked, TrackingAllocator};
 use std::borrow::Borrow;
 use std::cell::UnsafeCell;
 use std::convert::TryFrom;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
 use wasmtime::{
-    AsContext, AsContextMut, ResourceLimiter, Store as StoreImpl, StoreContext, StoreContextMut,
-    StoreLimits, StoreLimitsBuilder,
+    AsContext, AsContextMut, CallHook, ResourceLimiter, Store as StoreImpl, StoreContext,
+    StoreContextMut, StoreLimits, StoreLimitsBuilder, UpdateDeadline,
 };
 use wasmtime_wasi::p1::WasiP1Ctx;
 use wasmtime_wasi::{I32Exit, ResourceTable};
@@ -30,8 +38,51 @@ define_rb_intern!(
     WASI_CONFIG => "wasi_config",
     WASI_P1_CONFIG => "wasi_p1_config",
     LIMITS => "limits",
+    V128 => "v128",
+    CONTINUE => "continue",
+    TRAP => "trap",
+    MEMORY_GROWING => "memory_growing",
+    TABLE_GROWING => "table_growing",
+    MEMORY_GROW_FAILED => "memory_grow_failed",
+    TABLE_GROW_FAILED => "table_grow_failed",
+    CALLING_WASM => "calling_wasm",
+    RETURNING_FROM_WASM => "returning_from_wasm",
+    CALLING_HOST => "calling_host",
+    RETURNING_FROM_HOST => "returning_from_host",
 );
 
+/// A Ruby callable registered on a {Store}, e.g. through
+/// {Store#on_epoch_deadline}. The Store's Ruby object is kept alongside the
+/// callable so that it can be yielded back to Ruby.
+pub struct StoreCallback {
+    store: Value,
+    callable: Value,
+}
+
+impl StoreCallback {
+    pub fn new(store: Value, callable: Value) -> Self {
+        Self { store, callable }
+    }
+
+    pub fn store(&self) -> Value {
+        self.store
+    }
+
+    pub fn callable(&self) -> Value {
+        self.callable
+    }
+
+    pub fn mark(&self, marker: &Marker) {
+        marker.mark_movable(self.store);
+        marker.mark_movable(self.callable);
+    }
+
+    pub fn compact(&mut self, compactor: &Compactor) {
+        self.store = compactor.location(self.store);
+        self.callable = compactor.location(self.callable);
+    }
+}
+
 pub struct StoreData {
     user_data: Value,
     wasi_p1: Option<WasiP1Ctx>,
@@ -41,6 +92,19 @@ pub struct StoreData {
     last_error: Option<Error>,
     store_limits: TrackingResourceLimiter,
     resource_table: ResourceTable,
+    epoch_deadline_callback: Option<StoreCallback>,
+    call_hook: Option<Value>,
+    epoch_deadline_at: u64,
+    epoch: Arc<AtomicU64>,
+    epoch_interval_ms: Arc<AtomicU64>,
+    timeout_at: Option<u64>,
+    timed_out: bool,
+    interrupted: Option<Arc<AtomicBool>>,
+    fuel_consumed: u64,
+    fuel_set: u64,
+    async_support: bool,
+    epoch_yield_delta: Option<u64>,
+    v128_format: V128Format,
 }
 
 impl StoreData {
@@ -48,6 +112,10 @@ impl StoreData {
         self.user_data
     }
 
+    pub fn v128_format(&self) -> V128Format {
+        self.v128_format
+    }
+
     pub fn has_wasi_p1_ctx(&self) -> bool {
         self.wasi_p1.is_some()
     }
@@ -66,12 +134,62 @@ impl StoreData {
         self.refs.push(value);
     }
 
+    /// Tracks an +IO::Buffer+ mapping one of the store's memories, so that
+    /// it's released before any memory grows.
+    pub fn track_io_buffer(&mut self, buffer: Value) -> Result<(), Error> {
+        self.store_limits.io_buffers.track(buffer)
+    }
+
     pub fn set_error(&mut self, error: Error) {
         self.last_error = Some(error);
     }
 
+    /// The number of ticks before Wasmtime should call
+    /// [`epoch_deadline_reached`]: until the epoch deadline or the timeout,
+    /// whichever comes first. That's at most one tick once an
+    /// `InterruptHandle` exists, so that interruptions are noticed on the
+    /// next tick.
+    fn ticks_until_check(&self) -> u64 {
+        let at = match self.timeout_at {
+            Some(timeout_at) => timeout_at.min(self.epoch_deadline_at),
+            None => self.epoch_deadline_at,
+        };
+        let ticks = at.saturating_sub(self.epoch.load(Ordering::Relaxed));
+        match self.interrupted {
+            Some(_) => ticks.min(1),
+            None => ticks,
+        }
+    }
+
+    /// Whether the last interruption was caused by [`Store::with_timeout`].
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    pub fn is_async(&self) -> bool {
+        self.async_support
+    }
+
+    /// Ruby callbacks can't run on the fiber stack used by async calls, see
+    /// [`crate::helpers::block_on`]. Only host functions are moved back to
+    /// Ruby's stack.
+    pub fn check_async_callbacks(&self) -> Result<(), Error> {
+        if self.epoch_deadline_callback.is_some()
+            || self.call_hook.is_some()
+            || self.store_limits.ruby_limiter.is_some()
+        {
+            return err!(
+                "Store#on_epoch_deadline, Store#call_hook and Store#resource_limiter= are not supported by async calls"
+            );
+        }
+
+        Ok(())
+    }
+
     pub fn take_error(&mut self) -> Option<Error> {
-        self.last_error.take()
+        self.last_error
+            .take()
+            .or_else(|| self.store_limits.take_error())
     }
 
     pub fn check_socket_errors(&mut self) {
@@ -111,6 +229,16 @@ impl StoreData {
         for value in self.refs.iter() {
             marker.mark_movable(*value);
         }
+
+        if let Some(ref callback) = self.epoch_deadline_callback {
+            callback.mark(marker);
+        }
+
+        if let Some(call_hook) = self.call_hook {
+            marker.mark_movable(call_hook);
+        }
+
+        self.store_limits.mark(marker);
     }
 
     pub fn compact(&mut self, compactor: &Compactor) {
@@ -119,6 +247,16 @@ impl StoreData {
         for value in self.refs.iter_mut() {
             *value = compactor.location(*value);
         }
+
+        if let Some(ref mut callback) = self.epoch_deadline_callback {
+            callback.compact(compactor);
+        }
+
+        if let Some(call_hook) = self.call_hook {
+            self.call_hook = Some(compactor.location(call_hook));
+        }
+
+        self.store_limits.compact(compactor);
     }
 }
 
@@ -136,6 +274,10 @@ impl DataTypeFunctions for Store {
         self.context().data().mark(marker);
     }
 
+    fn size(&self) -> usize {
+        size_of_val(self) + self.context().data().refs.capacity() * size_of::<Value>()
+    }
+
     fn compact(&self, compactor: &Compactor) {
         self.context_mut().data_mut().compact(compactor);
     }
@@ -147,7 +289,7 @@ unsafe impl Send for StoreData {}
 impl Store {
     /// @yard
     ///
-    /// @def new(engine, data = nil, wasi_config: nil, wasi_p1_config: nil, limits: nil)
+    /// @def new(engine, data = nil, wasi_config: nil, wasi_p1_config: nil, limits: nil, v128: :integer)
     /// @param engine [Wasmtime::Engine]
     ///   The engine for this store.
     /// @param data [Object]
@@ -169,6 +311,10 @@ impl Store {
     ///   The maximum number of tables that can be created for a Store.
     /// @option limits memories [Integer]
     ///   The maximum number of linear memories that can be created for a Store.
+    /// @param v128 [Symbol]
+    ///   How +v128+ values are returned to Ruby: +:integer+ (the default) for
+    ///   unsigned 128-bit Integers, or +:string+ for 16-byte little-endian
+    ///   binary Strings. Both are accepted when passing +v128+ values to Wasm.
     /// @return [Wasmtime::Store]
     ///
     /// @example
@@ -182,12 +328,17 @@ impl Store {
         let kw = scan_args::get_kwargs::<
             _,
             (),
-            (Option<&WasiConfig>, Option<&WasiConfig>, Option<RHash>),
+            (
+                Option<&WasiConfig>,
+                Option<&WasiConfig>,
+                Option<RHash>,
+                Option<Value>,
+            ),
             (),
         >(
             args.keywords,
             &[],
-            &[*WASI_CONFIG, *WASI_P1_CONFIG, *LIMITS],
+            &[*WASI_CONFIG, *WASI_P1_CONFIG, *LIMITS, *V128],
         )?;
 
         let (engine,) = args.required;
@@ -218,6 +369,12 @@ impl Store {
         }
         .build();
         let limiter = TrackingResourceLimiter::new(limiter);
+        let v128_format = kw
+            .optional
+            .3
+            .map(V128Format::from_value)
+            .transpose()?
+            .unwrap_or_default();
 
         let eng = engine.get();
         let store_data = StoreData {
@@ -229,12 +386,30 @@ impl Store {
             last_error: Default::default(),
             store_limits: limiter,
             resource_table: Default::default(),
+            epoch_deadline_callback: None,
+            call_hook: None,
+            epoch_deadline_at: 0,
+            epoch: engine.epoch(),
+            epoch_interval_ms: engine.epoch_interval_ms(),
+            timeout_at: None,
+            timed_out: false,
+            interrupted: None,
+            fuel_consumed: 0,
+            fuel_set: 0,
+            async_support: engine.is_async(),
+            epoch_yield_delta: None,
+            v128_format,
         };
         let store = Self {
             inner: UnsafeCell::new(StoreImpl::new(eng, store_data)),
         };
 
-        unsafe { &mut *store.inner.get() }.limiter(|data| &mut data.store_limits);
+        let inner = unsafe { &mut *store.inner.get() };
+        inner.limiter(|data| &mut data.store_limits);
+        // Wasmtime's epoch can run ahead of the engine's tick count when an
+        // `InterruptHandle` of another store bumps it. Deadlines are checked
+        // against the tick count so that such bumps don't trap this store.
+        inner.epoch_deadline_callback(epoch_deadline_reached);
 
         Ok(store)
     }
@@ -260,13 +435,34 @@ impl Store {
     /// @def set_fuel(fuel)
     /// @raise [Error] if fuel consumption is not enabled via {Wasmtime::Engine#new}
     pub fn set_fuel(&self, fuel: u64) -> Result<(), Error> {
-        unsafe { &mut *self.inner.get() }
-            .set_fuel(fuel)
-            .map_err(|e| error!("{}", e))?;
+        set_fuel(unsafe { &mut *self.inner.get() }, fuel).map_err(|e| error!("{}", e))?;
 
         Ok(())
     }
 
+    /// @yard
+    /// Returns the total amount of fuel consumed by the {Store} since it was
+    /// created, across all calls and refuelings.
+    ///
+    /// @return [Integer]
+    /// @raise [Error] if fuel consumption is not enabled via {Wasmtime::Engine#new}
+    pub fn fuel_consumed(&self) -> Result<u64, Error> {
+        fuel_consumed(self.inner_ref()).map_err(|e| error!("{}", e))
+    }
+
+    /// @yard
+    /// Configures Wasm to yield back to the caller every +interval+ units of
+    /// fuel consumed when running asynchronously. +nil+ disables yielding.
+    ///
+    /// @def fuel_async_yield_interval=(interval)
+    /// @param interval [Integer, nil]
+    /// @raise [Error] if the {Engine} is not configured for async execution
+    pub fn set_fuel_async_yield_interval(&self, interval: Option<u64>) -> Result<(), Error> {
+        unsafe { &mut *self.inner.get() }
+            .fuel_async_yield_interval(interval)
+            .map_err(|e| error!("{}", e))
+    }
+
     /// @yard
     /// Sets the epoch deadline to a certain number of ticks in the future.
     ///
@@ -278,7 +474,280 @@ impl Store {
     /// @param ticks_beyond_current [Integer] The number of ticks before this store reaches the deadline.
     /// @return [nil]
     pub fn set_epoch_deadline(&self, ticks_beyond_current: u64) {
-        unsafe { &mut *self.inner.get() }.set_epoch_deadline(ticks_beyond_current);
+        let inner = unsafe { &mut *self.inner.get() };
+        apply_epoch_deadline(inner, ticks_beyond_current);
+    }
+
+    /// @yard
+    /// Runs the block with an epoch deadline derived from +seconds+ and the
+    /// interval of the {Engine}'s epoch timer. Wasm still running when the
+    /// deadline is reached raises a {Timeout}.
+    ///
+    /// Requires the {Engine} to be created with +epoch_interruption: true+
+    /// and its timer started with {Engine#start_epoch_interval}. The timeout's
+    /// precision is bound to the timer's interval.
+    ///
+    /// The timeout takes precedence over {#on_epoch_deadline}: the block given
+    /// to it isn't called for the timeout. The {Store}'s own epoch deadline
+    /// is unaffected, and still applies once the block returns.
+    ///
+    /// @def with_timeout(seconds, &block)
+    /// @param seconds [Float, Integer] The maximum time to run Wasm for.
+    /// @yield [store] The calls to run with the timeout.
+    /// @yieldparam store [Store] This store.
+    /// @return [Object] The block's result.
+    /// @raise [Timeout] if Wasm runs beyond the deadline.
+    ///
+    /// @example
+    ///   engine = Wasmtime::Engine.new(epoch_interruption: true)
+    ///   engine.start_epoch_interval(10)
+    ///   store = Wasmtime::Store.new(engine)
+    ///   # ...
+    ///   store.with_timeout(0.5) { instance.invoke("run") }
+    pub fn with_timeout(ruby: &Ruby, rb_self: Obj<Self>, seconds: f64) -> Result<Value, Error> {
+        if !seconds.is_finite() || seconds < 0.0 {
+            return Err(Error::new(
+                ruby.exception_arg_error(),
+                format!("invalid timeout: {seconds}"),
+            ));
+        }
+
+        let interval_ms = rb_self
+            .context()
+            .data()
+            .epoch_interval_ms
+            .load(Ordering::Relaxed);
+        if interval_ms == 0 {
+            return err!("Store#with_timeout requires a running epoch timer, see Engine#start_epoch_interval");
+        }
+
+        if rb_self.context().data().timeout_at.is_some() {
+            return err!("Store#with_timeout cannot be nested");
+        }
+
+        let ticks = ((seconds * 1000.0) / interval_ms as f64).ceil().max(1.0) as u64;
+        let inner = unsafe { &mut *rb_self.inner.get() };
+        let data = inner.data_mut();
+        data.timeout_at = Some(data.epoch.load(Ordering::Relaxed).saturating_add(ticks));
+        data.timed_out = false;
+        let ticks = data.ticks_until_check();
+        inner.epoch_deadline_callback(epoch_deadline_reached);
+        inner.set_epoch_deadline(ticks);
+
+        let result = ruby.yield_value(rb_self);
+
+        let inner = unsafe { &mut *rb_self.inner.get() };
+        let data = inner.data_mut();
+        data.timeout_at = None;
+        data.timed_out = false;
+        let ticks = data.ticks_until_check();
+        inner.set_epoch_deadline(ticks);
+
+        result
+    }
+
+    /// @yard
+    /// Registers a block to be called whenever the {Store}'s epoch deadline is
+    /// reached, instead of trapping. The block decides whether execution
+    /// should carry on or be interrupted:
+    /// * +[:continue, delta]+ resumes execution and sets a new deadline +delta+ ticks in the future.
+    /// * +:trap+ interrupts execution with a {Trap} (code +:interrupt+).
+    ///
+    /// Exceptions raised by the block are propagated to the caller of the
+    /// interrupted Wasm code.
+    ///
+    /// @see https://docs.rs/wasmtime/latest/wasmtime/struct.Store.html#method.epoch_deadline_callback Rust's doc on +epoch_deadline_callback+ for more details.
+    /// @def on_epoch_deadline(&block)
+    /// @yield [store] The block to call when the deadline is reached.
+    /// @yieldparam store [Store] This store.
+    /// @yieldreturn [Symbol, Array(Symbol, Integer)] +:trap+ or +[:continue, delta]+.
+    /// @return [nil]
+    ///
+    /// @example Allow 3 time slices before interrupting
+    ///   slices = 0
+    ///   store.on_epoch_deadline do |_store|
+    ///     slices += 1
+    ///     slices < 3 ? [:continue, 1] : :trap
+    ///   end
+    pub fn on_epoch_deadline(ruby: &Ruby, rb_self: Obj<Self>) -> Result<(), Error> {
+        let block = ruby.block_proc()?;
+        let callback = StoreCallback::new(rb_self.as_value(), block.as_value());
+        let inner = unsafe { &mut *rb_self.inner.get() };
+
+        inner.data_mut().epoch_deadline_callback = Some(callback);
+        inner.data_mut().epoch_yield_delta = None;
+        inner.epoch_deadline_callback(epoch_deadline_reached);
+
+        Ok(())
+    }
+
+    /// @yard
+    /// Configures async calls to yield to the +Fiber.scheduler+ when the epoch
+    /// deadline is reached, and then extend the deadline by +delta+ ticks.
+    /// Replaces any block given to {#on_epoch_deadline}.
+    ///
+    /// @def epoch_deadline_async_yield_and_update(delta)
+    /// @param delta [Integer]
+    /// @return [nil]
+    /// @raise [Error] if the {Engine} is not configured for async execution
+    /// @see Func#call_async
+    pub fn epoch_deadline_async_yield_and_update(&self, delta: u64) -> Result<(), Error> {
+        let inner = unsafe { &mut *self.inner.get() };
+        if !inner.data().is_async() {
+            return err!(
+                "Store#epoch_deadline_async_yield_and_update requires an Engine created with async_support: true"
+            );
+        }
+
+        inner.data_mut().epoch_deadline_callback = None;
+        inner.data_mut().epoch_yield_delta = Some(delta);
+        inner.epoch_deadline_callback(epoch_deadline_reached);
+
+        Ok(())
+    }
+
+    /// @yard
+    /// Returns a handle that can interrupt Wasm running in this {Store} from
+    /// any thread or Ractor, without holding this Ractor's GVL.
+    ///
+    /// Requires the {Engine} to be created with +epoch_interruption: true+,
+    /// but not a running epoch timer. Once a handle exists, the {Store} checks
+    /// for interruptions on every epoch tick; the epoch deadline and
+    /// {#on_epoch_deadline} keep working as before.
+    ///
+    /// @return [InterruptHandle]
+    /// @see InterruptHandle#interrupt!
+    pub fn interrupt_handle(
+        ruby: &Ruby,
+        rb_self: Obj<Self>,
+    ) -> Result<Obj<InterruptHandle>, Error> {
+        let inner = unsafe { &mut *rb_self.inner.get() };
+        let interrupted = match inner.data().interrupted {
+            Some(ref interrupted) => interrupted.clone(),
+            None => {
+                let interrupted = Arc::new(AtomicBool::new(false));
+                inner.data_mut().interrupted = Some(interrupted.clone());
+                inner.epoch_deadline_callback(epoch_deadline_reached);
+
+                let data = inner.data();
+                let ticks = data.ticks_until_check();
+                inner.set_epoch_deadline(ticks);
+                interrupted
+            }
+        };
+
+        let handle = ruby.obj_wrap(InterruptHandle::new(interrupted, inner.engine().clone()));
+        handle.freeze();
+        Ok(handle)
+    }
+
+    /// @yard
+    /// Registers a block to be called on every transition between the host
+    /// and Wasm. Raising from the block aborts the transition: the exception
+    /// is propagated to the caller of the Wasm code.
+    ///
+    /// Calling it without a block removes the previously registered hook.
+    ///
+    /// @see https://docs.rs/wasmtime/latest/wasmtime/struct.Store.html#method.call_hook Rust's doc on +call_hook+ for more details.
+    /// @def call_hook(&block)
+    /// @yield [kind] The block to call on each transition.
+    /// @yieldparam kind [Symbol] One of +:calling_wasm+, +:returning_from_wasm+,
+    ///   +:calling_host+ or +:returning_from_host+.
+    /// @return [nil]
+    ///
+    /// @example Measure time spent in Wasm
+    ///   in_wasm = 0.0
+    ///   started_at = nil
+    ///   store.call_hook do |kind|
+    ///     case kind
+    ///     when :calling_wasm, :returning_from_host
+    ///       started_at = Process.clock_gettime(Process::CLOCK_MONOTONIC)
+    ///     when :returning_from_wasm, :calling_host
+    ///       in_wasm += Process.clock_gettime(Process::CLOCK_MONOTONIC) - started_at
+    ///     end
+    ///   end
+    pub fn call_hook(ruby: &Ruby, rb_self: Obj<Self>) -> Result<(), Error> {
+        let inner = unsafe { &mut *rb_self.inner.get() };
+
+        if !ruby.block_given() {
+            inner.data_mut().call_hook = None;
+            return Ok(());
+        }
+
+        inner.data_mut().call_hook = Some(ruby.block_proc()?.as_value());
+        inner.call_hook(|mut context, kind| {
+            let Some(call_hook) = context.data().call_hook else {
+                return Ok(());
+            };
+
+            let kind = match kind {
+                CallHook::CallingWasm => *CALLING_WASM,
+                CallHook::ReturningFromWasm => *RETURNING_FROM_WASM,
+                CallHook::CallingHost => *CALLING_HOST,
+                CallHook::ReturningFromHost => *RETURNING_FROM_HOST,
+            };
+
+            with_gvl(|| call_hook.funcall::<_, _, Value>("call", (Symbol::from(kind),)))
+                .map(|_| ())
+                .map_err(|e| {
+                    context.data_mut().set_error(e);
+                    wasmtime::Error::msg("")
+                })
+        });
+
+        Ok(())
+    }
+
+    /// @yard
+    /// Sets a Ruby object that decides at runtime whether memories and tables
+    /// may grow. The object must respond to:
+    /// * +memory_growing(current, desired, maximum)+
+    /// * +table_growing(current, desired, maximum)+
+    ///
+    /// Both return a truthy value to allow the growth, or a falsy one to deny it.
+    /// +current+ and +desired+ are in bytes for memories and in elements for
+    /// tables; +maximum+ is +nil+ when unbounded.
+    ///
+    /// The object may also respond to +memory_grow_failed(message)+ and
+    /// +table_grow_failed(message)+ to be notified when a growth fails.
+    ///
+    /// The object is only consulted when the growth is allowed by the
+    /// +limits+ given to {.new}. Exceptions it raises are propagated to the
+    /// code that attempted the growth.
+    ///
+    /// @see https://docs.rs/wasmtime/latest/wasmtime/trait.ResourceLimiter.html Wasmtime's Rust doc
+    /// @def resource_limiter=(limiter)
+    /// @param limiter [Object, nil] The limiter, or +nil+ to remove it.
+    pub fn set_resource_limiter(
+        ruby: &Ruby,
+        rb_self: Obj<Self>,
+        limiter: Value,
+    ) -> Result<(), Error> {
+        let limiter = if limiter.is_nil() {
+            None
+        } else {
+            for method in [*MEMORY_GROWING, *TABLE_GROWING] {
+                if !limiter.respond_to(method, false)? {
+                    return Err(Error::new(
+                        ruby.exception_arg_error(),
+                        format!(
+                            "resource limiter must respond to #{}",
+                            Symbol::from(method).name()?
+                        ),
+                    ));
+                }
+            }
+            Some(limiter)
+        };
+
+        rb_self.context_mut().data_mut().store_limits.ruby_limiter = limiter;
+        Ok(())
+    }
+
+    /// @yard
+    /// @return [Object, nil] The limiter set with {#resource_limiter=}.
+    pub fn resource_limiter(&self) -> Option<Value> {
+        self.context().data().store_limits.ruby_limiter
     }
 
     /// @yard
@@ -369,6 +838,31 @@ impl StoreContextValue<'_> {
         }
     }
 
+    /// Whether both values refer to the same store, e.g. a {Store} and a
+    /// {Caller} of one of its calls.
+    pub fn is_same_store(&self, other: &Self) -> Result<bool, Error> {
+        Ok(std::ptr::eq(
+            self.context()?.data(),
+            other.context()?.data(),
+        ))
+    }
+
+    pub fn track_io_buffer(&self, buffer: Value) -> Result<(), Error> {
+        let ruby = Ruby::get().unwrap();
+        match self {
+            Self::Store(store) => ruby
+                .get_inner_ref(store)
+                .context_mut()
+                .data_mut()
+                .track_io_buffer(buffer),
+            Self::Caller(caller) => {
+                let caller = ruby.get_inner_ref(caller);
+                caller.context_mut()?.data_mut().track_io_buffer(buffer)?;
+                caller.track_io_buffer(buffer)
+            }
+        }
+    }
+
     pub fn set_last_error(&self, error: Error) {
         let ruby = Ruby::get().unwrap();
         match self {
@@ -392,11 +886,23 @@ impl StoreContextValue<'_> {
             wasi_exit_error().new_instance((exit.0,)).unwrap().into()
         } else {
             Trap::try_from(error)
-                .map(|trap| trap.into_error(ruby))
+                .map(|trap| {
+                    if trap.is_interrupt() && self.timed_out() {
+                        trap.into_timeout_error(ruby)
+                    } else {
+                        trap.into_error(ruby)
+                    }
+                })
                 .unwrap_or_else(|e| error!("{}", e))
         }
     }
 
+    fn timed_out(&self) -> bool {
+        self.context()
+            .map(|context| context.data().timed_out())
+            .unwrap_or(false)
+    }
+
     pub fn retain(&self, value: Value) -> Result<(), Error> {
         self.context_mut()?.data_mut().retain(value);
         Ok(())
@@ -421,6 +927,115 @@ impl StoreContextValue<'_> {
     }
 }
 
+/// Sets the fuel of a store, keeping track of the fuel consumed until now for
+/// `Store#fuel_consumed`.
+pub fn set_fuel(mut store: impl AsContextMut<Data = StoreData>, fuel: u64) -> wasmtime::Result<()> {
+    let mut context = store.as_context_mut();
+    let remaining = context.get_fuel()?;
+    let data = context.data_mut();
+    data.fuel_consumed += data.fuel_set.saturating_sub(remaining);
+    data.fuel_set = fuel;
+    context.set_fuel(fuel)
+}
+
+/// Returns the fuel consumed by a store since its creation.
+pub fn fuel_consumed(store: impl AsContext<Data = StoreData>) -> wasmtime::Result<u64> {
+    let context = store.as_context();
+    let remaining = context.get_fuel()?;
+    let data = context.data();
+    Ok(data.fuel_consumed + data.fuel_set.saturating_sub(remaining))
+}
+
+/// Sets the epoch deadline `ticks` in the future. The deadline is tracked
+/// against the engine's epoch count, so that Wasmtime's deadline can be kept
+/// at most one tick away once an `InterruptHandle` exists: this lets
+/// [`epoch_deadline_reached`] check for interruptions on every tick without
+/// moving the store's actual deadline.
+fn apply_epoch_deadline(store: &mut StoreImpl<StoreData>, ticks: u64) {
+    let data = store.data_mut();
+    data.epoch_deadline_at = data.epoch.load(Ordering::Relaxed).saturating_add(ticks);
+    let ticks = data.ticks_until_check();
+    store.set_epoch_deadline(ticks);
+}
+
+fn epoch_deadline_reached(
+    mut context: StoreContextMut<'_, StoreData>,
+) -> wasmtime::Result<UpdateDeadline> {
+    let data = context.data_mut();
+
+    if let Some(ref interrupted) = data.interrupted {
+        if interrupted.swap(false, Ordering::SeqCst) {
+            // An explicit interruption is not a timeout, even within `with_timeout`.
+            data.timed_out = false;
+            return Ok(UpdateDeadline::Interrupt);
+        }
+    }
+
+    let epoch = data.epoch.load(Ordering::Relaxed);
+    if data
+        .timeout_at
+        .is_some_and(|timeout_at| epoch >= timeout_at)
+    {
+        data.timed_out = true;
+        return Ok(UpdateDeadline::Interrupt);
+    }
+
+    if epoch < data.epoch_deadline_at {
+        return Ok(UpdateDeadline::Continue(data.ticks_until_check()));
+    }
+
+    if let Some(delta) = data.epoch_yield_delta {
+        data.epoch_deadline_at = epoch.saturating_add(delta);
+        return Ok(UpdateDeadline::Yield(data.ticks_until_check()));
+    }
+
+    let Some(ref callback) = data.epoch_deadline_callback else {
+        return Ok(UpdateDeadline::Interrupt);
+    };
+    let (store, callable) = (callback.store(), callback.callable());
+
+    let result = with_gvl(|| {
+        let ruby = Ruby::get().unwrap();
+        callable
+            .funcall::<_, _, Value>("call", (store,))
+            .and_then(|value| to_update_deadline(&ruby, value))
+    });
+
+    match result {
+        Ok(UpdateDeadline::Continue(ticks)) => {
+            data.epoch_deadline_at = epoch.saturating_add(ticks);
+            Ok(UpdateDeadline::Continue(data.ticks_until_check()))
+        }
+        Ok(update) => Ok(update),
+        Err(e) => {
+            data.set_error(e);
+            Err(wasmtime::Error::msg(""))
+        }
+    }
+}
+
+fn to_update_deadline(ruby: &Ruby, value: Value) -> Result<UpdateDeadline, Error> {
+    if let Some(sym) = Symbol::from_value(value) {
+        if *TRAP == Id::from(sym) {
+            return Ok(UpdateDeadline::Interrupt);
+        }
+    } else if let Some(ary) = RArray::from_value(value) {
+        if let Ok((action, delta)) = <(Symbol, u64)>::try_convert(ary.as_value()) {
+            if *CONTINUE == Id::from(action) {
+                return Ok(UpdateDeadline::Continue(delta));
+            }
+        }
+    }
+
+    Err(Error::new(
+        ruby.exception_arg_error(),
+        format!(
+            "expected :trap or [:continue, delta] from epoch deadline callback, got {}",
+            value.inspect()
+        ),
+    ))
+}
+
 fn hash_to_store_limits_builder(ruby: &Ruby, limits: RHash) -> Result<StoreLimitsBuilder, Error> {
     let mut limiter: StoreLimitsBuilder = StoreLimitsBuilder::new();
 
@@ -456,7 +1071,22 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     class.define_method("data", method!(Store::data, 0))?;
     class.define_method("get_fuel", method!(Store::get_fuel, 0))?;
     class.define_method("set_fuel", method!(Store::set_fuel, 1))?;
+    class.define_method("fuel_consumed", method!(Store::fuel_consumed, 0))?;
+    class.define_method(
+        "fuel_async_yield_interval=",
+        method!(Store::set_fuel_async_yield_interval, 1),
+    )?;
     class.define_method("set_epoch_deadline", method!(Store::set_epoch_deadline, 1))?;
+    class.define_method("on_epoch_deadline", method!(Store::on_epoch_deadline, 0))?;
+    class.define_method(
+        "epoch_deadline_async_yield_and_update",
+        method!(Store::epoch_deadline_async_yield_and_update, 1),
+    )?;
+    class.define_method("call_hook", method!(Store::call_hook, 0))?;
+    class.define_method("with_timeout", method!(Store::with_timeout, 1))?;
+    class.define_method("interrupt_handle", method!(Store::interrupt_handle, 0))?;
+    class.define_method("resource_limiter=", method!(Store::set_resource_limiter, 1))?;
+    class.define_method("resource_limiter", method!(Store::resource_limiter, 0))?;
     class.define_method(
         "linear_memory_limit_hit?",
         method!(Store::linear_memory_limit_hit, 0),
@@ -470,11 +1100,18 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
 }
 
 /// A resource limiter proxy used to report memory usage to Ruby's GC.
+/// Growth requests allowed by the inner limiter are also submitted to the
+/// Ruby limiter set with {Store#resource_limiter=}, when present.
 struct TrackingResourceLimiter {
     inner: StoreLimits,
     tracker: ManuallyTracked<()>,
     linear_memory_limit_hit: bool,
     max_linear_memory_consumed: usize,
+    ruby_limiter: Option<Value>,
+    ruby_error: Option<Error>,
+    // Growing a memory may move it, so buffers mapping memories are
+    // released beforehand.
+    io_buffers: IoBuffers,
 }
 
 impl TrackingResourceLimiter {
@@ -485,6 +1122,9 @@ impl TrackingResourceLimiter {
             tracker: ManuallyTracked::new(0),
             linear_memory_limit_hit: false,
             max_linear_memory_consumed: 0,
+            ruby_limiter: None,
+            ruby_error: None,
+            io_buffers: Default::default(),
         }
     }
 
@@ -495,6 +1135,80 @@ impl TrackingResourceLimiter {
     pub fn max_linear_memory_consumed(&self) -> usize {
         self.max_linear_memory_consumed
     }
+
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.ruby_error.take()
+    }
+
+    pub fn mark(&self, marker: &Marker) {
+        if let Some(limiter) = self.ruby_limiter {
+            marker.mark_movable(limiter);
+        }
+
+        if let Some(ref error) = self.ruby_error {
+            if let Some(val) = error.value() {
+                marker.mark(val);
+            }
+        }
+
+        self.io_buffers.mark(marker);
+    }
+
+    pub fn compact(&mut self, compactor: &Compactor) {
+        if let Some(limiter) = self.ruby_limiter {
+            self.ruby_limiter = Some(compactor.location(limiter));
+        }
+    }
+
+    /// Asks the Ruby limiter, if any, whether a growth is allowed.
+    fn ruby_growing(
+        &mut self,
+        method: StaticId,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        let Some(limiter) = self.ruby_limiter else {
+            return Ok(true);
+        };
+
+        with_gvl(|| limiter.funcall::<_, _, Value>(method, (current, desired, maximum)))
+            .map(|allowed| allowed.to_bool())
+            .map_err(|e| self.store_ruby_error(e))
+    }
+
+    /// Notifies the Ruby limiter, if it cares, that a growth failed.
+    fn ruby_grow_failed(
+        &mut self,
+        method: StaticId,
+        error: &wasmtime::Error,
+    ) -> wasmtime::Result<()> {
+        let Some(limiter) = self.ruby_limiter else {
+            return Ok(());
+        };
+
+        with_gvl(|| -> Result<(), Error> {
+            if limiter.respond_to(method, false)? {
+                limiter.funcall::<_, _, Value>(method, (error.to_string(),))?;
+            }
+            Ok(())
+        })
+        .map_err(|e| self.store_ruby_error(e))
+    }
+
+    /// Releases the +IO::Buffer+s mapping memories. A buffer that can't be
+    /// released, e.g. because it's locked, fails the growth.
+    fn release_io_buffers(&mut self) -> wasmtime::Result<()> {
+        let io_buffers = &mut self.io_buffers;
+        with_gvl(|| io_buffers.release()).map_err(|e| self.store_ruby_error(e))
+    }
+
+    // Same as Func calls: keep the Ruby error around so that it can be marked,
+    // and return a generic error that gets swapped for the Ruby one.
+    fn store_ruby_error(&mut self, error: Error) -> wasmtime::Error {
+        self.ruby_error = Some(error);
+        wasmtime::Error::msg("")
+    }
 }
 
 impl ResourceLimiter for TrackingResourceLimiter {
@@ -504,7 +1218,21 @@ impl ResourceLimiter for TrackingResourceLimiter {
         desired: usize,
         maximum: Option<usize>,
     ) -> wasmtime::Result<bool> {
-        let res = self.inner.memory_growing(current, desired, maximum);
+        let res = self
+            .inner
+            .memory_growing(current, desired, maximum)
+            .and_then(|allowed| match allowed {
+                true => self.ruby_growing(*MEMORY_GROWING, current, desired, maximum),
+                false => Ok(false),
+            })
+            .and_then(|allowed| {
+                // Empty memories can't be mapped by buffers, e.g. when
+                // they're created.
+                if allowed && current > 0 {
+                    self.release_io_buffers()?;
+                }
+                Ok(allowed)
+            });
 
         // Update max_linear_memory_consumed
         self.max_linear_memory_consumed = self.max_linear_memory_consumed.max(desired);
@@ -530,15 +1258,22 @@ impl ResourceLimiter for TrackingResourceLimiter {
         desired: usize,
         maximum: Option<usize>,
     ) -> wasmtime::Result<bool> {
-        self.inner.table_growing(current, desired, maximum)
+        self.inner
+            .table_growing(current, desired, maximum)
+            .and_then(|allowed| match allowed {
+                true => self.ruby_growing(*TABLE_GROWING, current, desired, maximum),
+                false => Ok(false),
+            })
     }
 
     fn memory_grow_failed(&mut self, error: wasmtime::Error) -> wasmtime::Result<()> {
         self.linear_memory_limit_hit = true;
+        self.ruby_grow_failed(*MEMORY_GROW_FAILED, &error)?;
         self.inner.memory_grow_failed(error)
     }
 
     fn table_grow_failed(&mut self, error: wasmtime::Error) -> wasmtime::Result<()> {
+        self.ruby_grow_failed(*TABLE_GROW_FAILED, &error)?;
         self.inner.table_grow_failed(error)
     }
 
@@ -0,0 +1,259 @@
+use super::{
+    convert::{field_type_to_value, to_field_type, ToRubyValue, ToWasmVal},
+    root,
+    store::{Store, StoreContextValue},
+};
+use crate::{err, error, Engine};
+use magnus::{
+    function, gc::Marker, method, prelude::*, typed_data::Obj, DataTypeFunctions, Error, Object,
+    RArray, Ruby, TypedData, Value,
+};
+use wasmtime::{
+    FieldType, OwnedRooted, Rooted, StructRef as StructRefImpl, StructRefPre,
+    StructType as StructTypeImpl, ValType,
+};
+
+/// @yard
+/// @rename Wasmtime::StructType
+/// Represents a WebAssembly GC struct type.
+///
+/// Fields are described by their type Symbol, which may also be +:i8+ or
+/// +:i16+ for packed fields, or by +[:mut, type]+ for mutable fields.
+/// @see https://docs.rs/wasmtime/latest/wasmtime/struct.StructType.html Wasmtime's Rust doc
+#[derive(TypedData)]
+#[magnus(
+    class = "Wasmtime::StructType",
+    size,
+    mark,
+    free_immediately,
+    unsafe_generics
+)]
+pub struct StructType {
+    inner: StructTypeImpl,
+}
+
+impl DataTypeFunctions for StructType {}
+
+impl StructType {
+    /// @yard
+    /// @def new(engine, fields)
+    /// @param engine [Engine] Requires +wasm_gc: true+.
+    /// @param fields [Array<Symbol, RefType, Array>] The fields' types,
+    ///   e.g. +[:i32, [:mut, :f64]]+.
+    /// @return [StructType]
+    pub fn new(engine: &Engine, fields: RArray) -> Result<Self, Error> {
+        let engine = engine.get();
+        let fields = fields
+            .to_vec::<Value>()?
+            .into_iter()
+            .map(|field| to_field_type(field, engine))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let inner = StructTypeImpl::new(engine, fields).map_err(|e| error!("{}", e))?;
+
+        Ok(Self { inner })
+    }
+
+    pub fn from_inner(inner: StructTypeImpl) -> Self {
+        Self { inner }
+    }
+
+    /// @yard
+    /// @return [Array<Symbol, RefType, Array>] The fields' types.
+    pub fn fields(ruby: &Ruby, rb_self: &Self) -> Result<RArray, Error> {
+        let array = ruby.ary_new_capa(rb_self.inner.fields().len());
+        for field in rb_self.inner.fields() {
+            array.push(field_type_to_value(ruby, &field)?)?;
+        }
+        Ok(array)
+    }
+
+    pub fn get(&self) -> &StructTypeImpl {
+        &self.inner
+    }
+}
+
+/// @yard
+/// @rename Wasmtime::StructRef
+/// Represents a reference to a WebAssembly GC struct. Structs are returned
+/// by Wasm functions for +structref+ (and +anyref+ or +eqref+) results, and
+/// can be allocated from Ruby with {.new}.
+///
+/// Packed +:i8+ and +:i16+ fields are read as zero-extended Integers.
+/// @see https://docs.rs/wasmtime/latest/wasmtime/struct.StructRef.html Wasmtime's Rust doc
+#[derive(TypedData)]
+#[magnus(
+    class = "Wasmtime::StructRef",
+    size,
+    mark,
+    free_immediately,
+    unsafe_generics
+)]
+pub struct StructRef<'a> {
+    store: StoreContextValue<'a>,
+    inner: OwnedRooted<StructRefImpl>,
+}
+
+impl DataTypeFunctions for StructRef<'_> {
+    fn mark(&self, marker: &Marker) {
+        self.store.mark(marker)
+    }
+}
+
+impl<'a> StructRef<'a> {
+    /// @yard
+    /// Allocates a struct in +store+'s GC heap. Raises for {Store}s with
+    /// async support.
+    ///
+    /// @def new(store, type, fields)
+    /// @param store [Store]
+    /// @param type [StructType]
+    /// @param fields [Array<Object>] The initial value of each field.
+    /// @return [StructRef]
+    pub fn new(store: Obj<Store>, ty: &StructType, fields: RArray) -> Result<Self, Error> {
+        let store_value: StoreContextValue = store.into();
+        let field_types: Vec<FieldType> = ty.inner.fields().collect();
+        let values = fields.to_vec::<Value>()?;
+        if values.len() != field_types.len() {
+            return err!(
+                "wrong number of fields (given {}, expected {})",
+                values.len(),
+                field_types.len()
+            );
+        }
+
+        let vals = field_types
+            .iter()
+            .zip(&values)
+            .map(|(field, value)| {
+                value.to_wasm_val(&store_value, field.element_type().unpack().clone())
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut context = store.context_mut();
+        if context.data().is_async() {
+            return err!("cannot allocate a struct in a store with async support");
+        }
+        let pre = StructRefPre::new(&mut context, ty.inner.clone());
+        let inner = StructRefImpl::new(&mut context, &pre, &vals)
+            .and_then(|struct_ref| struct_ref.to_owned_rooted(&mut context))
+            .map_err(|e| error!("{}", e))?;
+
+        let struct_ref = Self {
+            store: store_value,
+            inner,
+        };
+        for (field, value) in field_types.iter().zip(values) {
+            struct_ref.retain_non_nil_extern_ref(field, value)?;
+        }
+
+        Ok(struct_ref)
+    }
+
+    pub fn from_inner(store: StoreContextValue<'a>, inner: OwnedRooted<StructRefImpl>) -> Self {
+        Self { store, inner }
+    }
+
+    /// @yard
+    /// @def type
+    /// @return [StructType]
+    pub fn type_(ruby: &Ruby, rb_self: &Self) -> Result<Obj<StructType>, Error> {
+        let ty = rb_self
+            .inner
+            .ty(rb_self.store.context()?)
+            .map_err(|e| error!("{}", e))?;
+        Ok(ruby.obj_wrap(StructType::from_inner(ty)))
+    }
+
+    /// @yard
+    /// Returns the value of the field at +index+.
+    ///
+    /// @def get(index)
+    /// @param index [Integer]
+    /// @return [Object]
+    pub fn get(ruby: &Ruby, rb_self: &Self, index: usize) -> Result<Value, Error> {
+        rb_self
+            .inner
+            .field(rb_self.store.context_mut()?, index)
+            .map_err(|e| error!("{}", e))?
+            .to_ruby_value(ruby, &rb_self.store)
+    }
+
+    /// @yard
+    /// Sets the mutable field at +index+ to +value+.
+    ///
+    /// @def set(index, value)
+    /// @param index [Integer]
+    /// @param value [Object]
+    /// @return [void]
+    pub fn set(&self, index: usize, value: Value) -> Result<(), Error> {
+        let field = self
+            .field_type(index)?
+            .ok_or_else(|| error!("field index {index} out of bounds"))?;
+        let val = value.to_wasm_val(&self.store, field.element_type().unpack().clone())?;
+        self.inner
+            .set_field(self.store.context_mut()?, index, val)
+            .map_err(|e| error!("{}", e))?;
+        self.retain_non_nil_extern_ref(&field, value)
+    }
+
+    /// @yard
+    /// @return [Integer] The number of fields.
+    pub fn size(&self) -> Result<usize, Error> {
+        self.inner
+            .ty(self.store.context()?)
+            .map(|ty| ty.fields().len())
+            .map_err(|e| error!("{}", e))
+    }
+
+    /// @yard
+    /// @return [Array<Object>] The values of all fields.
+    pub fn to_a(ruby: &Ruby, rb_self: &Self) -> Result<RArray, Error> {
+        let size = rb_self.size()?;
+        let array = ruby.ary_new_capa(size);
+        for index in 0..size {
+            array.push(Self::get(ruby, rb_self, index)?)?;
+        }
+        Ok(array)
+    }
+
+    /// Roots the struct in `store`, which must be the store it was allocated
+    /// in, to pass it to Wasm.
+    pub fn to_rooted(&self, store: &StoreContextValue) -> Result<Rooted<StructRefImpl>, Error> {
+        if !self.store.is_same_store(store)? {
+            return err!("struct belongs to a different store");
+        }
+        Ok(self.inner.to_rooted(store.context_mut()?))
+    }
+
+    fn field_type(&self, index: usize) -> Result<Option<FieldType>, Error> {
+        self.inner
+            .ty(self.store.context()?)
+            .map(|ty| ty.field(index))
+            .map_err(|e| error!("{}", e))
+    }
+
+    fn retain_non_nil_extern_ref(&self, field: &FieldType, value: Value) -> Result<(), Error> {
+        if !value.is_nil() && field.element_type().unpack().matches(&ValType::EXTERNREF) {
+            self.store.retain(value)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn init(ruby: &Ruby) -> Result<(), Error> {
+    let type_class = root().define_class("StructType", ruby.class_object())?;
+    type_class.define_singleton_method("new", function!(StructType::new, 2))?;
+    type_class.define_method("fields", method!(StructType::fields, 0))?;
+
+    let class = root().define_class("StructRef", ruby.class_object())?;
+    class.define_singleton_method("new", function!(StructRef::new, 3))?;
+    class.define_method("type", method!(StructRef::type_, 0))?;
+    class.define_method("get", method!(StructRef::get, 1))?;
+    class.define_method("[]", method!(StructRef::get, 1))?;
+    class.define_method("set", method!(StructRef::set, 2))?;
+    class.define_method("[]=", method!(StructRef::set, 2))?;
+    class.define_method("size", method!(StructRef::size, 0))?;
+    class.define_method("to_a", method!(StructRef::to_a, 0))?;
+
+    Ok(())
+}
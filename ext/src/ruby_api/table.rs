@@ -1,19 +1,20 @@
 use super::{
-    convert::{ToRubyValue, ToSym, ToValType, ToWasmVal},
+    convert::{ToRubyType, ToRubyValue, ToValType, ToWasmVal},
     root,
     store::{Store, StoreContextValue},
 };
 
-use crate::{define_rb_intern, error};
+use crate::{define_rb_intern, err, error};
 use magnus::{
     class, function, gc::Marker, method, prelude::*, scan_args, typed_data::Obj, DataTypeFunctions,
-    Error, IntoValue, Object, Ruby, Symbol, TypedData, Value,
+    Error, IntoValue, Object, RArray, Ruby, TypedData, Value,
 };
-use wasmtime::{Extern, Table as TableImpl, Val};
+use wasmtime::{Extern, Ref, Table as TableImpl, Val};
 
 define_rb_intern!(
     MIN_SIZE => "min_size",
     MAX_SIZE => "max_size",
+    TABLE64 => "table64",
 );
 
 #[derive(TypedData)]
@@ -33,9 +34,9 @@ impl TableType {
 
     /// @yard
     /// @def type
-    /// @return [Symbol] The Wasm type of the elements of this table.
-    pub fn type_(&self) -> Result<Symbol, Error> {
-        self.inner.element().to_sym()
+    /// @return [Symbol, RefType] The Wasm type of the elements of this table.
+    pub fn type_(ruby: &Ruby, rb_self: &Self) -> Result<Value, Error> {
+        rb_self.inner.element().to_ruby_type(ruby)
     }
 
     /// @yard
@@ -49,6 +50,12 @@ impl TableType {
     pub fn max_size(&self) -> Option<u64> {
         self.inner.maximum()
     }
+
+    /// @yard
+    /// @return [Boolean] Whether the table is indexed with 64-bit indices (table64).
+    pub fn is_64(&self) -> bool {
+        self.inner.is_64()
+    }
 }
 
 impl From<&TableType> for wasmtime::ExternType {
@@ -76,23 +83,26 @@ impl DataTypeFunctions for Table<'_> {
 
 impl<'a> Table<'a> {
     /// @yard
-    /// @def new(store, type, initial, min_size:, max_size: nil)
+    /// @def new(store, type, initial, min_size:, max_size: nil, table64: false)
     /// @param store [Store]
-    /// @param type [Symbol] The WebAssembly type of the value held by this table.
+    /// @param type [Symbol, RefType] The WebAssembly type of the value held by this table.
     /// @param initial [Value] The initial value of values in the table.
     /// @param min_size [Integer] The minimum number of elements in the table.
     /// @param max_size [Integer, nil] The maximum number of elements in the table.
+    /// @param table64 [Boolean] Whether the table is indexed with 64-bit
+    ///   indices, requires an {Engine} with +wasm_memory64: true+.
     pub fn new(args: &[Value]) -> Result<Self, Error> {
-        let args = scan_args::scan_args::<(Obj<Store>, Symbol, Value), (), (), (), _, ()>(args)?;
-        let kw = scan_args::get_kwargs::<_, (u32,), (Option<u32>,), ()>(
+        let args = scan_args::scan_args::<(Obj<Store>, Value, Value), (), (), (), _, ()>(args)?;
+        let kw = scan_args::get_kwargs::<_, (u64,), (Option<Option<u64>>, Option<bool>), ()>(
             args.keywords,
             &[*MIN_SIZE],
-            &[*MAX_SIZE],
+            &[*MAX_SIZE, *TABLE64],
         )?;
         let (store, value_type, default) = args.required;
         let (min,) = kw.required;
-        let (max,) = kw.optional;
-        let wasm_type = value_type.to_val_type()?;
+        let (max, table64) = kw.optional;
+        let max = max.flatten();
+        let wasm_type = value_type.to_val_type(store.context().engine())?;
         let wasm_default = default.to_wasm_val(&store.into(), wasm_type.clone())?;
         let ref_ = wasm_default
             .ref_()
@@ -103,12 +113,18 @@ impl<'a> Table<'a> {
             .ok_or_else(|| error!("Expected RefType"))?
             .clone();
 
-        let inner = TableImpl::new(
-            store.context_mut(),
-            wasmtime::TableType::new(table_type, min, max),
-            ref_,
-        )
-        .map_err(|e| error!("{}", e))?;
+        let ty = match table64 {
+            Some(true) => wasmtime::TableType::new64(table_type, min, max),
+            _ => wasmtime::TableType::new(
+                table_type,
+                u32::try_from(min)
+                    .map_err(|_| error!("min_size out of range for a 32-bit table"))?,
+                max.map(u32::try_from)
+                    .transpose()
+                    .map_err(|_| error!("max_size out of range for a 32-bit table"))?,
+            ),
+        };
+        let inner = TableImpl::new(store.context_mut(), ty, ref_).map_err(|e| error!("{}", e))?;
 
         let table = Self {
             store: store.into(),
@@ -126,9 +142,9 @@ impl<'a> Table<'a> {
 
     /// @yard
     /// @def type
-    /// @return [Symbol] The Wasm type of the elements of this table.
-    pub fn type_(&self) -> Result<Symbol, Error> {
-        self.ty()?.element().to_sym()
+    /// @return [Symbol, RefType] The Wasm type of the elements of this table.
+    pub fn type_(ruby: &Ruby, rb_self: &Self) -> Result<Value, Error> {
+        rb_self.ty()?.element().to_ruby_type(ruby)
     }
 
     /// @yard
@@ -164,15 +180,9 @@ impl<'a> Table<'a> {
     /// @param value [Object]
     /// @return [void]
     pub fn set(&self, index: u64, value: Value) -> Result<(), Error> {
+        let ref_ = self.to_ref(value)?;
         self.inner
-            .set(
-                self.store.context_mut()?,
-                index,
-                value
-                    .to_wasm_val(&self.store, wasmtime::ValType::from(self.value_type()?))?
-                    .ref_()
-                    .ok_or_else(|| error!("Expected Ref"))?,
-            )
+            .set(self.store.context_mut()?, index, ref_)
             .map_err(|e| error!("{}", e))
             .and_then(|result| {
                 self.retain_non_nil_extern_ref(value)?;
@@ -189,22 +199,78 @@ impl<'a> Table<'a> {
     /// @param initial [Object] The initial value for newly added table slots.
     /// @return [void]
     pub fn grow(&self, delta: u64, initial: Value) -> Result<u64, Error> {
+        let ruby = Ruby::get_with(initial);
+        let ref_ = self.to_ref(initial)?;
         self.inner
-            .grow(
-                self.store.context_mut()?,
-                delta,
-                initial
-                    .to_wasm_val(&self.store, wasmtime::ValType::from(self.value_type()?))?
-                    .ref_()
-                    .ok_or_else(|| error!("Expected Ref"))?,
-            )
-            .map_err(|e| error!("{}", e))
+            .grow(self.store.context_mut()?, delta, ref_)
+            .map_err(|e| self.store.handle_wasm_error(&ruby, e))
             .and_then(|result| {
                 self.retain_non_nil_extern_ref(initial)?;
                 Ok(result)
             })
     }
 
+    /// @yard
+    /// Sets +len+ table entries starting at +start+ to +value+.
+    ///
+    /// @def fill(start, value, len)
+    /// @param start [Integer]
+    /// @param value [Object]
+    /// @param len [Integer]
+    /// @return [void]
+    pub fn fill(&self, start: u64, value: Value, len: u64) -> Result<(), Error> {
+        let ref_ = self.to_ref(value)?;
+        self.inner
+            .fill(self.store.context_mut()?, start, ref_, len)
+            .map_err(|e| error!("{}", e))?;
+        self.retain_non_nil_extern_ref(value)
+    }
+
+    /// @yard
+    /// Copies +len+ entries of +src_table+ starting at +src_index+ to
+    /// +dst_table+ starting at +dst_index+. Both tables must belong to the
+    /// same {Store}, and may be the same table.
+    ///
+    /// @def copy(dst_table, dst_index, src_table, src_index, len)
+    /// @param dst_table [Table]
+    /// @param dst_index [Integer]
+    /// @param src_table [Table]
+    /// @param src_index [Integer]
+    /// @param len [Integer]
+    /// @return [void]
+    pub fn copy(
+        dst_table: &Self,
+        dst_index: u64,
+        src_table: &Self,
+        src_index: u64,
+        len: u64,
+    ) -> Result<(), Error> {
+        if !dst_table.store.is_same_store(&src_table.store)? {
+            return err!("tables must belong to the same store");
+        }
+
+        TableImpl::copy(
+            dst_table.store.context_mut()?,
+            &dst_table.inner,
+            dst_index,
+            &src_table.inner,
+            src_index,
+            len,
+        )
+        .map_err(|e| error!("{}", e))
+    }
+
+    /// @yard
+    /// @return [Array<Object>] All the table's elements.
+    pub fn to_a(ruby: &Ruby, rb_self: Obj<Self>) -> Result<RArray, Error> {
+        let size = rb_self.size()?;
+        let array = ruby.ary_new_capa(size as usize);
+        for index in 0..size {
+            array.push(Self::get(ruby, rb_self, index)?)?;
+        }
+        Ok(array)
+    }
+
     /// @yard
     /// @return [Integer] The size of the table.
     pub fn size(&self) -> Result<u64, Error> {
@@ -222,6 +288,25 @@ impl<'a> Table<'a> {
         Ok(el.clone())
     }
 
+    /// Converts `value` to a reference of the table's element type. Typed
+    /// function references are checked against the function's type, and
+    /// +nil+ is rejected for non-nullable references.
+    fn to_ref(&self, value: Value) -> Result<Ref, Error> {
+        let ty = self.value_type()?;
+        let ref_ = value
+            .to_wasm_val(&self.store, wasmtime::ValType::from(ty.clone()))?
+            .ref_()
+            .ok_or_else(|| error!("Expected Ref"))?;
+
+        let matches = ref_
+            .matches_ty(self.store.context()?, &ty)
+            .map_err(|e| error!("{}", e))?;
+        if !matches {
+            return err!("type mismatch: value does not match table element type {ty}");
+        }
+        Ok(ref_)
+    }
+
     fn retain_non_nil_extern_ref(&self, value: Value) -> Result<(), Error> {
         if !value.is_nil() && self.value_type()?.matches(&wasmtime::RefType::EXTERNREF) {
             self.store.retain(value)?;
@@ -245,9 +330,11 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     type_class.define_method("type", method!(TableType::type_, 0))?;
     type_class.define_method("min_size", method!(TableType::min_size, 0))?;
     type_class.define_method("max_size", method!(TableType::max_size, 0))?;
+    type_class.define_method("is_64?", method!(TableType::is_64, 0))?;
 
     let class = root().define_class("Table", ruby.class_object())?;
     class.define_singleton_method("new", function!(Table::new, -1))?;
+    class.define_singleton_method("copy", function!(Table::copy, 5))?;
 
     class.define_method("type", method!(Table::type_, 0))?;
     class.define_method("min_size", method!(Table::min_size, 0))?;
@@ -257,6 +344,8 @@ pub fn init(ruby: &Ruby) -> Result<(), Error> {
     class.define_method("set", method!(Table::set, 2))?;
     class.define_method("grow", method!(Table::grow, 2))?;
     class.define_method("size", method!(Table::size, 0))?;
+    class.define_method("fill", method!(Table::fill, 3))?;
+    class.define_method("to_a", method!(Table::to_a, 0))?;
 
     Ok(())
 }
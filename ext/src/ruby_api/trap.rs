@@ -14,6 +14,13 @@ pub fn trap_error() -> ExceptionClass {
     ruby.get_inner(&ERR)
 }
 
+/// Raised when Wasm execution exceeds the time given to {Store#with_timeout}.
+pub fn timeout_error() -> ExceptionClass {
+    static ERR: Lazy<ExceptionClass> = Lazy::new(|_| root().const_get("Timeout").unwrap());
+    let ruby = Ruby::get().unwrap();
+    ruby.get_inner(&ERR)
+}
+
 macro_rules! trap_const {
     ($trap:ident) => {
         trap_error().const_get(stringify!($trap)).map(Some)
@@ -86,11 +93,24 @@ impl Trap {
         ))
     }
 
+    pub fn is_interrupt(&self) -> bool {
+        self.trap == wasmtime::Trap::Interrupt
+    }
+
     pub fn into_error(self, ruby: &Ruby) -> Error {
         magnus::Exception::from_value(ruby.obj_wrap(self).as_value())
             .unwrap() // Can't fail: Wasmtime::Trap is an Exception
             .into()
     }
+
+    pub fn into_timeout_error(self, ruby: &Ruby) -> Error {
+        magnus::Exception::from_value(
+            ruby.obj_wrap_as(self, timeout_error().as_r_class())
+                .as_value(),
+        )
+        .unwrap() // Can't fail: Wasmtime::Timeout is an Exception
+        .into()
+    }
 }
 
 impl TryFrom<wasmtime::Error> for Trap {
@@ -109,6 +129,7 @@ impl TryFrom<wasmtime::Error> for Trap {
 }
 
 pub fn init() -> Result<(), Error> {
+    let _ = timeout_error();
     let class = trap_error();
     class.define_method("message", method!(Trap::message, 0))?;
     class.define_method(
@@ -1,6 +1,6 @@
 use super::root;
 use crate::error;
-use crate::helpers::OutputLimitedBuffer;
+use crate::helpers::{with_gvl, OutputLimitedBuffer};
 use crate::ruby_api::convert::ToValType;
 use crate::{define_rb_intern, helpers::SymbolEnum};
 use lazy_static::lazy_static;
@@ -142,6 +142,11 @@ struct SocketAddrProc {
 
 impl SocketAddrProc {
     fn call(&self, addr: SocketAddr, use_: SocketAddrUse) -> bool {
+        // Socket checks may come from Wasm running without the GVL.
+        with_gvl(|| self.call_with_gvl(addr, use_))
+    }
+
+    fn call_with_gvl(&self, addr: SocketAddr, use_: SocketAddrUse) -> bool {
         let ruby = Ruby::get().unwrap();
 
         // Convert arguments to Ruby values
@@ -168,7 +173,7 @@ impl SocketAddrProc {
     }
 }
 
-// SAFETY: We only access the Ruby proc when we have the GVL (during WASI operations).
+// SAFETY: We only access the Ruby proc when we have the GVL, see `SocketAddrProc::call`.
 // The Proc is kept alive by the Store's refs field, which is marked during GC.
 unsafe impl Send for SocketAddrProc {}
 unsafe impl Sync for SocketAddrProc {}
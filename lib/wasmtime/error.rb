@@ -10,6 +10,17 @@ module Wasmtime
   # Raised when converting an {Wasmtime::Extern} to its concrete type fails.
   class ConversionError < Error; end
 
+  # Raised when a Wasm module or component fails validation.
+  class ValidationError < Error
+    # @return [Integer, nil] The byte offset at which validation failed, if known.
+    attr_reader(:offset)
+
+    def initialize(message, offset = nil)
+      super(message)
+      @offset = offset
+    end
+  end
+
   # Raised on Wasm trap.
   class Trap < Error
     STACK_OVERFLOW = :stack_overflow
@@ -27,6 +38,10 @@ module Wasmtime
     UNKNOWN = :unknown
   end
 
+  # Raised when Wasm execution exceeds the time given to
+  # {Wasmtime::Store#with_timeout}. Its {Trap#code} is {Trap::INTERRUPT}.
+  class Timeout < Trap; end
+
   # Raised when a WASI program terminates early by calling +exit+.
   class WasiExit < Error
     # @return [Integer] The system exit code.
@@ -0,0 +1,187 @@
+require "spec_helper"
+
+module Wasmtime
+  RSpec.describe "Async execution" do
+    # A scheduler that only records yields; enough to observe where Wasm
+    # hands control back to the event loop.
+    let(:scheduler_class) do
+      Class.new do
+        attr_reader :yields
+
+        def initialize
+          @yields = 0
+        end
+
+        def fiber(&block)
+          Fiber.new(blocking: false, &block).tap(&:resume)
+        end
+
+        def kernel_sleep(duration = nil)
+          @yields += 1
+        end
+
+        def block(blocker, timeout = nil)
+        end
+
+        def unblock(blocker, fiber)
+        end
+
+        def io_wait(io, events, timeout)
+        end
+
+        def close
+        end
+      end
+    end
+
+    let(:engine) { Engine.new(async_support: true, epoch_interruption: true, consume_fuel: true) }
+    let(:store) { Store.new(engine).tap { |store| store.set_fuel(10_000_000) } }
+    let(:mod) do
+      Module.new(engine, <<~WAT)
+        (module
+          (import "" "host" (func $host (param i32) (result i32)))
+          (func (export "add") (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            i32.add)
+          (func (export "call_host") (param i32) (result i32)
+            local.get 0
+            call $host)
+          (func (export "call_host_then_spin") (param i32) (result i32)
+            local.get 0
+            call $host
+            i32.const 10
+            call $spin)
+          (func $spin (export "spin") (param i32)
+            (loop
+              local.get 0
+              i32.const 1
+              i32.sub
+              local.tee 0
+              br_if 0)))
+      WAT
+    end
+    let(:host) { Func.new(store, [:i32], [:i32]) { |_caller, arg| arg * 2 } }
+    let(:instance) { Instance.new(store, mod, [host]) }
+
+    def with_scheduler
+      scheduler = scheduler_class.new
+      result = nil
+      Thread.new do
+        Fiber.set_scheduler(scheduler)
+        Fiber.schedule { result = yield }
+      end.join
+      [result, scheduler]
+    end
+
+    it "calls functions with Instance#invoke_async" do
+      store.set_epoch_deadline(1)
+      expect(instance.invoke_async("add", 1, 2)).to eq(3)
+    end
+
+    it "calls functions with Func#call_async" do
+      store.set_epoch_deadline(1)
+      expect(instance.export("add").to_func.call_async(1, 2)).to eq(3)
+    end
+
+    it "calls Ruby host functions" do
+      store.set_epoch_deadline(1)
+      expect(instance.invoke_async("call_host", 21)).to eq(42)
+    end
+
+    it "bubbles exceptions raised by host functions" do
+      store.set_epoch_deadline(1)
+      host = Func.new(store, [:i32], [:i32]) { raise "boom" }
+      instance = Instance.new(store, mod, [host])
+
+      expect { instance.invoke_async("call_host", 1) }.to raise_error(RuntimeError, "boom")
+    end
+
+    it "yields to the fiber scheduler on epoch deadlines" do
+      store.epoch_deadline_async_yield_and_update(1)
+      store.set_epoch_deadline(1)
+      host = Func.new(store, [:i32], [:i32]) do |_caller, arg|
+        engine.increment_epoch
+        arg
+      end
+      instance = Instance.new(store, mod, [host])
+
+      result, scheduler = with_scheduler { instance.invoke_async("call_host_then_spin", 7) }
+      expect(result).to eq(7)
+      expect(scheduler.yields).to be >= 1
+    end
+
+    it "yields to the fiber scheduler on fuel intervals" do
+      store.set_epoch_deadline(1)
+      store.fuel_async_yield_interval = 1_000
+
+      _, scheduler = with_scheduler { instance.invoke_async("spin", 10_000) }
+      expect(scheduler.yields).to be > 1
+    end
+
+    it "rejects synchronous calls" do
+      store.set_epoch_deadline(1)
+      expect { instance.invoke("add", 1, 2) }.to raise_error(Wasmtime::Error, /use Func#call_async/)
+    end
+
+    it "rejects Ruby store callbacks" do
+      store.set_epoch_deadline(1)
+      instance
+      store.call_hook { |_kind| }
+
+      expect { instance.invoke_async("add", 1, 2) }.to raise_error(Wasmtime::Error, /not supported by async calls/)
+    end
+
+    it "requires an async engine" do
+      instance = Instance.new(Store.new(GLOBAL_ENGINE), Module.new(GLOBAL_ENGINE, <<~WAT))
+        (module (func (export "f")))
+      WAT
+
+      expect { instance.invoke_async("f") }.to raise_error(Wasmtime::Error, /async_support: true/)
+    end
+
+    describe "async host functions" do
+      it "lets Func.new_async blocks use the fiber scheduler" do
+        store.set_epoch_deadline(1)
+        host = Func.new_async(store, [:i32], [:i32]) do |_caller, arg|
+          sleep(0.01)
+          arg + 1
+        end
+        instance = Instance.new(store, mod, [host])
+
+        result, scheduler = with_scheduler { instance.invoke_async("call_host", 1) }
+        expect(result).to eq(2)
+        expect(scheduler.yields).to eq(1)
+      end
+
+      it "lets Linker#func_new_async blocks use the fiber scheduler" do
+        store.set_epoch_deadline(1)
+        linker = Linker.new(engine)
+        linker.func_new_async("", "host", [:i32], [:i32]) do |_caller, arg|
+          sleep(0.01)
+          arg + 1
+        end
+        instance = linker.instantiate(store, mod)
+
+        result, scheduler = with_scheduler { instance.invoke_async("call_host", 1) }
+        expect(result).to eq(2)
+        expect(scheduler.yields).to eq(1)
+      end
+
+      it "requires an async engine" do
+        expect { Func.new_async(Store.new(GLOBAL_ENGINE), [], []) {} }
+          .to raise_error(Wasmtime::Error, /async_support: true/)
+        expect { Linker.new(GLOBAL_ENGINE).func_new_async("", "", [], []) {} }
+          .to raise_error(Wasmtime::Error, /async_support: true/)
+      end
+    end
+
+    it "instantiates through a Linker" do
+      store.set_epoch_deadline(1)
+      linker = Linker.new(engine)
+      linker.func_new("", "host", [:i32], [:i32]) { |_caller, arg| arg + 1 }
+
+      expect(linker.instantiate(store, mod).invoke_async("call_host", 1)).to eq(2)
+    end
+  end
+end
@@ -49,6 +49,246 @@ module Wasmtime
       expect { instance.invoke("42") }.to raise_error(Trap)
     end
 
+    describe "Store#on_epoch_deadline" do
+      it "continues execution with a new deadline" do
+        calls = 0
+        store = Store.new(engine)
+        store.on_epoch_deadline do |yielded_store|
+          expect(yielded_store).to equal(store)
+          calls += 1
+          [:continue, 1]
+        end
+        instance = Instance.new(store, mod)
+
+        expect(instance.invoke("42")).to eq(42)
+        expect(calls).to be > 0
+      end
+
+      it "traps when the block returns :trap" do
+        store = Store.new(engine)
+        store.on_epoch_deadline { :trap }
+        instance = Instance.new(store, mod)
+
+        expect { instance.invoke("42") }.to raise_error(Trap) do |trap|
+          expect(trap.code).to eq(:interrupt)
+        end
+      end
+
+      it "interrupts an infinite loop after a number of slices" do
+        slices = 0
+        store = Store.new(engine)
+        store.set_epoch_deadline(1)
+        store.on_epoch_deadline do
+          slices += 1
+          engine.increment_epoch
+          (slices < 3) ? [:continue, 1] : :trap
+        end
+        instance = Instance.new(store, mod)
+        engine.increment_epoch
+
+        expect { instance.invoke("loop_forever") }.to raise_error(Trap)
+        expect(slices).to eq(3)
+      end
+
+      it "bubbles exceptions raised by the block" do
+        store = Store.new(engine)
+        store.on_epoch_deadline { raise "boom" }
+        instance = Instance.new(store, mod)
+
+        expect { instance.invoke("42") }.to raise_error(RuntimeError, "boom")
+      end
+
+      it "raises on an invalid return value" do
+        store = Store.new(engine)
+        store.on_epoch_deadline { :nope }
+        instance = Instance.new(store, mod)
+
+        expect { instance.invoke("42") }.to raise_error(ArgumentError, /expected :trap or \[:continue, delta\]/)
+      end
+    end
+
+    describe "Store#with_timeout" do
+      after { engine.stop_epoch_interval }
+
+      it "raises Timeout when Wasm runs past the deadline" do
+        instance = Instance.new(store_deadline_1, mod)
+        engine.start_epoch_interval(5)
+
+        expect { store_deadline_1.with_timeout(0.02) { instance.invoke("loop_forever") } }
+          .to raise_error(Timeout) do |timeout|
+            expect(timeout).to be_a(Trap)
+            expect(timeout.code).to eq(Trap::INTERRUPT)
+          end
+      end
+
+      it "returns the block's result" do
+        instance = Instance.new(store_deadline_1, mod)
+        engine.start_epoch_interval(5)
+
+        expect(store_deadline_1.with_timeout(1) { instance.invoke("42") }).to eq(42)
+      end
+
+      it "restores the previous epoch deadline" do
+        store = Store.new(engine)
+        instance = Instance.new(store_deadline_1, mod)
+        engine.start_epoch_interval(1000)
+        store.set_epoch_deadline(0)
+
+        store.with_timeout(5) {}
+        expect { Instance.new(store, autostart_mod) }.to raise_error(Trap) do |trap|
+          expect(trap).not_to be_a(Timeout)
+        end
+        expect(instance.invoke("42")).to eq(42)
+      end
+
+      it "keeps counting the previous epoch deadline from when it was set" do
+        store = Store.new(engine)
+        engine.start_epoch_interval(1_000_000)
+        store.set_epoch_deadline(3)
+
+        store.with_timeout(1000) { 3.times { engine.increment_epoch } }
+        expect { Instance.new(store, autostart_mod) }.to raise_error(Trap) do |trap|
+          expect(trap).not_to be_a(Timeout)
+        end
+      end
+
+      it "raises Timeout even when the epoch deadline block continues" do
+        store = Store.new(engine)
+        store.on_epoch_deadline { [:continue, 1] }
+        instance = Instance.new(store, mod)
+        engine.start_epoch_interval(5)
+
+        expect { store.with_timeout(0.02) { instance.invoke("loop_forever") } }
+          .to raise_error(Timeout)
+      end
+
+      it "raises without a running epoch timer" do
+        expect { store_deadline_1.with_timeout(1) {} }
+          .to raise_error(Wasmtime::Error, /requires a running epoch timer/)
+      end
+
+      it "raises on negative timeouts" do
+        engine.start_epoch_interval(5)
+
+        expect { store_deadline_1.with_timeout(-1) {} }.to raise_error(ArgumentError)
+      end
+    end
+
+    describe "Store#interrupt_handle" do
+      after { engine.stop_epoch_interval }
+
+      it "returns a frozen, Ractor-shareable handle" do
+        handle = store_deadline_1.interrupt_handle
+
+        expect(handle).to be_a(InterruptHandle)
+        expect(handle).to be_frozen
+        expect(Ractor.shareable?(handle)).to be true
+      end
+
+      it "interrupts the next call when no Wasm is running" do
+        store = Store.new(engine)
+        store.set_epoch_deadline(10)
+        instance = Instance.new(store, mod)
+        store.interrupt_handle.interrupt!
+        engine.increment_epoch
+
+        expect { instance.invoke("42") }.to raise_error(Trap) do |trap|
+          expect(trap.code).to eq(Trap::INTERRUPT)
+        end
+        expect(instance.invoke("42")).to eq(42)
+      end
+
+      it "interrupts running Wasm" do
+        handle = store_deadline_1.interrupt_handle
+        store_deadline_1.set_epoch_deadline(1_000_000)
+        mod = Module.new(engine, <<~WAT)
+          (module
+            (func $interrupt (import "" "interrupt"))
+            (func (export "run")
+              call $interrupt
+              (loop br 0)))
+        WAT
+        interrupt = Func.new(store_deadline_1, [], []) { handle.interrupt! }
+        instance = Instance.new(store_deadline_1, mod, [interrupt])
+        engine.start_epoch_interval(5)
+
+        expect { instance.invoke("run") }.to raise_error(Trap) do |trap|
+          expect(trap.code).to eq(Trap::INTERRUPT)
+        end
+      end
+
+      it "interrupts running Wasm without an epoch timer" do
+        handle = store_deadline_1.interrupt_handle
+        store_deadline_1.set_epoch_deadline(1_000_000)
+        mod = Module.new(engine, <<~WAT)
+          (module
+            (func $interrupt (import "" "interrupt"))
+            (func (export "run")
+              call $interrupt
+              (loop br 0)))
+        WAT
+        interrupt = Func.new(store_deadline_1, [], []) { handle.interrupt! }
+        instance = Instance.new(store_deadline_1, mod, [interrupt])
+
+        expect { instance.invoke("run") }.to raise_error(Trap) do |trap|
+          expect(trap.code).to eq(Trap::INTERRUPT)
+        end
+      end
+
+      it "interrupts the next call without an epoch tick" do
+        store = Store.new(engine)
+        store.set_epoch_deadline(10)
+        instance = Instance.new(store, mod)
+        store.interrupt_handle.interrupt!
+
+        expect { instance.invoke("42") }.to raise_error(Trap) do |trap|
+          expect(trap.code).to eq(Trap::INTERRUPT)
+        end
+        expect(instance.invoke("42")).to eq(42)
+      end
+
+      it "doesn't move the deadlines of other stores" do
+        instance = Instance.new(store_deadline_1, mod)
+        handle = Store.new(engine).interrupt_handle
+
+        3.times { handle.interrupt! }
+
+        expect(instance.invoke("42")).to eq(42)
+        engine.increment_epoch
+        expect { instance.invoke("42") }.to raise_error(Trap)
+      end
+
+      it "only interrupts its own store" do
+        interrupted = Store.new(engine).tap { |store| store.set_epoch_deadline(10) }
+        other = Store.new(engine).tap { |store| store.set_epoch_deadline(10) }
+        other.interrupt_handle
+        interrupted_instance = Instance.new(interrupted, mod)
+        other_instance = Instance.new(other, mod)
+
+        interrupted.interrupt_handle.interrupt!
+        engine.increment_epoch
+
+        expect(other_instance.invoke("42")).to eq(42)
+        expect { interrupted_instance.invoke("42") }.to raise_error(Trap) do |trap|
+          expect(trap.code).to eq(Trap::INTERRUPT)
+        end
+        expect(other_instance.invoke("42")).to eq(42)
+      end
+
+      it "preserves the epoch deadline" do
+        store = Store.new(engine)
+        store.set_epoch_deadline(3)
+        store.interrupt_handle
+        instance = Instance.new(store, mod)
+
+        2.times { engine.increment_epoch }
+        expect(instance.invoke("42")).to eq(42)
+
+        engine.increment_epoch
+        expect { instance.invoke("42") }.to raise_error(Trap)
+      end
+    end
+
     describe "Engine timer" do
       it "prevents infinite loop from running forever" do
         instance = Instance.new(store_deadline_1, mod)
@@ -46,6 +46,41 @@ RSpec.describe "Ractor", ractor: true do
     end
   end
 
+  it "passes Engine & Module to Ractors without Ractor.make_shareable" do
+    engine = Wasmtime::Engine.new
+    mod = Wasmtime::Module.new(engine, wat)
+
+    ractor = Ractor.new(engine, mod) do |engine, mod|
+      linker = Wasmtime::Linker.new(engine)
+      store = Wasmtime::Store.new(engine, Object.new)
+      linker.instantiate(store, mod).invoke("hello")
+    end
+
+    expect(value(ractor)).to eq([1, 2, 3.0, 4.0])
+  end
+
+  it "supports sharing Component with Ractors" do
+    engine = Wasmtime::Engine.new
+    component = Wasmtime::Component::Component.from_file(engine, "spec/fixtures/component_adder.wat")
+
+    ractor = Ractor.new(engine, component) do |engine, component|
+      linker = Wasmtime::Component::Linker.new(engine)
+      store = Wasmtime::Store.new(engine)
+      linker.instantiate(store, component).get_func("add").call(1, 2)
+    end
+
+    expect(value(ractor)).to eq(3)
+  end
+
+  it "keeps the engine's precompile_compatibility_key available" do
+    engine = Wasmtime::Engine.new
+    key = engine.precompile_compatibility_key
+
+    ractor = Ractor.new(engine) { |engine| engine.precompile_compatibility_key }
+
+    expect(value(ractor)).to eq(key)
+  end
+
   if Gem::Version.new(RUBY_VERSION) >= Gem::Version.new("4.0")
     def value(ractor) = ractor.value
   else
@@ -0,0 +1,78 @@
+require "spec_helper"
+
+module Wasmtime
+  RSpec.describe ArrayRef do
+    let(:engine) { Engine.new(wasm_gc: true, wasm_function_references: true) }
+    let(:type) { ArrayType.new(engine, [:mut, :i16]) }
+
+    describe ArrayType do
+      it "exposes its element type" do
+        expect(type.element).to eq([:mut, :i16])
+        expect(ArrayType.new(engine, :externref).element).to eq(:externref)
+      end
+    end
+
+    describe ".new" do
+      it "allocates an array" do
+        array = ArrayRef.new(store, type, [1, 2, 3])
+        expect(array.size).to eq(3)
+        expect(array.length).to eq(3)
+        expect(array.to_a).to eq([1, 2, 3])
+        expect(array.type.element).to eq([:mut, :i16])
+      end
+
+      it "holds externrefs" do
+        array = ArrayRef.new(store, ArrayType.new(engine, :externref), ["foo", nil])
+        expect(array.to_a).to eq(["foo", nil])
+      end
+    end
+
+    describe "#get, #set" do
+      let(:array) { ArrayRef.new(store, type, [1, 2, 3]) }
+
+      it "reads and writes elements" do
+        array[0] = -1
+        expect(array[0]).to eq(0xFFFF)
+        array.set(2, 42)
+        expect(array.get(2)).to eq(42)
+      end
+
+      it "raises when out of bounds" do
+        expect { array[3] }.to raise_error(Wasmtime::Error)
+        expect { array[3] = 1 }.to raise_error(Wasmtime::Error)
+      end
+
+      it "raises on immutable arrays" do
+        array = ArrayRef.new(store, ArrayType.new(engine, :i32), [1])
+        expect { array[0] = 2 }.to raise_error(Wasmtime::Error)
+      end
+    end
+
+    describe "with Wasm" do
+      let(:instance) do
+        compile(<<~WAT)
+          (module
+            (type $bytes (array (mut i8)))
+            (func (export "make") (param i32) (result (ref $bytes))
+              (array.new $bytes (i32.const 7) (local.get 0)))
+            (func (export "len") (param arrayref) (result i32)
+              (array.len (local.get 0)))
+            (func (export "first") (param (ref $bytes)) (result i32)
+              (array.get_u $bytes (local.get 0) (i32.const 0))))
+        WAT
+      end
+
+      it "returns arrays to Ruby" do
+        array = instance.invoke("make", 2)
+        expect(array).to be_instance_of(ArrayRef)
+        expect(array.to_a).to eq([7, 7])
+      end
+
+      it "passes arrays to Wasm" do
+        bytes = ArrayRef.new(store, ArrayType.new(engine, [:mut, :i8]), [9, 8, 7])
+        expect(instance.invoke("len", bytes)).to eq(3)
+        expect(instance.invoke("first", bytes)).to eq(9)
+      end
+    end
+  end
+end
@@ -10,6 +10,47 @@ module Wasmtime
         expect(deserialized.serialize).to eq(serialized)
       end
 
+      it "is frozen and Ractor-shareable" do
+        component = Component.new(engine, "(component)")
+        expect(component).to be_frozen
+        expect(Ractor.shareable?(component)).to be(true)
+      end
+
+      describe ".validate" do
+        it "returns nil for a valid component" do
+          expect(Component.validate(engine, Wasmtime.wat2wasm("(component)"))).to be_nil
+        end
+
+        it "raises a ValidationError with the offset on invalid bytes" do
+          expect { Component.validate(engine, "\0asm\x0d\0\x01\0garbage") }
+            .to raise_error(ValidationError) do |error|
+              expect(error.offset).to be_a(Integer)
+            end
+        end
+
+        it "raises a ValidationError on a core module" do
+          expect { Component.validate(engine, Wasmtime.wat2wasm("(module)")) }
+            .to raise_error(ValidationError)
+        end
+
+        it "validates with the engine's Wasm features" do
+          wasm = Wasmtime.wat2wasm("(component (core module (memory 1 1 shared)))")
+
+          expect(Component.validate(engine, wasm)).to be_nil
+          expect { Component.validate(Engine.new(wasm_threads: false), wasm) }
+            .to raise_error(ValidationError)
+        end
+
+        it "rejects the features the engine's compiler doesn't support" do
+          winch = Engine.new(strategy: :winch)
+          wasm = Wasmtime.wat2wasm("(component (core module (func (return_call 0))))")
+
+          expect(Component.validate(engine, wasm)).to be_nil
+          expect { Component.new(winch, wasm) }.to raise_error(Wasmtime::Error)
+          expect { Component.validate(winch, wasm) }.to raise_error(ValidationError)
+        end
+      end
+
       describe ".from_file" do
         it "loads the Component" do
           component = Component.from_file(engine, "spec/fixtures/empty_component.wat")
@@ -38,6 +38,30 @@ module Wasmtime
           end
         end
       end
+
+      describe "#call with gvl: false" do
+        let(:instance) { linker.instantiate(store, @adder_component) }
+
+        it "still returns correct results" do
+          expect(instance.get_func("add", gvl: false).call(1, 2)).to eq(3)
+        end
+
+        it "raises trap when component traps" do
+          func = linker.instantiate(store, @trap_component).get_func("unreachable", gvl: false)
+          expect { func.call }.to raise_error(Trap)
+        end
+
+        it "runs calls from several threads, each with its own store" do
+          results = 10.times.map do
+            Thread.new do
+              store = Store.new(engine)
+              linker.instantiate(store, @adder_component).get_func("add", gvl: false).call(20, 22)
+            end
+          end.map(&:value)
+
+          expect(results).to eq(Array.new(10, 42))
+        end
+      end
     end
   end
 end
@@ -34,6 +34,13 @@ module Wasmtime
           expect(linker.instantiate(store, component))
             .to be_instance_of(Wasmtime::Component::Instance)
         end
+
+        it "accepts gvl: false" do
+          component = Component.new(engine, "(component)")
+          store = Store.new(engine)
+          expect(linker.instantiate(store, component, gvl: false))
+            .to be_instance_of(Wasmtime::Component::Instance)
+        end
       end
 
       describe "LinkerInstance#func_new" do
@@ -196,6 +203,34 @@ module Wasmtime
 
             expect(result).to eq(1234)
           end
+
+          it "re-acquires the GVL for host functions called from a gvl: false func" do
+            stub_component_imports(linker, except: :greet)
+
+            linker.root do |root|
+              root.func_new("greet") do |name|
+                GC.start
+                "Hello, #{name}!"
+              end
+            end
+
+            instance = linker.instantiate(store, @host_imports_component, gvl: false)
+            result = instance.get_func("test-greet", gvl: false).call("World")
+
+            expect(result).to eq("Hello, World!")
+          end
+
+          it "bubbles host function exceptions from a gvl: false func" do
+            stub_component_imports(linker, except: :greet)
+
+            linker.root do |root|
+              root.func_new("greet") { |_name| raise "boom" }
+            end
+
+            instance = linker.instantiate(store, @host_imports_component)
+            expect { instance.get_func("test-greet", gvl: false).call("World") }
+              .to raise_error(RuntimeError, "boom")
+          end
         end
 
         context "with complex types" do
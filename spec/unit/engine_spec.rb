@@ -16,6 +16,12 @@ module Wasmtime
         expect { Engine.new(1, 2) }.to raise_error(ArgumentError)
       end
 
+      it "returns a frozen, Ractor-shareable engine" do
+        engine = Engine.new
+        expect(engine).to be_frozen
+        expect(Ractor.shareable?(engine)).to be(true)
+      end
+
       # bool & numeric options
       [
         [:debug_info, true],
@@ -25,12 +31,17 @@ module Wasmtime
         [:epoch_interruption, true],
         [:max_wasm_stack, 400, true],
         [:wasm_threads, true],
+        [:shared_memory, true],
         [:wasm_multi_memory, true],
         [:wasm_memory64, true],
+        [:wasm_custom_page_sizes, true],
         [:parallel_compilation, true],
         [:wasm_reference_types, true],
+        [:wasm_function_references, true],
+        [:wasm_gc, true],
         [:wasm_exceptions, true],
-        [:async_stack_zeroing, true]
+        [:async_stack_zeroing, true],
+        [:async_support, true]
       ].each do |option, valid, invalid = nil|
         it "supports #{option}" do
           Engine.new(option => valid)
@@ -80,6 +91,69 @@ module Wasmtime
       end
     end
 
+    describe "#compile_many" do
+      let(:sources) do
+        (1..20).map { |i| "(module (func (export \"f\") (result i32) i32.const #{i}))" }
+      end
+
+      it "returns Modules in order" do
+        modules = engine.compile_many(sources)
+
+        expect(modules).to all(be_instance_of(Wasmtime::Module))
+        results = modules.map { |mod| Instance.new(Store.new(engine), mod).invoke("f") }
+        expect(results).to eq((1..20).to_a)
+      end
+
+      it "returns errors per item" do
+        modules = engine.compile_many(["(module)", "(not a module)", "(module)"])
+
+        expect(modules[0]).to be_instance_of(Wasmtime::Module)
+        expect(modules[1]).to be_instance_of(Wasmtime::Error)
+        expect(modules[1].message).to match(/Could not build module/)
+        expect(modules[2]).to be_instance_of(Wasmtime::Module)
+      end
+
+      it "accepts an empty Array" do
+        expect(engine.compile_many([])).to eq([])
+      end
+
+      it "rejects non-String items" do
+        expect { engine.compile_many([nil]) }.to raise_error(TypeError)
+      end
+
+      it "lets other threads run while compiling" do
+        counter = 0
+        running = true
+        sibling = Thread.new { counter += 1 while running }
+
+        before = counter
+        engine.compile_many(sources * 10)
+        progress = counter - before
+
+        running = false
+        sibling.join
+        expect(progress).to be > 0
+      end
+    end
+
+    describe "#precompile_many" do
+      it "returns Strings usable by Module.deserialize, in order" do
+        serialized = engine.precompile_many(["(module)", "(module (func (export \"f\")))"])
+
+        expect(serialized).to all(be_instance_of(String))
+        mod = Module.deserialize(engine, serialized[1])
+        expect(Instance.new(Store.new(engine), mod).export("f")).not_to be_nil
+      end
+
+      it "returns errors per item" do
+        serialized = engine.precompile_many(["(module)", "(not a module)"])
+
+        expect(serialized[0]).to be_instance_of(String)
+        expect(serialized[1]).to be_instance_of(Wasmtime::Error)
+        expect(serialized[1].message).to start_with("Could not build module: ")
+      end
+    end
+
     describe ".precompile_component" do
       it "returns a String" do
         serialized = engine.precompile_component("(component)")
@@ -22,6 +22,15 @@ module Wasmtime
     let(:engine) { Engine.new(consume_fuel: true) }
     let(:store) { Store.new(engine) }
     let(:store_without_fuel) { Store.new(Engine.new) }
+    let(:counter_mod) do
+      Module.new(engine, <<~WAT)
+        (module
+          (func (export "f") (result i32)
+            i32.const 40
+            i32.const 2
+            i32.add))
+      WAT
+    end
 
     describe "#set_fuel" do
       test_on_store_and_caller "returns nil on success" do |store_like|
@@ -44,6 +53,77 @@ module Wasmtime
       end
     end
 
+    describe "#fuel_consumed" do
+      test_on_store_and_caller "starts at 0" do |store_like|
+        expect(store_like.fuel_consumed).to eq(0)
+      end
+
+      test_on_store_and_caller "is not reset by refueling" do |store_like|
+        store_like.set_fuel(100)
+        store_like.set_fuel(10)
+        expect(store_like.fuel_consumed).to eq(0)
+      end
+
+      test_on_store_and_caller "raises an error when fuel is not configured", :store_without_fuel do |store_like|
+        expect { store_like.fuel_consumed }.to(raise_error(Wasmtime::Error, /fuel is not configured in this store/))
+      end
+
+      it "accumulates across calls and refuels" do
+        instance = Instance.new(store, counter_mod)
+        store.set_fuel(1_000)
+        instance.invoke("f")
+        consumed = store.fuel_consumed
+        expect(consumed).to eq(1_000 - store.get_fuel)
+
+        store.set_fuel(1_000)
+        instance.invoke("f")
+        expect(store.fuel_consumed).to eq(consumed * 2)
+      end
+    end
+
+    describe "fuel: keyword" do
+      it "runs the call with its own budget and returns the remainder" do
+        instance = Instance.new(store, counter_mod)
+        store.set_fuel(5)
+
+        result, remaining = instance.invoke("f", fuel: 1_000)
+        expect(result).to eq(42)
+        expect(remaining).to be < 1_000
+        expect(store.fuel_consumed).to eq(1_000 - remaining)
+        expect(store.get_fuel).to eq(5)
+      end
+
+      it "is accepted by Func#call" do
+        instance = Instance.new(store, counter_mod)
+        func = instance.export("f").to_func
+
+        expect(func.call(fuel: 1_000)).to match([42, be < 1_000])
+      end
+
+      it "restores the store's fuel when the call traps" do
+        instance = Instance.new(store, counter_mod)
+        store.set_fuel(1_000)
+
+        expect { instance.invoke("f", fuel: 1) }.to raise_error(Trap, /all fuel consumed/)
+        expect(store.get_fuel).to eq(1_000)
+      end
+
+      it "raises when fuel is not configured" do
+        engine = Engine.new
+        mod = Module.new(engine, "(module (func (export \"f\")))")
+        instance = Instance.new(Store.new(engine), mod)
+
+        expect { instance.invoke("f", fuel: 1_000) }
+          .to raise_error(Wasmtime::Error, /fuel is not configured in this store/)
+      end
+    end
+
+    describe "#fuel_async_yield_interval=" do
+      it "raises when the engine is not configured for async" do
+        expect { store.fuel_async_yield_interval = 1_000 }.to raise_error(Wasmtime::Error)
+      end
+    end
+
     it "traps when Wasm execution runs out of fuel" do
       mod = Module.new(engine, <<~WAT)
         (module
@@ -28,7 +28,7 @@ module Wasmtime
 
       it "rejects unknown symbols" do
         expect { build_func([:nope], []) {} }
-          .to raise_error(ArgumentError, /expected one of \[:i32, :i64, :f32, :f64, :v128, :funcref, :externref\], got :nope/)
+          .to raise_error(ArgumentError, /expected one of \[:i32, :i64, :f32, :f64, :v128, :funcref, :externref, :anyref, :eqref, :i31ref, :structref, :arrayref\], got :nope/)
       end
 
       it "rejects non-symbols" do
@@ -117,6 +117,91 @@ module Wasmtime
       end
     end
 
+    describe "v128" do
+      let(:wasm) do
+        <<~WAT
+          (module
+            (import "" "host" (func $host (param v128) (result v128)))
+            (func (export "add") (param v128 v128) (result v128)
+              (i64x2.add (local.get 0) (local.get 1)))
+            (func (export "roundtrip") (param v128) (result v128)
+              (call $host (local.get 0))))
+        WAT
+      end
+
+      def instantiate(store, &host)
+        linker = Linker.new(engine)
+        linker.func_new("", "host", [:v128], [:v128], &host)
+        linker.instantiate(store, Module.new(engine, wasm))
+      end
+
+      it "converts to and from Integers" do
+        instance = instantiate(store) { |_, v| v + 1 }
+        expect(instance.invoke("add", 2**64 + 1, 2)).to eq(2**64 + 3)
+        expect(instance.invoke("roundtrip", 2**127)).to eq(2**127 + 1)
+      end
+
+      it "accepts negative Integers as two's complement" do
+        instance = instantiate(store) { |_, v| v }
+        expect(instance.invoke("roundtrip", -1)).to eq(2**128 - 1)
+      end
+
+      it "converts to and from 16-byte Strings with v128: :string" do
+        store = Store.new(engine, v128: :string)
+        instance = instantiate(store) { |_, v| v.reverse }
+        bytes = (0...16).to_a.pack("C*")
+
+        result = instance.invoke("roundtrip", bytes)
+        expect(result).to eq(bytes.reverse)
+        expect(result.encoding).to eq(Encoding::ASCII_8BIT)
+        expect(instance.invoke("add", [1, 2].pack("Q<Q<"), 3)).to eq([4, 2].pack("Q<Q<"))
+      end
+
+      it "rejects Strings of the wrong size" do
+        instance = instantiate(store) { |_, v| v }
+        expect { instance.invoke("roundtrip", "short") }
+          .to raise_error(Wasmtime::Error, /expected a 16-byte String for v128, got 5 bytes/)
+      end
+
+      it "rejects out of range Integers" do
+        instance = instantiate(store) { |_, v| v }
+        expect { instance.invoke("roundtrip", 2**128) }.to raise_error(RangeError)
+      end
+    end
+
+    describe "anyref" do
+      let(:engine) { Engine.new(wasm_gc: true, wasm_function_references: true) }
+
+      it "converts i31ref to and from Integers" do
+        instance = compile(<<~WAT)
+          (module
+            (func (export "roundtrip") (param anyref) (result anyref) (local.get 0))
+            (func (export "make") (param i32) (result i31ref) (ref.i31 (local.get 0))))
+        WAT
+
+        expect(instance.invoke("roundtrip", -42)).to eq(-42)
+        expect(instance.invoke("roundtrip", nil)).to be_nil
+        expect(instance.invoke("make", 2**30 - 1)).to eq(2**30 - 1)
+      end
+
+      it "rejects Integers out of i31 range" do
+        instance = compile('(module (func (export "id") (param anyref) (result anyref) (local.get 0)))')
+        expect { instance.invoke("id", 2**30) }.to raise_error(RangeError, /out of range for i31ref/)
+      end
+
+      it "rejects other objects" do
+        instance = compile('(module (func (export "id") (param anyref) (result anyref) (local.get 0)))')
+        expect { instance.invoke("id", "foo") }.to raise_error(TypeError)
+      end
+
+      it "supports GC types in Func.new" do
+        func = Func.new(store, [:eqref], [:i31ref]) { |_, value| value + 1 }
+        expect(func.params).to eq([:eqref])
+        expect(func.results).to eq([:i31ref])
+        expect(func.call(1)).to eq(2)
+      end
+    end
+
     describe "Caller" do
       it "exposes memory and func for the duration of the call only" do
         mod = Module.new(engine, <<~WAT)
@@ -209,6 +294,161 @@ module Wasmtime
         expect(marks.last).to be > marks.first
       end
 
+      it "is accepted as a keyword by Func#call" do
+        store = Store.new(engine)
+        mod = Module.new(engine, <<~WAT)
+          (module (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))))
+        WAT
+        func = Instance.new(store, mod).export("add").to_func
+        expect(func.call(2, 3, gvl: false)).to eq(5)
+      end
+
+      it "releases the GVL when passed to Func#call" do
+        mod = Module.new(engine, <<~WAT)
+          (module
+            (import "env" "mark" (func $mark))
+            (func (export "spin") (param $n i64) (result i64)
+              (local $i i64)
+              (call $mark)
+              (block $done
+                (loop $loop
+                  (br_if $done (i64.ge_u (local.get $i) (local.get $n)))
+                  (local.set $i (i64.add (local.get $i) (i64.const 1)))
+                  (br $loop)))
+              (call $mark)
+              (local.get $i)))
+        WAT
+
+        counter = 0
+        running = true
+        sibling = Thread.new { counter += 1 while running }
+
+        marks = []
+        store = Store.new(engine)
+        mark = Func.new(store, [], []) { marks << counter }
+        linker = Linker.new(engine)
+        linker.define(store, "env", "mark", mark)
+        instance = linker.instantiate(store, mod)
+
+        instance.export("spin").to_func.call(500_000_000, gvl: false)
+
+        running = false
+        sibling.join
+
+        expect(marks.length).to eq(2)
+        expect(marks.last).to be > marks.first
+      end
+
+      it "is accepted as a keyword by Instance#invoke" do
+        store = Store.new(engine)
+        mod = Module.new(engine, <<~WAT)
+          (module (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))))
+        WAT
+        expect(Instance.new(store, mod).invoke("add", 2, 3, gvl: false)).to eq(5)
+      end
+
+      it "releases the GVL when passed to Instance#invoke" do
+        mod = Module.new(engine, <<~WAT)
+          (module
+            (import "env" "mark" (func $mark))
+            (func (export "spin") (param $n i64) (result i64)
+              (local $i i64)
+              (call $mark)
+              (block $done
+                (loop $loop
+                  (br_if $done (i64.ge_u (local.get $i) (local.get $n)))
+                  (local.set $i (i64.add (local.get $i) (i64.const 1)))
+                  (br $loop)))
+              (call $mark)
+              (local.get $i)))
+        WAT
+
+        counter = 0
+        running = true
+        sibling = Thread.new { counter += 1 while running }
+
+        marks = []
+        store = Store.new(engine)
+        mark = Func.new(store, [], []) { marks << counter }
+        linker = Linker.new(engine)
+        linker.define(store, "env", "mark", mark)
+        instance = linker.instantiate(store, mod)
+
+        instance.invoke("spin", 500_000_000, gvl: false)
+
+        running = false
+        sibling.join
+
+        expect(marks.length).to eq(2)
+        expect(marks.last).to be > marks.first
+      end
+
+      it "can be combined with fuel:" do
+        engine = Engine.new(consume_fuel: true)
+        store = Store.new(engine)
+        mod = Module.new(engine, <<~WAT)
+          (module (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))))
+        WAT
+
+        result, remaining = Instance.new(store, mod).invoke("add", 2, 3, fuel: 1_000, gvl: false)
+        expect(result).to eq(5)
+        expect(remaining).to be < 1_000
+      end
+
+      it "overrides the to_func(gvl: false) default" do
+        store = Store.new(engine)
+        mod = Module.new(engine, <<~WAT)
+          (module (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))))
+        WAT
+        func = Instance.new(store, mod).export("add").to_func(gvl: false)
+        expect(func.call(2, 3, gvl: true)).to eq(5)
+      end
+
+      it "keeps the GVL when gvl: true overrides the to_func(gvl: false) default" do
+        mod = Module.new(engine, <<~WAT)
+          (module
+            (import "env" "mark" (func $mark))
+            (func (export "spin") (param $n i64) (result i64)
+              (local $i i64)
+              (call $mark)
+              (block $done
+                (loop $loop
+                  (br_if $done (i64.ge_u (local.get $i) (local.get $n)))
+                  (local.set $i (i64.add (local.get $i) (i64.const 1)))
+                  (br $loop)))
+              (call $mark)
+              (local.get $i)))
+        WAT
+
+        counter = 0
+        running = true
+        sibling = Thread.new { counter += 1 while running }
+
+        # Passing before the first mark starts a fresh time slice, so Ruby
+        # doesn't switch to the sibling when the block returns to Wasm.
+        marks = []
+        store = Store.new(engine)
+        mark = Func.new(store, [], []) do
+          Thread.pass if marks.empty?
+          marks << counter
+        end
+        linker = Linker.new(engine)
+        linker.define(store, "env", "mark", mark)
+        instance = linker.instantiate(store, mod)
+
+        instance.export("spin").to_func(gvl: false).call(500_000_000, gvl: true)
+
+        running = false
+        sibling.join
+
+        expect(marks.length).to eq(2)
+        expect(marks.last).to eq(marks.first)
+      end
+
       it "re-acquires the GVL for Ruby host callbacks during a released call" do
         expect(run_released_with_callback(host_callback_module, spin: 1_000)).to eq(42)
       end
@@ -28,6 +28,21 @@ module Wasmtime
       end
     end
 
+    describe "v128" do
+      it "gets and sets Integers" do
+        global = Global.var(store, :v128, 2**100)
+        expect(global.get).to eq(2**100)
+        global.set(-2)
+        expect(global.get).to eq(2**128 - 2)
+      end
+
+      it "returns Strings with v128: :string" do
+        store = Store.new(engine, v128: :string)
+        global = Global.var(store, :v128, 1)
+        expect(global.get).to eq("\x01" + "\x00" * 15)
+      end
+    end
+
     describe "#type" do
       it "returns the Wasm type as symbol" do
         global = Global.const(store, :i32, 1)
@@ -85,6 +85,128 @@ module Wasmtime
       end
     end
 
+    describe "#snapshot and #restore" do
+      let(:instance) do
+        compile(<<~WAT)
+          (module
+            (memory (export "mem") 1 3)
+            (global (export "counter") (mut i32) (i32.const 0))
+            (global (export "ref") (mut externref) (ref.null extern))
+            (table (export "table") 2 funcref)
+            (func $a (result i32) i32.const 1)
+            (elem (i32.const 0) $a)
+            (func (export "bump") (result i32)
+              (global.set 0 (i32.add (global.get 0) (i32.const 1)))
+              (i32.store (i32.const 0) (global.get 0))
+              (global.get 0)))
+        WAT
+      end
+      let(:memory) { instance.export("mem").to_memory }
+      let(:counter) { instance.export("counter").to_global }
+      let(:table) { instance.export("table").to_table }
+
+      it "rolls memories and globals back" do
+        snapshot = instance.snapshot
+        3.times { instance.invoke("bump") }
+        expect(memory.read(0, 1)).to eq("\x03")
+
+        instance.restore(snapshot)
+
+        expect(counter.get).to eq(0)
+        expect(memory.read(0, 1)).to eq("\x00")
+        expect(instance.invoke("bump")).to eq(1)
+      end
+
+      it "can be restored many times" do
+        snapshot = instance.snapshot
+        results = 3.times.map do
+          result = instance.invoke("bump")
+          instance.restore(snapshot)
+          result
+        end
+
+        expect(results).to eq([1, 1, 1])
+      end
+
+      it "zeroes memory pages grown since the snapshot" do
+        snapshot = instance.snapshot
+        memory.grow(1)
+        memory.write(65_536, "leak")
+
+        instance.restore(snapshot)
+
+        expect(memory.size).to eq(2)
+        expect(memory.read(65_536, 4)).to eq("\x00" * 4)
+      end
+
+      it "rolls tables back" do
+        snapshot = instance.snapshot
+        func = table.get(0)
+        table.set(0, nil)
+        table.grow(1, func)
+
+        instance.restore(snapshot)
+
+        expect(table.get(0)).to be_a(Func)
+        expect(table.get(1)).to be_nil
+        expect(table.get(2)).to be_nil
+      end
+
+      it "rolls externref globals back" do
+        ref = instance.export("ref").to_global
+        ref.set("before")
+        snapshot = instance.snapshot
+        ref.set("after")
+
+        instance.restore(snapshot)
+        GC.start
+
+        expect(ref.get).to eq("before")
+      end
+
+      it "keeps Ruby objects retained since the snapshot alive" do
+        snapshot = instance.snapshot
+        func = Func.new(store, [], [:i32]) { 2 }
+        table.set(1, func)
+
+        instance.restore(snapshot)
+        GC.start
+
+        expect(func.call).to eq(2)
+      end
+
+      it "doesn't grow the store's retained objects across restores" do
+        require "objspace"
+        ref = instance.export("ref").to_global
+        ref.set(Object.new)
+        snapshot = instance.snapshot
+        instance.restore(snapshot)
+        size = ObjectSpace.memsize_of(store)
+
+        1_000.times { instance.restore(snapshot) }
+
+        expect(ObjectSpace.memsize_of(store)).to eq(size)
+      end
+
+      it "rejects snapshots of other instances" do
+        other = compile("(module)")
+        expect { other.restore(instance.snapshot) }
+          .to raise_error(Wasmtime::Error, /another instance/)
+      end
+
+      it "restores a snapshot taken after an earlier restored one" do
+        ref = instance.export("ref").to_global
+        first = instance.snapshot
+        ref.set("second")
+        second = instance.snapshot
+        instance.restore(first)
+        GC.start
+
+        instance.restore(second)
+        expect(ref.get).to eq("second")
+      end
+    end
+
     private
 
     def invoke_identity_function(type, arg)
@@ -154,6 +154,42 @@ module Wasmtime
 
       expect(linker.get_default(store, "mod1")).to be_instance_of(Func)
       expect { linker.get_default(store, "mod2") }.to raise_error(Wasmtime::Error, /not a function/)
+      expect(linker.get_default(store, "mod1", gvl: false).call).to be_nil
+    end
+
+    it "#get_default with gvl: false releases the GVL during calls" do
+      mod = Module.new(engine, <<~WAT)
+        (module
+          (import "env" "mark" (func $mark))
+          (func (export "")
+            (local $i i64)
+            (call $mark)
+            (block $done
+              (loop $loop
+                (br_if $done (i64.ge_u (local.get $i) (i64.const 500_000_000)))
+                (local.set $i (i64.add (local.get $i) (i64.const 1)))
+                (br $loop)))
+            (call $mark)))
+      WAT
+
+      counter = 0
+      running = true
+      sibling = Thread.new { counter += 1 while running }
+
+      marks = []
+      store = Store.new(engine)
+      mark = Func.new(store, [], []) { marks << counter }
+      linker = new_linker
+      linker.define(store, "env", "mark", mark)
+      linker.module(store, "spinner", mod)
+
+      linker.get_default(store, "spinner", gvl: false).call
+
+      running = false
+      sibling.join
+
+      expect(marks.length).to eq(2)
+      expect(marks.last).to be > marks.first
     end
 
     it "#use_deterministic_scheduling_functions" do
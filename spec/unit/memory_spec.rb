@@ -8,6 +8,59 @@ module Wasmtime
         mem = Memory.new(store, min_size: 1)
         expect(mem).to be_instance_of(Wasmtime::Memory)
       end
+
+      it "creates a 64-bit memory" do
+        store = Store.new(Engine.new(wasm_memory64: true))
+        mem = Memory.new(store, min_size: 1, memory64: true)
+        expect(mem.size).to eq(1)
+      end
+
+      it "creates a memory with a custom page size" do
+        store = Store.new(Engine.new(wasm_custom_page_sizes: true))
+        mem = Memory.new(store, min_size: 3, max_size: 10, page_size_log2: 0)
+        expect(mem.data_size).to eq(3)
+        expect(mem.grow(2)).to eq(3)
+        expect(mem.data_size).to eq(5)
+      end
+
+      it "raises on invalid page sizes" do
+        expect { Memory.new(store, min_size: 1, page_size_log2: 3) }.to raise_error(Wasmtime::Error)
+      end
+
+      it "reads and writes beyond 4GiB" do
+        store = Store.new(Engine.new(wasm_memory64: true))
+        mem = Memory.new(store, min_size: 2**16 + 1, memory64: true)
+        mem.write(2**32 + 8, "far")
+        expect(mem.read(2**32 + 8, 3)).to eq("far")
+        expect(mem.read(8, 3)).to eq("\0\0\0")
+      end
+    end
+
+    describe MemoryType do
+      def memory_type(engine, wat)
+        Module.new(engine, wat).imports.first["type"].to_memory_type
+      end
+
+      it "exposes 32-bit memories' metadata" do
+        type = memory_type(engine, '(module (import "" "m" (memory 1)))')
+        expect(type).not_to be_is_64
+        expect(type).not_to be_shared
+        expect(type.page_size).to eq(64 * 2**10)
+        expect(type.page_size_log2).to eq(16)
+      end
+
+      it "exposes 64-bit memories' metadata" do
+        engine = Engine.new(wasm_memory64: true)
+        type = memory_type(engine, '(module (import "" "m" (memory i64 1)))')
+        expect(type).to be_is_64
+      end
+
+      it "exposes custom page sizes" do
+        engine = Engine.new(wasm_custom_page_sizes: true)
+        type = memory_type(engine, '(module (import "" "m" (memory 1 (pagesize 1))))')
+        expect(type.page_size).to eq(1)
+        expect(type.page_size_log2).to eq(0)
+      end
     end
 
     describe "#size" do
@@ -258,6 +311,117 @@ module Wasmtime
       end
     end
 
+    describe "#read_utf16, #write_utf16" do
+      it "round-trips strings, including surrogate pairs" do
+        mem = Memory.new(store, min_size: 1)
+        expect(mem.write_utf16(0, "héllo 🌍")).to eq(8)
+        expect(mem.read(0, 4)).to eq("h\0\xE9\0".b)
+
+        str = mem.read_utf16(0, 8)
+        expect(str).to eq("héllo 🌍")
+        expect(str.encoding).to eq(Encoding::UTF_8)
+      end
+
+      it "supports big-endian" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write_utf16(0, "hi", endian: :big)
+        expect(mem.read(0, 4)).to eq("\0h\0i")
+        expect(mem.read_utf16(0, 2, endian: :big)).to eq("hi")
+      end
+
+      it "raises on unpaired surrogates" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, "\x00\xD8")
+        expect { mem.read_utf16(0, 1) }.to raise_error(Wasmtime::Error, /invalid utf-16/)
+      end
+
+      it "replaces unpaired surrogates with lossy: true" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, "h\x00\x00\xD8i\x00")
+        expect(mem.read_utf16(0, 3, lossy: true)).to eq("h\uFFFDi")
+      end
+
+      it "raises when out of bounds" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.read_utf16(64 * 2**10 - 2, 2) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+        expect { mem.write_utf16(64 * 2**10 - 2, "ab") }
+          .to raise_error(Wasmtime::Error, /out of bounds/)
+      end
+    end
+
+    describe "#copy" do
+      it "copies bytes, including overlapping ranges" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, "abcdef")
+        expect(mem.copy(2, 0, 4)).to be_nil
+        expect(mem.read(0, 6)).to eq("ababcd")
+      end
+
+      it "raises when out of bounds" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.copy(64 * 2**10 - 1, 0, 2) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+        expect { mem.copy(0, 64 * 2**10 - 1, 2) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+      end
+    end
+
+    describe "#fill" do
+      it "sets bytes" do
+        mem = Memory.new(store, min_size: 1)
+        expect(mem.fill(1, 0x61, 3)).to be_nil
+        expect(mem.read(0, 5)).to eq("\0aaa\0")
+      end
+
+      it "raises when out of bounds" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.fill(64 * 2**10 - 1, 0, 2) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+        expect { mem.fill(0, 256, 1) }.to raise_error(RangeError)
+      end
+    end
+
+    describe "#compare" do
+      it "compares bytes like memcmp" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, "abcabd")
+        expect(mem.compare(0, 3, 2)).to eq(0)
+        expect(mem.compare(0, 3, 3)).to eq(-1)
+        expect(mem.compare(3, 0, 3)).to eq(1)
+      end
+
+      it "raises when out of bounds" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.compare(0, 64 * 2**10 - 1, 2) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+      end
+    end
+
+    describe "#find" do
+      it "finds a byte" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, "hello\0world\0")
+        expect(mem.find(0)).to eq(5)
+        expect(mem.find(0, from: 6)).to eq(11)
+      end
+
+      it "finds a string" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, "hello world")
+        expect(mem.find("o")).to eq(4)
+        expect(mem.find("wor")).to eq(6)
+        expect(mem.find("o", from: 5)).to eq(7)
+        expect(mem.find("nope")).to be_nil
+      end
+
+      it "raises when out of bounds" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.find(0, from: 64 * 2**10 + 1) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+      end
+    end
+
     describe "#unsafe_slice" do
       it "exposes a frozen string" do
         mem = Memory.new(store, min_size: 1)
@@ -304,5 +468,377 @@ module Wasmtime
           .to raise_error(Wasmtime::Error, "out of bounds memory access")
       end
     end
+
+    describe "#read_array, #write_array" do
+      {
+        i8: [-128, 0, 127],
+        u8: [0, 1, 255],
+        i16: [-32768, 0, 32767],
+        u16: [0, 1, 65535],
+        i32: [-2**31, 0, 2**31 - 1],
+        u32: [0, 1, 2**32 - 1],
+        i64: [-2**63, 0, 2**63 - 1],
+        u64: [0, 1, 2**64 - 1],
+        f32: [-1.5, 0.0, 3.25],
+        f64: [-1.5, 0.0, Float::MAX]
+      }.each do |type, values|
+        it "round-trips #{type}" do
+          mem = Memory.new(store, min_size: 1)
+          expect(mem.write_array(type, 8, values)).to be_nil
+          expect(mem.read_array(type, 8, values.size)).to eq(values)
+        end
+      end
+
+      it "defaults to little-endian" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write_array(:u32, 0, [1, 2])
+        expect(mem.read(0, 8)).to eq([1, 2].pack("L<*"))
+        expect(mem.read_u32(4)).to eq(2)
+      end
+
+      it "supports big-endian" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write_array(:u16, 0, [0x0102, 0x0304], endian: :big)
+        expect(mem.read(0, 4)).to eq("\x01\x02\x03\x04".b)
+        expect(mem.read_array(:u16, 0, 2, endian: :big)).to eq([0x0102, 0x0304])
+        expect(mem.read_array(:u16, 0, 2)).to eq([0x0201, 0x0403])
+      end
+
+      it "returns a packed String when requested" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write_array(:i32, 0, [-1, 7])
+        packed = mem.read_array(:i32, 0, 2, packed: true)
+        expect(packed).to eq([-1, 7].pack("l<*"))
+        expect(packed.encoding).to eq(Encoding::ASCII_8BIT)
+      end
+
+      it "writes packed Strings as-is" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write_array(:u16, 0, [1, 2].pack("S<*"))
+        expect(mem.read_array(:u16, 0, 2)).to eq([1, 2])
+        expect { mem.write_array(:u16, 0, "abc") }
+          .to raise_error(ArgumentError, /not a multiple of the element size/)
+      end
+
+      it "reads 10k elements" do
+        mem = Memory.new(store, min_size: 1)
+        values = (0...10_000).to_a
+        mem.write_array(:u32, 0, values)
+        expect(mem.read_array(:u32, 0, 10_000)).to eq(values)
+      end
+
+      it "writes nothing when a value can't be converted" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.write_array(:u8, 0, [1, 256]) }.to raise_error(RangeError)
+        expect { mem.write_array(:u8, 0, [1, "2"]) }.to raise_error(TypeError)
+        expect(mem.read(0, 2)).to eq("\0\0")
+      end
+
+      it "raises on invalid types and endianness" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.read_array(:i128, 0, 1) }.to raise_error(ArgumentError, /invalid type/)
+        expect { mem.read_array(:u8, 0, 1, endian: :middle) }
+          .to raise_error(ArgumentError, /invalid :endian/)
+      end
+
+      it "raises when out of bounds" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.read_array(:u32, 64 * 2**10 - 4, 2) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+        expect { mem.write_array(:u32, 64 * 2**10 - 4, [1, 2]) }
+          .to raise_error(Wasmtime::Error, /out of bounds/)
+      end
+    end
+
+    describe "#read_struct" do
+      it "aligns fields like C" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(16, "\x01")
+        mem.write_u32(20, 1024)
+        mem.write_u32(24, 5)
+        mem.write_f64(32, 2.5)
+
+        result = mem.read_struct(16, {tag: :u8, ptr: :u32, len: :u32, value: :f64})
+        expect(result).to eq(tag: 1, ptr: 1024, len: 5, value: 2.5)
+      end
+
+      it "supports packed layouts" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, [1, 1024].pack("CL<"))
+        expect(mem.read_struct(0, {tag: :u8, ptr: :u32}, packed: true)).to eq(tag: 1, ptr: 1024)
+      end
+
+      it "supports fixed-size arrays" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, [1, 2, 3, 0, 9].pack("CCCCL<"))
+        expect(mem.read_struct(0, {rgb: [:u8, 3], id: :u32})).to eq(rgb: [1, 2, 3], id: 9)
+      end
+
+      it "supports big-endian" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, [258].pack("S>"))
+        expect(mem.read_struct(0, {value: :u16}, endian: :big)).to eq(value: 258)
+      end
+
+      it "raises when out of bounds" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.read_struct(64 * 2**10 - 2, {a: :u8, b: :u32}) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+      end
+    end
+
+    describe "#to_io_buffer" do
+      it "maps the memory without copying" do
+        mem = Memory.new(store, min_size: 1)
+        buffer = mem.to_io_buffer(8, 3, writable: true)
+
+        mem.write(8, "foo")
+        expect(buffer.get_string).to eq("foo")
+        buffer.set_string("bar")
+        expect(mem.read(8, 3)).to eq("bar")
+        expect(buffer).to be_external
+      end
+
+      it "is read-only by default" do
+        mem = Memory.new(store, min_size: 1)
+        buffer = mem.to_io_buffer(0, 3)
+
+        expect(buffer).to be_readonly
+        expect { buffer.set_string("foo") }.to raise_error(IO::Buffer::AccessError)
+      end
+
+      it "defaults to the rest of the memory" do
+        mem = Memory.new(store, min_size: 1)
+        expect(mem.to_io_buffer.size).to eq(mem.data_size)
+        expect(mem.to_io_buffer(16).size).to eq(mem.data_size - 16)
+      end
+
+      it "raises when out of bounds" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.to_io_buffer(64 * 2**10 - 1, 2) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+      end
+
+      it "is released when the memory grows" do
+        mem = Memory.new(store, min_size: 1)
+        buffer = mem.to_io_buffer(0, 3)
+        mem.grow(1)
+
+        expect(buffer).to be_null
+        expect { buffer.get_string }.to raise_error(IO::Buffer::AllocationError)
+      end
+
+      it "is released when Wasm grows a memory" do
+        instance = compile(<<~WAT).then { |mod| Instance.new(store, mod) }
+          (module
+            (memory (export "memory") 1)
+            (func (export "grow") (drop (memory.grow (i32.const 1)))))
+        WAT
+        buffer = instance.export("memory").to_memory.to_io_buffer(0, 3)
+        instance.invoke("grow")
+
+        expect(buffer).to be_null
+      end
+
+      it "is released at the end of the host call when obtained from a Caller" do
+        buffer = nil
+        linker = Linker.new(engine)
+        linker.func_new("", "capture", [], []) do |caller|
+          buffer = caller.export("memory").to_memory.to_io_buffer(0, 3)
+          expect(buffer).not_to be_null
+        end
+        instance = linker.instantiate(store, compile(<<~WAT))
+          (module
+            (import "" "capture" (func $capture))
+            (memory (export "memory") 1)
+            (func (export "run") (call $capture)))
+        WAT
+        instance.invoke("run")
+
+        expect(buffer).to be_null
+      end
+
+      it "fails the host call when obtained from a Caller and still locked" do
+        buffer = nil
+        locker = nil
+        linker = Linker.new(engine)
+        linker.func_new("", "capture", [], []) do |caller|
+          buffer = caller.export("memory").to_memory.to_io_buffer(0, 3)
+          locker = Fiber.new { buffer.locked { Fiber.yield } }
+          locker.resume
+        end
+        instance = linker.instantiate(store, compile(<<~WAT))
+          (module
+            (import "" "capture" (func $capture))
+            (memory (export "memory") 1)
+            (func (export "run") (call $capture)))
+        WAT
+
+        expect { instance.invoke("run") }.to raise_error(IO::Buffer::LockedError)
+        locker.resume
+        instance.export("memory").to_memory.grow(1)
+        expect(buffer).to be_null
+      end
+
+      it "fails the growth while locked" do
+        mem = Memory.new(store, min_size: 1)
+        buffer = mem.to_io_buffer(0, 3)
+
+        buffer.locked do
+          expect { mem.grow(1) }.to raise_error(IO::Buffer::LockedError)
+        end
+        expect(mem.size).to eq(1)
+        mem.grow(1)
+        expect(buffer).to be_null
+      end
+    end
+
+    describe "#read_into, #write_from" do
+      it "copies between the memory and a buffer" do
+        mem = Memory.new(store, min_size: 1)
+        source = IO::Buffer.for("hello".dup)
+        expect(mem.write_from(4, source)).to eq(5)
+        expect(mem.read(4, 5)).to eq("hello")
+
+        destination = IO::Buffer.new(3)
+        expect(mem.read_into(5, destination)).to eq(3)
+        expect(destination.get_string).to eq("ell")
+      end
+
+      it "accepts a length" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write_from(0, IO::Buffer.for("hello".dup), 2)
+        expect(mem.read(0, 3)).to eq("he\0")
+
+        destination = IO::Buffer.new(8)
+        mem.read_into(0, destination, 2)
+        expect(destination.get_string(0, 3)).to eq("he\0")
+      end
+
+      it "raises when out of bounds" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.read_into(64 * 2**10 - 1, IO::Buffer.new(2)) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+        expect { mem.write_from(64 * 2**10 - 1, IO::Buffer.new(2)) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+      end
+
+      it "raises when the buffer is too small" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.read_into(0, IO::Buffer.new(2), 4) }.to raise_error(ArgumentError)
+      end
+
+      it "requires an IO::Buffer" do
+        mem = Memory.new(store, min_size: 1)
+        leaky = Object.new
+        leaky.define_singleton_method(:copy) { |view| @view = view }
+
+        expect { mem.read_into(0, leaky, 4) }
+          .to raise_error(TypeError, "wrong argument type Object (expected IO::Buffer)")
+        expect { mem.write_from(0, "data") }
+          .to raise_error(TypeError, "wrong argument type String (expected IO::Buffer)")
+      end
+    end
+
+    describe "#open" do
+      it "reads and seeks within the range" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(10, "hello world")
+        io = mem.open(10, 11)
+
+        expect(io.size).to eq(11)
+        expect(io.read(5)).to eq("hello")
+        expect(io.pos).to eq(5)
+        expect(io.getbyte).to eq(32)
+        expect(io.read).to eq("world")
+        expect(io.read(1)).to be_nil
+        expect(io.read).to eq("")
+        expect(io).to be_eof
+
+        io.seek(-5, IO::SEEK_END)
+        expect(io.read(2)).to eq("wo")
+        io.seek(-4, :CUR)
+        expect(io.read(3, +"buf")).to eq("o w")
+        io.rewind
+        expect(io.readpartial(100)).to eq("hello world")
+        expect { io.readpartial(1) }.to raise_error(EOFError)
+      end
+
+      it "defaults to the rest of the memory" do
+        mem = Memory.new(store, min_size: 1)
+        expect(mem.open.size).to eq(64 * 2**10)
+        expect(mem.open(10).size).to eq(64 * 2**10 - 10)
+      end
+
+      it "returns binary strings" do
+        mem = Memory.new(store, min_size: 1)
+        expect(mem.open(0, 4).read.encoding).to eq(Encoding::ASCII_8BIT)
+      end
+
+      it "writes within the range" do
+        mem = Memory.new(store, min_size: 1)
+        io = mem.open(4, 8)
+        expect(io.write("ab", 12)).to eq(4)
+        expect(mem.read(4, 4)).to eq("ab12")
+        expect { io.write("too long") }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+      end
+
+      it "reads lines" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, "a\nbc\n\nd")
+        io = mem.open(0, 7)
+
+        expect(io.gets).to eq("a\n")
+        expect(io.gets(chomp: true)).to eq("bc")
+        expect(io.gets(nil)).to eq("\nd")
+        expect(io.gets).to be_nil
+
+        io.rewind
+        expect(io.each_line.to_a).to eq(["a\n", "bc\n", "\n", "d"])
+        io.rewind
+        expect(io.each_line(chomp: true).to_a).to eq(["a", "bc", "", "d"])
+        io.rewind
+        expect(io.gets(2)).to eq("a\n")
+        expect(io.gets("c", 1)).to eq("b")
+      end
+
+      it "raises on a zero limit for each_line" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, "a\nb")
+        io = mem.open(0, 3)
+
+        expect { io.each_line(0) {} }.to raise_error(ArgumentError, /invalid limit: 0/)
+        expect(io.gets(0)).to eq("")
+      end
+
+      it "reads paragraphs" do
+        mem = Memory.new(store, min_size: 1)
+        mem.write(0, "\na\nb\n\n\nc\n")
+        io = mem.open(0, 10)
+
+        expect(io.each_line("").to_a).to eq(["a\nb\n\n", "c\n"])
+        io.rewind
+        expect(io.each_line("", chomp: true).to_a).to eq(["a\nb", "c"])
+      end
+
+      it "raises on invalid positions" do
+        mem = Memory.new(store, min_size: 1)
+        expect { mem.open(64 * 2**10 - 1, 2) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+        expect { mem.open(0, 1).seek(-1) }.to raise_error(Errno::EINVAL)
+        expect { mem.open(0, 1).seek(0, :BOGUS) }.to raise_error(ArgumentError)
+      end
+
+      it "streams into Zlib" do
+        require "zlib"
+        compressed = Zlib.gzip("hello from wasm memory")
+        mem = Memory.new(store, min_size: 1)
+        mem.write(100, compressed)
+
+        reader = Zlib::GzipReader.new(mem.open(100, compressed.bytesize))
+        expect(reader.read).to eq("hello from wasm memory")
+      end
+    end
   end
 end
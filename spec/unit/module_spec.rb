@@ -12,6 +12,16 @@ module Wasmtime
       expect(deserialized.serialize).to eq(serialized)
     end
 
+    it "is frozen and Ractor-shareable" do
+      mod = Module.new(engine, wat)
+      deserialized = Module.deserialize(engine, mod.serialize)
+
+      [mod, deserialized, *engine.compile_many([wat])].each do |m|
+        expect(m).to be_frozen
+        expect(Ractor.shareable?(m)).to be(true)
+      end
+    end
+
     describe ".from_file" do
       it "loads the module" do
         mod = Module.from_file(engine, "spec/fixtures/empty.wat")
@@ -28,6 +38,27 @@ module Wasmtime
       end
     end
 
+    describe ".validate" do
+      it "returns nil for a valid module" do
+        expect(Module.validate(engine, Wasmtime.wat2wasm(wat))).to be_nil
+      end
+
+      it "raises a ValidationError with the offset and message" do
+        wasm = Wasmtime.wat2wasm("(module (func (result i32)))")
+
+        expect { Module.validate(engine, wasm) }.to raise_error(ValidationError) do |error|
+          expect(error.offset).to be_a(Integer)
+          expect(error.message).to include("type mismatch")
+        end
+      end
+
+      it "raises a ValidationError on a component" do
+        wasm = Wasmtime.wat2wasm("(component)")
+
+        expect { Module.validate(engine, wasm) }.to raise_error(ValidationError)
+      end
+    end
+
     describe ".deserialize_file" do
       include_context(:tmpdir)
       let(:tmpdir) { Dir.mktmpdir }
@@ -0,0 +1,116 @@
+require "spec_helper"
+
+module Wasmtime
+  RSpec.describe RefType do
+    let(:engine) { Engine.new(wasm_gc: true, wasm_function_references: true) }
+    let(:sig) { FuncType.new(engine, [:i32], [:i32]) }
+    let(:sig_ref) { RefType.new(sig, nullable: false) }
+
+    describe ".new" do
+      it "creates abstract reference types" do
+        type = RefType.new(:func)
+        expect(type).to be_nullable
+        expect(type.heap_type).to eq(:func)
+        expect(type.to_s).to eq("(ref null func)")
+      end
+
+      it "creates non-nullable concrete reference types" do
+        expect(sig_ref).not_to be_nullable
+        expect(sig_ref.heap_type).to be_instance_of(FuncType)
+        expect(sig_ref.heap_type.params).to eq([:i32])
+      end
+
+      it "rejects unknown heap types" do
+        expect { RefType.new(:nope) }.to raise_error(ArgumentError, /invalid heap type/)
+      end
+    end
+
+    describe "#==" do
+      it "compares reference types" do
+        expect(RefType.new(:extern, nullable: false)).to eq(RefType.new(:extern, nullable: false))
+        expect(RefType.new(:extern, nullable: false)).not_to eq(RefType.new(:extern))
+        expect(sig_ref).to eq(RefType.new(FuncType.new(engine, [:i32], [:i32]), nullable: false))
+        expect(sig_ref).not_to eq(:funcref)
+      end
+
+      it "is false for concrete types of different engines" do
+        other = RefType.new(FuncType.new(Engine.new(wasm_function_references: true), [:i32], [:i32]))
+        expect(RefType.new(sig)).not_to eq(other)
+      end
+    end
+
+    describe "FuncType" do
+      it "reports nullable abstract types as Symbols" do
+        type = FuncType.new(engine, [RefType.new(:func), RefType.new(:any)], [])
+        expect(type.params).to eq([:funcref, :anyref])
+      end
+
+      it "reports non-nullable and concrete types as RefTypes" do
+        type = FuncType.new(engine, [RefType.new(:func, nullable: false), sig_ref], [RefType.new(:none)])
+        expect(type.params).to eq([RefType.new(:func, nullable: false), sig_ref])
+        expect(type.results.first.heap_type).to eq(:none)
+      end
+
+      it "reports the types of module imports" do
+        mod = Module.new(engine, <<~WAT)
+          (module
+            (type $sig (func (param i32) (result i32)))
+            (import "" "f" (func (param (ref $sig) (ref null extern)))))
+        WAT
+        params = mod.imports.first["type"].to_func_type.params
+        expect(params).to eq([sig_ref, :externref])
+      end
+
+      it "raises for concrete types of another engine" do
+        other = Engine.new(wasm_function_references: true)
+        expect { FuncType.new(other, [sig_ref], []) }
+          .to raise_error(Wasmtime::Error, "type used with wrong engine")
+      end
+    end
+
+    describe "with Func, Table and Global" do
+      let(:double) { Func.new(store, [:i32], [:i32]) { |_, x| x * 2 } }
+
+      it "supports typed function references in Func.new" do
+        call = Func.new(store, [sig_ref, :i32], [:i32]) { |_, f, x| f.call(x) }
+        expect(call.params).to eq([sig_ref, :i32])
+        expect(call.call(double, 21)).to eq(42)
+      end
+
+      it "rejects nil for non-nullable references" do
+        func = Func.new(store, [sig_ref], []) {}
+        expect { func.call(nil) }.to raise_error(Wasmtime::Error)
+      end
+
+      it "rejects functions of the wrong type" do
+        func = Func.new(store, [sig_ref], []) {}
+        other = Func.new(store, [], []) {}
+        expect { func.call(other) }.to raise_error(Wasmtime::Error)
+      end
+
+      it "supports typed function references in Table.new" do
+        table = Table.new(store, sig_ref, double, min_size: 2)
+        expect(table.type).to eq(sig_ref)
+        expect(table.get(1).call(2)).to eq(4)
+        expect { table.set(0, nil) }.to raise_error(Wasmtime::Error)
+      end
+
+      it "supports typed function references in Global.var" do
+        global = Global.var(store, RefType.new(sig), nil)
+        expect(global.type).to eq(RefType.new(sig))
+        global.set(double)
+        expect(global.get.call(3)).to eq(6)
+      end
+
+      it "calls Wasm through typed function references" do
+        instance = compile(<<~WAT)
+          (module
+            (type $sig (func (param i32) (result i32)))
+            (func (export "apply") (param (ref $sig) i32) (result i32)
+              (call_ref $sig (local.get 1) (local.get 0))))
+        WAT
+        expect(instance.invoke("apply", double, 5)).to eq(10)
+      end
+    end
+  end
+end
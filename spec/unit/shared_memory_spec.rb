@@ -0,0 +1,137 @@
+require "spec_helper"
+
+module Wasmtime
+  RSpec.describe SharedMemory do
+    let(:engine) { Engine.new(wasm_threads: true, shared_memory: true) }
+    let(:memory) { SharedMemory.new(engine, min_size: 1, max_size: 2) }
+
+    describe ".new" do
+      it "creates a shared memory" do
+        expect(memory).to be_instance_of(SharedMemory)
+        expect(memory.min_size).to eq(1)
+        expect(memory.max_size).to eq(2)
+      end
+
+      it "raises when the engine doesn't allow shared memories" do
+        expect { SharedMemory.new(Engine.new, min_size: 1, max_size: 2) }
+          .to raise_error(Wasmtime::Error)
+      end
+
+      it "requires a max_size" do
+        expect { SharedMemory.new(engine, min_size: 1) }.to raise_error(ArgumentError)
+      end
+    end
+
+    describe "#type" do
+      it "is shared" do
+        expect(memory.type).to be_shared
+        expect(memory.type.max_size).to eq(2)
+      end
+
+      it "is not shared for regular memories" do
+        mod = Module.new(engine, '(module (import "env" "mem" (memory 1)))')
+        expect(mod.imports.first["type"].to_memory_type).not_to be_shared
+      end
+    end
+
+    describe "#read, #write" do
+      it "reads and writes" do
+        memory.write(0, "foo")
+        expect(memory.read(0, 3)).to eq("foo")
+      end
+
+      it "raises when out of bounds" do
+        expect { memory.read(64 * 2**10, 1) }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+        expect { memory.write(64 * 2**10, "a") }
+          .to raise_error(Wasmtime::Error, "out of bounds memory access")
+      end
+    end
+
+    describe "#grow" do
+      it "grows up to max_size" do
+        expect(memory.grow(1)).to eq(1)
+        expect(memory.size).to eq(2)
+        expect(memory.data_size).to eq(2 * 64 * 2**10)
+        expect { memory.grow(1) }.to raise_error(Wasmtime::Error)
+      end
+    end
+
+    describe "as an import" do
+      let(:wasm_module) do
+        Module.new(engine, <<~WAT)
+          (module
+            (import "env" "mem" (memory 1 2 shared))
+            (func (export "load") (param i32) (result i32)
+              (i32.atomic.load (local.get 0)))
+            (func (export "store") (param i32 i32)
+              (i32.atomic.store (local.get 0) (local.get 1))))
+        WAT
+      end
+
+      def instantiate
+        store = Store.new(engine)
+        linker = Linker.new(engine)
+        linker.define(store, "env", "mem", memory)
+        linker.instantiate(store, wasm_module)
+      end
+
+      it "is imported as a shared memory type" do
+        expect(wasm_module.imports.first["type"].to_memory_type).to be_shared
+      end
+
+      it "is shared across stores" do
+        first = instantiate
+        second = instantiate
+
+        first.invoke("store", 8, 42)
+        expect(second.invoke("load", 8)).to eq(42)
+        expect(memory.read(8, 4)).to eq([42].pack("l<"))
+      end
+
+      it "is exported as a SharedMemory" do
+        mod = Module.new(engine, <<~WAT)
+          (module (memory (export "mem") 1 1 shared))
+        WAT
+        instance = Instance.new(Store.new(engine), mod)
+
+        expect(instance.export("mem").to_shared_memory).to be_instance_of(SharedMemory)
+      end
+    end
+
+    describe "#atomic_wait32, #atomic_notify" do
+      it "returns :mismatch when the value differs" do
+        expect(memory.atomic_wait32(0, 1)).to eq(:mismatch)
+      end
+
+      it "times out" do
+        expect(memory.atomic_wait32(0, 0, timeout: 0.01)).to eq(:timed_out)
+        expect(memory.atomic_wait64(0, 0, timeout: 0.01)).to eq(:timed_out)
+      end
+
+      it "wakes up waiters from another thread" do
+        waiter = Thread.new { memory.atomic_wait32(4, 0, timeout: 10) }
+        Thread.pass while memory.atomic_notify(4, 1).zero? && waiter.alive?
+        expect(waiter.value).to eq(:ok)
+      end
+
+      it "can be interrupted while waiting without a timeout" do
+        waiter = Thread.new { memory.atomic_wait32(0, 0) }
+        Thread.pass until waiter.status == "sleep"
+        waiter.raise(Interrupt)
+
+        expect { waiter.join }.to raise_error(Interrupt)
+      end
+
+      it "accepts negative expected values" do
+        memory.write(0, "\xFF\xFF\xFF\xFF")
+        expect(memory.atomic_wait32(0, -1, timeout: 0)).to eq(:timed_out)
+      end
+
+      it "raises on unaligned addresses" do
+        expect { memory.atomic_notify(1, 1) }.to raise_error(Wasmtime::Error)
+        expect { memory.atomic_wait64(4, 0, timeout: 0) }.to raise_error(Wasmtime::Error)
+      end
+    end
+  end
+end
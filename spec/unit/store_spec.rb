@@ -14,6 +14,10 @@ module Wasmtime
         expect(store.data).to equal(data)
       end
 
+      it "rejects an unknown v128 format" do
+        expect { Store.new(engine, v128: :float) }.to raise_error(ArgumentError, /:v128/)
+      end
+
       it "can be gc compacted" do
         data = {foo: "bar"}
         10.times { data[:baz] = SecureRandom.hex(1024) }
@@ -145,5 +149,136 @@ module Wasmtime
         end
       end
     end
+
+    describe "#call_hook" do
+      let(:mod) do
+        Module.new(engine, <<~WAT)
+          (module
+            (import "" "host" (func $host))
+            (func (export "run")
+              call $host))
+        WAT
+      end
+
+      it "yields each host/wasm transition" do
+        kinds = []
+        store.call_hook { |kind| kinds << kind }
+        host = Func.new(store, [], []) { kinds << :host_body }
+        Instance.new(store, mod, [host]).invoke("run")
+
+        expect(kinds).to eq([
+          :calling_wasm,
+          :calling_host,
+          :host_body,
+          :returning_from_host,
+          :returning_from_wasm
+        ])
+      end
+
+      it "aborts the call when the block raises" do
+        host = Func.new(store, [], []) {}
+        instance = Instance.new(store, mod, [host])
+        store.call_hook { |kind| raise "denied" if kind == :calling_host }
+
+        expect { instance.invoke("run") }.to raise_error(RuntimeError, "denied")
+      end
+
+      it "can be removed by calling it without a block" do
+        kinds = []
+        host = Func.new(store, [], []) {}
+        instance = Instance.new(store, mod, [host])
+        store.call_hook { |kind| kinds << kind }
+        store.call_hook
+        instance.invoke("run")
+
+        expect(kinds).to be_empty
+      end
+    end
+
+    describe "#resource_limiter=" do
+      let(:limiter_class) do
+        Class.new do
+          attr_reader :calls
+
+          def initialize(allow: true)
+            @allow = allow
+            @calls = []
+          end
+
+          def memory_growing(current, desired, maximum)
+            @calls << [:memory_growing, current, desired, maximum]
+            @allow
+          end
+
+          def table_growing(current, desired, maximum)
+            @calls << [:table_growing, current, desired, maximum]
+            @allow
+          end
+
+          def memory_grow_failed(message)
+            @calls << [:memory_grow_failed, message]
+          end
+        end
+      end
+
+      it "consults the limiter on memory growth" do
+        limiter = limiter_class.new
+        store.resource_limiter = limiter
+        mem = Memory.new(store, min_size: 1)
+        mem.grow(1)
+
+        expect(limiter.calls).to include([:memory_growing, 65536, 131072, nil])
+        expect(store.resource_limiter).to equal(limiter)
+      end
+
+      it "consults the limiter on table growth" do
+        limiter = limiter_class.new
+        store.resource_limiter = limiter
+        table = Table.new(store, :funcref, nil, min_size: 1)
+        table.grow(2, nil)
+
+        expect(limiter.calls).to include([:table_growing, 1, 3, nil])
+      end
+
+      it "denies growth when the limiter returns false" do
+        mem = Memory.new(store, min_size: 0)
+        store.resource_limiter = limiter_class.new(allow: false)
+
+        expect { mem.grow(1) }.to raise_error(Wasmtime::Error, /failed to grow memory/)
+        expect(store.linear_memory_limit_hit?).to be true
+      end
+
+      it "is not consulted when static limits deny the growth" do
+        store = Store.new(engine, limits: {memory_size: 65536})
+        limiter = limiter_class.new
+        store.resource_limiter = limiter
+        mem = Memory.new(store, min_size: 1)
+
+        expect { mem.grow(1) }.to raise_error(Wasmtime::Error)
+        expect(limiter.calls.map(&:first)).not_to include(:memory_growing)
+      end
+
+      it "bubbles exceptions raised by the limiter" do
+        limiter = limiter_class.new
+        def limiter.memory_growing(*) = raise("quota service down")
+        mem = Memory.new(store, min_size: 0)
+        store.resource_limiter = limiter
+
+        expect { mem.grow(1) }.to raise_error(RuntimeError, "quota service down")
+      end
+
+      it "rejects objects not implementing the limiter interface" do
+        expect { store.resource_limiter = Object.new }
+          .to raise_error(ArgumentError, /must respond to #memory_growing/)
+      end
+
+      it "can be removed" do
+        store.resource_limiter = limiter_class.new(allow: false)
+        store.resource_limiter = nil
+        mem = Memory.new(store, min_size: 0)
+
+        expect(mem.grow(1)).to eq(0)
+      end
+    end
   end
 end
@@ -0,0 +1,100 @@
+require "spec_helper"
+
+module Wasmtime
+  RSpec.describe StructRef do
+    let(:engine) { Engine.new(wasm_gc: true, wasm_function_references: true) }
+    let(:type) { StructType.new(engine, [:i32, [:mut, :i8], [:mut, :anyref]]) }
+
+    describe StructType do
+      it "exposes its fields" do
+        expect(type.fields).to eq([:i32, [:mut, :i8], [:mut, :anyref]])
+      end
+
+      it "rejects invalid field types" do
+        expect { StructType.new(engine, [:nope]) }.to raise_error(ArgumentError)
+        expect { StructType.new(engine, [[:const, :i32]]) }.to raise_error(Wasmtime::Error)
+      end
+    end
+
+    describe ".new" do
+      it "allocates a struct" do
+        struct = StructRef.new(store, type, [1, 2, nil])
+        expect(struct.size).to eq(3)
+        expect(struct.to_a).to eq([1, 2, nil])
+        expect(struct.type.fields).to eq(type.fields)
+      end
+
+      it "raises on the wrong number of fields" do
+        expect { StructRef.new(store, type, [1]) }
+          .to raise_error(Wasmtime::Error, "wrong number of fields (given 1, expected 3)")
+      end
+
+      it "raises for async stores" do
+        engine = Engine.new(wasm_gc: true, wasm_function_references: true, async_support: true)
+        type = StructType.new(engine, [:i32])
+        expect { StructRef.new(Store.new(engine), type, [1]) }
+          .to raise_error(Wasmtime::Error, /async support/)
+      end
+    end
+
+    describe "#get, #set" do
+      let(:struct) { StructRef.new(store, type, [1, 2, nil]) }
+
+      it "reads and writes mutable fields" do
+        struct[1] = 0x1FF
+        expect(struct[1]).to eq(0xFF)
+
+        inner = StructRef.new(store, type, [4, 5, nil])
+        struct.set(2, inner)
+        expect(struct.get(2).to_a).to eq([4, 5, nil])
+      end
+
+      it "raises on immutable fields" do
+        expect { struct[0] = 2 }.to raise_error(Wasmtime::Error)
+      end
+
+      it "raises when out of bounds" do
+        expect { struct[3] }.to raise_error(Wasmtime::Error)
+        expect { struct[3] = 1 }.to raise_error(Wasmtime::Error, "field index 3 out of bounds")
+      end
+    end
+
+    describe "with Wasm" do
+      let(:instance) do
+        compile(<<~WAT)
+          (module
+            (type $point (struct (field $x (mut i32)) (field $y (mut i32))))
+            (func (export "make") (param i32 i32) (result (ref $point))
+              (struct.new $point (local.get 0) (local.get 1)))
+            (func (export "sum") (param (ref $point)) (result i32)
+              (i32.add
+                (struct.get $point $x (local.get 0))
+                (struct.get $point $y (local.get 0))))
+            (func (export "is_struct") (param anyref) (result i32)
+              (ref.test (ref struct) (local.get 0))))
+        WAT
+      end
+
+      it "returns structs to Ruby" do
+        struct = instance.invoke("make", 1, 2)
+        expect(struct).to be_instance_of(StructRef)
+        expect(struct.to_a).to eq([1, 2])
+        expect(struct.type.fields).to eq([[:mut, :i32], [:mut, :i32]])
+      end
+
+      it "passes structs back to Wasm" do
+        struct = instance.invoke("make", 1, 2)
+        struct[0] = 40
+        expect(instance.invoke("sum", struct)).to eq(42)
+        expect(instance.invoke("is_struct", struct)).to eq(1)
+        expect(instance.invoke("is_struct", 1)).to eq(0)
+      end
+
+      it "raises when passed to another store" do
+        struct = StructRef.new(Store.new(engine), type, [1, 2, nil])
+        expect { instance.invoke("is_struct", struct) }
+          .to raise_error(Wasmtime::Error, "struct belongs to a different store")
+      end
+    end
+  end
+end
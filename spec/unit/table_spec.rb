@@ -12,6 +12,24 @@ module Wasmtime
         table = Table.new(store, :funcref, noop_func, min_size: 1)
         expect(table).to be_instance_of(Wasmtime::Table)
       end
+
+      it "creates a 64-bit table" do
+        store = Store.new(Engine.new(wasm_memory64: true))
+        table = Table.new(store, :funcref, nil, min_size: 1, table64: true)
+        expect(table.size).to eq(1)
+      end
+    end
+
+    describe TableType do
+      it "exposes whether the table is 64-bit" do
+        engine = Engine.new(wasm_memory64: true)
+        types = Module.new(engine, <<~WAT).imports.map { |import| import["type"].to_table_type }
+          (module
+            (import "" "t32" (table 1 funcref))
+            (import "" "t64" (table i64 1 funcref)))
+        WAT
+        expect(types.map(&:is_64?)).to eq([false, true])
+      end
     end
 
     describe "#type" do
@@ -104,6 +122,99 @@ module Wasmtime
       end
     end
 
+    describe "#fill" do
+      it "sets a range of entries" do
+        table = Table.new(store, :funcref, nil, min_size: 4)
+        table.fill(1, noop_func, 2)
+        expect(table.to_a.map(&:class)).to eq([NilClass, Func, Func, NilClass])
+      end
+
+      it "raises when out of bounds" do
+        table = Table.new(store, :funcref, nil, min_size: 1)
+        expect { table.fill(0, nil, 2) }.to raise_error(Wasmtime::Error)
+      end
+    end
+
+    describe ".copy" do
+      it "copies entries between tables" do
+        src = Table.new(store, :funcref, noop_func, min_size: 2)
+        dst = Table.new(store, :funcref, nil, min_size: 3)
+        Table.copy(dst, 1, src, 0, 2)
+        expect(dst.to_a.map(&:class)).to eq([NilClass, Func, Func])
+      end
+
+      it "copies within a table" do
+        table = Table.new(store, :externref, nil, min_size: 3)
+        table.set(0, "a")
+        table.set(1, "b")
+        Table.copy(table, 1, table, 0, 2)
+        expect(table.to_a).to eq(["a", "a", "b"])
+      end
+
+      it "raises when out of bounds" do
+        src = Table.new(store, :funcref, nil, min_size: 1)
+        dst = Table.new(store, :funcref, nil, min_size: 1)
+        expect { Table.copy(dst, 0, src, 0, 2) }.to raise_error(Wasmtime::Error)
+      end
+
+      it "raises on element type mismatch" do
+        src = Table.new(store, :externref, nil, min_size: 1)
+        dst = Table.new(store, :funcref, nil, min_size: 1)
+        expect { Table.copy(dst, 0, src, 0, 1) }.to raise_error(Wasmtime::Error, /type mismatch/)
+      end
+
+      it "raises when tables belong to different stores" do
+        src = Table.new(Store.new(engine), :funcref, nil, min_size: 1)
+        dst = Table.new(store, :funcref, nil, min_size: 1)
+        expect { Table.copy(dst, 0, src, 0, 1) }
+          .to raise_error(Wasmtime::Error, "tables must belong to the same store")
+      end
+    end
+
+    describe "#to_a" do
+      it "returns all elements" do
+        table = Table.new(store, :externref, 1, min_size: 2)
+        expect(table.to_a).to eq([1, 1])
+      end
+    end
+
+    describe "typed function references" do
+      let(:engine) { Engine.new(wasm_function_references: true) }
+      let(:instance) do
+        Instance.new(store, Module.new(engine, <<~WAT))
+          (module
+            (type $i (func (result i32)))
+            (type $v (func))
+            (func $f (export "f") (type $i) (i32.const 1))
+            (func (export "g") (type $v))
+            (table (export "nullable") 2 (ref null $i))
+            (table (export "non_null") 2 (ref $i) (ref.func $f)))
+        WAT
+      end
+      let(:f) { instance.export("f").to_func }
+      let(:g) { instance.export("g").to_func }
+
+      it "accepts functions of the element type" do
+        table = instance.export("nullable").to_table
+        table.set(0, f)
+        table.fill(1, f, 1)
+        expect(table.to_a.map { |func| func.call }).to eq([1, 1])
+      end
+
+      it "rejects functions of another type" do
+        table = instance.export("nullable").to_table
+        expect { table.set(0, g) }.to raise_error(Wasmtime::Error, /type mismatch/)
+        expect { table.fill(0, g, 1) }.to raise_error(Wasmtime::Error, /type mismatch/)
+        expect { table.grow(1, g) }.to raise_error(Wasmtime::Error, /type mismatch/)
+      end
+
+      it "rejects nil for non-nullable references" do
+        table = instance.export("non_null").to_table
+        expect { table.set(0, nil) }.to raise_error(Wasmtime::Error, /type mismatch/)
+        expect(table.get(0).call).to eq(1)
+      end
+    end
+
     it "keeps externrefs alive" do
       table = Table.new(store, :externref, +"foo", min_size: 2)
       generate_new_objects
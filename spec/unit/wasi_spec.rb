@@ -71,6 +71,22 @@ module Wasmtime
       end
     end
 
+    describe "Component::WasiCommand#call_run" do
+      it "runs without the GVL when gvl: false" do
+        linker = Component::Linker.new(@engine)
+        WASI::P2.add_to_linker_sync(linker)
+        stdout_str = ""
+        wasi_config = WasiConfig.new
+          .set_stdin_string("some str")
+          .set_stdout_buffer(stdout_str, 40000)
+        store = Store.new(@engine, wasi_config: wasi_config)
+
+        Component::WasiCommand.new(store, wasi_component, linker).call_run(store, gvl: false)
+
+        expect(JSON.parse(stdout_str).fetch("name")).to eq("stdout")
+      end
+    end
+
     shared_examples WasiConfig do
       it "writes std streams to files" do
         File.write(tempfile_path("stdin"), "stdin content")
@@ -17,5 +17,156 @@ module Wasmtime
         expect { Wasmtime.wat2wasm("not wat") }.to raise_error(Wasmtime::Error)
       end
     end
+
+    describe ".preinitialize" do
+      let(:wat) do
+        <<~WAT
+          (module
+            (memory (export "memory") 1 4)
+            (global $counter (export "counter") (mut i32) (i32.const 0))
+            (global $ratio (export "ratio") (mut f64) (f64.const 0))
+            (global (export "answer") i32 (i32.const 42))
+            (data (i32.const 0) "before")
+            (func (export "wizer.initialize")
+              (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+              (global.set $ratio (f64.const 1.5))
+              (i32.store8 (i32.const 0) (i32.const 0x61))
+              (i64.store (i32.const 1000) (i64.const 0x0102030405060708))
+              (drop (memory.grow (i32.const 1))))
+            (func (export "counter_value") (result i32) (global.get $counter)))
+        WAT
+      end
+
+      def instantiate(wasm)
+        Instance.new(Store.new(engine), Module.new(engine, wasm))
+      end
+
+      it "returns a binary string" do
+        wasm = Wasmtime.preinitialize(engine, wat)
+        expect(wasm.encoding).to eq(Encoding::ASCII_8BIT)
+        expect(wasm).to start_with("\x00asm")
+      end
+
+      it "reproduces the state after initialization" do
+        expected = instantiate(wat)
+        expected.invoke("wizer.initialize")
+        instance = instantiate(Wasmtime.preinitialize(engine, wat))
+
+        expect(instance.invoke("counter_value")).to eq(1)
+        %w[counter ratio answer].each do |name|
+          expect(instance.export(name).to_global.get).to eq(expected.export(name).to_global.get)
+        end
+
+        memory = instance.export("memory").to_memory
+        expected_memory = expected.export("memory").to_memory
+        expect(memory.size).to eq(2)
+        expect(memory.read(0, memory.data_size)).to eq(expected_memory.read(0, expected_memory.data_size))
+        expect(memory.read(0, 6)).to eq("aefore")
+      end
+
+      it "removes the init function export" do
+        instance = instantiate(Wasmtime.preinitialize(engine, wat))
+        expect(instance.export("wizer.initialize")).to be_nil
+      end
+
+      it "can be precompiled" do
+        serialized = engine.precompile_module(Wasmtime.preinitialize(engine, wat))
+        instance = Instance.new(Store.new(engine), Module.deserialize(engine, serialized))
+        expect(instance.invoke("counter_value")).to eq(1)
+      end
+
+      it "does not run the start function again" do
+        wasm = Wasmtime.preinitialize(engine, <<~WAT, init_func: "init")
+          (module
+            (global $count (mut i32) (i32.const 0))
+            (func $start (global.set $count (i32.add (global.get $count) (i32.const 1))))
+            (start $start)
+            (func (export "init"))
+            (func (export "count") (result i32) (global.get $count)))
+        WAT
+
+        expect(instantiate(wasm).invoke("count")).to eq(1)
+      end
+
+      it "adds data segments to modules without any" do
+        wasm = Wasmtime.preinitialize(engine, <<~WAT, init_func: "init")
+          (module
+            (memory (export "memory") 1)
+            (func (export "init") (i32.store (i32.const 8) (i32.const 0x2a))))
+        WAT
+
+        expect(instantiate(wasm).export("memory").to_memory.read(8, 1)).to eq("*")
+      end
+
+      it "stays within the data segment limit for fragmented memories" do
+        wat = <<~WAT
+          (module
+            (memory (export "memory") 128)
+            (func (export "init")
+              (local $i i32)
+              (loop $loop
+                (i32.store8 (local.get $i) (i32.const 1))
+                (local.set $i (i32.add (local.get $i) (i32.const 32)))
+                (br_if $loop (i32.lt_u (local.get $i) (i32.const 0x800000))))))
+        WAT
+        expected = instantiate(wat)
+        expected.invoke("init")
+        expected_memory = expected.export("memory").to_memory
+
+        memory = instantiate(Wasmtime.preinitialize(engine, wat, init_func: "init")).export("memory").to_memory
+        expect(memory.read(0, memory.data_size)).to eq(expected_memory.read(0, expected_memory.data_size))
+      end
+
+      it "snapshots null reference globals" do
+        wasm = Wasmtime.preinitialize(engine, <<~WAT, init_func: "init")
+          (module
+            (func $f)
+            (elem declare func $f)
+            (global $ref (export "ref") (mut funcref) (ref.func $f))
+            (func (export "init") (global.set $ref (ref.null func))))
+        WAT
+
+        expect(instantiate(wasm).export("ref").to_global.get).to be_nil
+      end
+
+      it "raises on mutable globals holding non-null references" do
+        wat = <<~WAT
+          (module
+            (func $f)
+            (elem declare func $f)
+            (global $ref (mut funcref) (ref.null func))
+            (func (export "init") (global.set $ref (ref.func $f))))
+        WAT
+
+        expect { Wasmtime.preinitialize(engine, wat, init_func: "init") }
+          .to raise_error(Wasmtime::Error, /cannot snapshot global 0: it holds a non-null reference/)
+      end
+
+      it "uses the given linker and store" do
+        linker = Linker.new(engine)
+        linker.func_new("env", "seed", [], [:i32]) { 7 }
+        store = Store.new(engine)
+        wasm = Wasmtime.preinitialize(engine, <<~WAT, init_func: "init", linker: linker, store: store)
+          (module
+            (import "env" "seed" (func $seed (result i32)))
+            (global $value (mut i32) (i32.const 0))
+            (func (export "init") (global.set $value (call $seed)))
+            (func (export "value") (result i32) (global.get $value)))
+        WAT
+
+        instance = linker.instantiate(Store.new(engine), Module.new(engine, wasm))
+        expect(instance.invoke("value")).to eq(7)
+      end
+
+      it "raises when the init function is missing" do
+        expect { Wasmtime.preinitialize(engine, "(module)") }
+          .to raise_error(Wasmtime::Error, /wizer\.initialize/)
+      end
+
+      it "raises on components" do
+        expect { Wasmtime.preinitialize(engine, "(component)") }
+          .to raise_error(Wasmtime::Error, /expected a core Wasm module/)
+      end
+    end
   end
 end